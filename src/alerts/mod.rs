@@ -0,0 +1,397 @@
+// Threshold alerts - rules come from `[[alerts]]` in the config
+use anyhow::{bail, Result};
+use std::collections::VecDeque;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::config::{AlertMetric, AlertRule, WidgetType};
+use crate::data::{RefreshScheduler, Snapshot};
+use crate::widgets::percentage;
+
+// Older entries are dropped from the alert log
+const LOG_SIZE: usize = 50;
+
+/// An alert firing or clearing
+pub struct AlertEvent {
+    pub timestamp: i64,
+    pub rule: String,
+    pub value: f64,
+    pub fired: bool,
+    /// Why the rule's command didn't start, when stderr isn't ours to write to
+    pub hook_error: Option<String>,
+}
+
+impl AlertEvent {
+    pub fn message(&self) -> String {
+        let time = chrono::DateTime::from_timestamp(self.timestamp, 0)
+            .map(|time| {
+                time.with_timezone(&chrono::Local)
+                    .format("%H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        let state = if self.fired { "FIRED" } else { "cleared" };
+        let mut message = format!("{} {} {} (now {:.1})", time, state, self.rule, self.value);
+        if let Some(error) = &self.hook_error {
+            message.push_str(&format!(" - {}", error));
+        }
+        message
+    }
+}
+
+struct RuleState {
+    rule: AlertRule,
+    label: String,
+    /// Snapshot time the value first went over the threshold
+    above_since: Option<i64>,
+    firing: bool,
+}
+
+/// Tracks every rule across snapshots and keeps a log of what fired and cleared
+pub struct AlertMonitor {
+    rules: Vec<RuleState>,
+    log: VecDeque<AlertEvent>,
+    // Off while the TUI owns the terminal, the alert log says it all there
+    stderr: bool,
+}
+
+impl AlertMonitor {
+    pub fn new(rules: &[AlertRule]) -> Result<Self> {
+        for rule in rules {
+            if rule.mount.is_some() && rule.metric != AlertMetric::Disk {
+                bail!(
+                    "Alert on `{}` sets `mount`, which only applies to `disk` alerts",
+                    rule.metric.as_str()
+                );
+            }
+        }
+
+        Ok(Self {
+            rules: rules
+                .iter()
+                .map(|rule| RuleState {
+                    label: describe(rule),
+                    rule: rule.clone(),
+                    above_since: None,
+                    firing: false,
+                })
+                .collect(),
+            log: VecDeque::new(),
+            stderr: true,
+        })
+    }
+
+    /// Keep everything in the alert log instead of writing to stderr, for the TUI
+    pub fn on_screen(mut self) -> Self {
+        self.stderr = false;
+        self
+    }
+
+    /// Keep tracking the rules but never run their commands, for data from elsewhere or from the past
+    pub fn without_hooks(mut self) -> Self {
+        for state in &mut self.rules {
+            state.rule.command = None;
+            state.rule.stderr = false;
+        }
+        self
+    }
+
+    /// Evaluate every rule against a new snapshot, running hooks for alerts that change state
+    pub fn check(&mut self, snapshot: &Snapshot) {
+        for state in &mut self.rules {
+            // Nothing to compare against, e.g. the watched mount is gone
+            let Some(value) = current_value(&state.rule, snapshot) else {
+                continue;
+            };
+
+            let changed = if value > state.rule.above {
+                let since = *state.above_since.get_or_insert(snapshot.timestamp);
+                let fire =
+                    !state.firing && snapshot.timestamp - since >= state.rule.for_secs as i64;
+                state.firing |= fire;
+                fire
+            } else {
+                state.above_since = None;
+                std::mem::replace(&mut state.firing, false)
+            };
+
+            if changed {
+                let mut event = AlertEvent {
+                    timestamp: snapshot.timestamp,
+                    rule: state.label.clone(),
+                    value,
+                    fired: state.firing,
+                    hook_error: None,
+                };
+                if self.stderr && state.rule.stderr {
+                    eprintln!("rust-conky alert: {}", event.message());
+                }
+                if let Err(error) = run_hook(&state.rule, &event) {
+                    if self.stderr {
+                        eprintln!("rust-conky: {}", error);
+                    } else {
+                        event.hook_error = Some(error);
+                    }
+                }
+                if self.log.len() == LOG_SIZE {
+                    self.log.pop_front();
+                }
+                self.log.push_back(event);
+            }
+        }
+    }
+
+    /// Whether a firing alert is about a metric this widget shows
+    pub fn is_alerting(&self, widget_type: WidgetType) -> bool {
+        self.rules
+            .iter()
+            .any(|state| state.firing && state.rule.metric.widgets().contains(&widget_type))
+    }
+
+    /// Fired and cleared alerts, oldest first
+    pub fn log(&self) -> &VecDeque<AlertEvent> {
+        &self.log
+    }
+}
+
+/// Refresh what the rules watch, so an alert works without a widget showing its metric
+pub fn schedule(rules: &[AlertRule], scheduler: &mut RefreshScheduler, update_interval: u64) {
+    let interval = Duration::from_secs(update_interval.max(1));
+    for rule in rules {
+        for source in rule.metric.sources() {
+            scheduler.every(*source, interval);
+        }
+    }
+}
+
+/// Rule as shown in the log, e.g. `disk "/" > 95% for 30s`
+fn describe(rule: &AlertRule) -> String {
+    let mut label = rule.metric.as_str().to_string();
+    if let Some(mount) = &rule.mount {
+        label.push_str(&format!(" {:?}", mount));
+    }
+    label.push_str(&format!(" > {}", rule.above));
+    if rule.metric != AlertMetric::Load {
+        label.push('%');
+    }
+    if rule.for_secs > 0 {
+        label.push_str(&format!(" for {}s", rule.for_secs));
+    }
+    label
+}
+
+fn current_value(rule: &AlertRule, snapshot: &Snapshot) -> Option<f64> {
+    let memory = &snapshot.memory;
+    match rule.metric {
+        AlertMetric::Cpu => Some(snapshot.cpu.usage as f64),
+        AlertMetric::Memory => Some(percentage(memory.used, memory.total)),
+        AlertMetric::Swap => Some(percentage(memory.used_swap, memory.total_swap)),
+        AlertMetric::Load => Some(snapshot.cpu.load_average.one),
+        // Without a mount point, watch the fullest disk
+        AlertMetric::Disk => snapshot
+            .disks
+            .iter()
+            .filter(|disk| {
+                rule.mount
+                    .as_ref()
+                    .is_none_or(|mount| *mount == disk.mount_point)
+            })
+            .map(|disk| percentage(disk.total.saturating_sub(disk.available), disk.total))
+            .reduce(f64::max),
+    }
+}
+
+/// Start the rule's command, if it has one
+fn run_hook(rule: &AlertRule, event: &AlertEvent) -> Result<(), String> {
+    let Some(command) = &rule.command else {
+        return Ok(());
+    };
+
+    // Output is discarded so hooks can't scribble over the display
+    let spawned = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("CONKY_ALERT", &event.rule)
+        .env(
+            "CONKY_ALERT_STATE",
+            if event.fired { "fired" } else { "cleared" },
+        )
+        .env("CONKY_ALERT_VALUE", format!("{:.1}", event.value))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+
+    match spawned {
+        // Reap the hook in the background so slow commands don't stall refreshes
+        Ok(mut child) => {
+            std::thread::spawn(move || child.wait());
+            Ok(())
+        }
+        Err(error) => Err(format!("alert command `{}` failed: {}", command, error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::WidgetConfig;
+    use crate::data::{fake, DataSource};
+    use crate::widgets::WidgetGrid;
+
+    fn rule(metric: AlertMetric, above: f64, for_secs: u64) -> AlertRule {
+        AlertRule {
+            metric,
+            above,
+            mount: None,
+            for_secs,
+            command: None,
+            stderr: false,
+        }
+    }
+
+    fn cpu_at(timestamp: i64, usage: f32) -> Snapshot {
+        let mut snapshot = fake::snapshot(0);
+        snapshot.timestamp = timestamp;
+        snapshot.cpu.usage = usage;
+        snapshot
+    }
+
+    fn states(monitor: &AlertMonitor) -> Vec<bool> {
+        monitor.log().iter().map(|event| event.fired).collect()
+    }
+
+    #[test]
+    fn fires_and_clears_on_threshold() {
+        let mut monitor = AlertMonitor::new(&[rule(AlertMetric::Cpu, 90.0, 0)]).unwrap();
+
+        monitor.check(&cpu_at(0, 50.0));
+        assert!(monitor.log().is_empty());
+        monitor.check(&cpu_at(1, 95.0));
+        assert!(monitor.is_alerting(WidgetType::Cpu));
+        assert!(monitor.is_alerting(WidgetType::CpuGraph));
+        assert!(!monitor.is_alerting(WidgetType::Memory));
+        // Staying above doesn't fire again
+        monitor.check(&cpu_at(2, 99.0));
+        monitor.check(&cpu_at(3, 40.0));
+        assert!(!monitor.is_alerting(WidgetType::Cpu));
+
+        assert_eq!(states(&monitor), [true, false]);
+        let fired = &monitor.log()[0];
+        assert_eq!(fired.rule, "cpu > 90%");
+        assert_eq!((fired.timestamp, fired.value), (1, 95.0));
+    }
+
+    #[test]
+    fn waits_for_the_hold_time() {
+        let mut monitor = AlertMonitor::new(&[rule(AlertMetric::Cpu, 90.0, 30)]).unwrap();
+
+        monitor.check(&cpu_at(0, 95.0));
+        monitor.check(&cpu_at(20, 95.0));
+        assert!(monitor.log().is_empty());
+        // Dipping below starts the wait over
+        monitor.check(&cpu_at(25, 50.0));
+        monitor.check(&cpu_at(30, 95.0));
+        monitor.check(&cpu_at(59, 95.0));
+        assert!(monitor.log().is_empty());
+        monitor.check(&cpu_at(60, 95.0));
+        assert_eq!(states(&monitor), [true]);
+        assert_eq!(monitor.log()[0].rule, "cpu > 90% for 30s");
+    }
+
+    #[test]
+    fn watches_one_mount_or_the_fullest() {
+        let mut snapshot = fake::snapshot(0);
+        let mut root = rule(AlertMetric::Disk, 50.0, 0);
+        root.mount = Some("/".to_string());
+        let mut monitor = AlertMonitor::new(&[root, rule(AlertMetric::Disk, 65.0, 0)]).unwrap();
+
+        // `/` is 60% full, the USB stick 6%
+        monitor.check(&snapshot);
+        assert_eq!(states(&monitor), [true]);
+        assert_eq!(monitor.log()[0].rule, "disk \"/\" > 50%");
+
+        // A rule whose mount went away is left alone
+        snapshot.disks.clear();
+        monitor.check(&snapshot);
+        assert_eq!(states(&monitor), [true]);
+
+        let mut cpu_mount = rule(AlertMetric::Cpu, 50.0, 0);
+        cpu_mount.mount = Some("/".to_string());
+        assert!(AlertMonitor::new(&[cpu_mount]).is_err());
+    }
+
+    #[test]
+    fn runs_the_hook() {
+        let output = std::env::temp_dir().join(format!("rust-conky-hook-{}", std::process::id()));
+        let _ = std::fs::remove_file(&output);
+        let mut hooked = rule(AlertMetric::Cpu, 90.0, 0);
+        hooked.command = Some(format!(
+            "printf '%s|%s|%s' \"$CONKY_ALERT\" \"$CONKY_ALERT_STATE\" \"$CONKY_ALERT_VALUE\" > {}.tmp && mv {0}.tmp {0}",
+            output.display()
+        ));
+        let mut monitor = AlertMonitor::new(&[hooked]).unwrap();
+        monitor.check(&cpu_at(0, 95.0));
+
+        // The hook runs in the background
+        let mut written = None;
+        for _ in 0..100 {
+            if let Ok(content) = std::fs::read_to_string(&output) {
+                written = Some(content);
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        let _ = std::fs::remove_file(&output);
+        assert_eq!(written.as_deref(), Some("cpu > 90%|fired|95.0"));
+    }
+
+    #[test]
+    fn fires_without_hooks() {
+        let mut hooked = rule(AlertMetric::Cpu, 90.0, 0);
+        hooked.command = Some("exit 1".to_string());
+        hooked.stderr = true;
+        let mut monitor = AlertMonitor::new(&[hooked]).unwrap().without_hooks();
+        assert!(monitor
+            .rules
+            .iter()
+            .all(|state| state.rule.command.is_none() && !state.rule.stderr));
+
+        // Still fires and highlights, just nothing runs
+        monitor.check(&cpu_at(0, 95.0));
+        assert_eq!(monitor.log().len(), 1);
+        assert!(monitor.is_alerting(WidgetType::Cpu));
+    }
+
+    #[test]
+    fn schedules_sources_no_widget_uses() {
+        let grid = WidgetGrid::from_config(&[WidgetConfig {
+            widget_type: WidgetType::Cpu,
+            position: [0, 0],
+            update_interval: Some(2),
+        }])
+        .unwrap();
+        let mut scheduler = grid.scheduler(5);
+        assert_eq!(scheduler.interval(DataSource::Memory), None);
+
+        let rules = [
+            rule(AlertMetric::Swap, 0.0, 0),
+            rule(AlertMetric::Disk, 95.0, 0),
+            rule(AlertMetric::Cpu, 90.0, 0),
+        ];
+        schedule(&rules, &mut scheduler, 5);
+        assert_eq!(
+            scheduler.interval(DataSource::Memory),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(
+            scheduler.interval(DataSource::Disks),
+            Some(Duration::from_secs(5))
+        );
+        // A widget refreshing faster keeps its interval
+        assert_eq!(
+            scheduler.interval(DataSource::Cpu),
+            Some(Duration::from_secs(2))
+        );
+    }
+}
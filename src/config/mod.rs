@@ -1,21 +1,393 @@
+use anyhow::Context;
 use serde::Deserialize;
 use std::fs;
+use std::time::Duration;
+
+use crate::data::DataSource;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
     pub update_interval: u64,
+    /// How far back the graph widgets reach
+    #[serde(default = "default_history_minutes")]
+    pub history_minutes: u64,
+    #[serde(default)]
+    pub widgets: Vec<WidgetConfig>,
+    #[serde(default)]
+    pub alerts: Vec<AlertRule>,
+    #[serde(default)]
+    pub json: JsonConfig,
+    #[serde(default)]
+    pub disks: DiskConfig,
+    #[serde(default)]
+    pub systemd: SystemdConfig,
+    /// conky style TEXT template, replaces the widget grid in terminal mode
+    pub template: Option<String>,
+}
+
+fn default_history_minutes() -> u64 {
+    5
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            update_interval: 1,
+            history_minutes: default_history_minutes(),
+            widgets: Vec::new(),
+            alerts: Vec::new(),
+            json: JsonConfig::default(),
+            disks: DiskConfig::default(),
+            systemd: SystemdConfig::default(),
+            template: None,
+        }
+    }
+}
+
+/// The `[json]` section, for `--json` output
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct JsonConfig {
+    /// Indented output instead of one object per line
+    pub pretty: bool,
+    /// How many of the busiest processes to list
+    pub processes: usize,
+    /// Fields to keep, as dotted paths like `cpu.usage`; everything when empty
+    pub include: Vec<String>,
+    /// Fields to drop, applied after `include`
+    pub exclude: Vec<String>,
+}
+
+impl Default for JsonConfig {
+    fn default() -> Self {
+        Self {
+            pretty: false,
+            processes: 5,
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+}
+
+/// The `[disks]` section: which mounts show up anywhere
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct DiskConfig {
+    /// Only mounts matching this are kept; everything when empty
+    pub include: DiskMatch,
+    /// Mounts matching this are dropped, applied after `include`
+    pub exclude: DiskMatch,
+}
+
+impl Default for DiskConfig {
+    fn default() -> Self {
+        Self {
+            include: DiskMatch::default(),
+            // Snap images, container layers and memory backed mounts
+            exclude: DiskMatch {
+                mounts: vec!["/snap/*".to_string()],
+                fs_types: ["squashfs", "overlay", "tmpfs", "devtmpfs"]
+                    .map(String::from)
+                    .to_vec(),
+                devices: Vec::new(),
+            },
+        }
+    }
+}
+
+impl DiskConfig {
+    pub fn allows(&self, mount_point: &str, fs_type: &str, device: &str) -> bool {
+        (self.include.is_empty() || self.include.matches(mount_point, fs_type, device))
+            && !self.exclude.matches(mount_point, fs_type, device)
+    }
+}
+
+/// The `[systemd]` section, for the systemd widget and `units` in the JSON output
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SystemdConfig {
+    /// Unit names, e.g. `nginx.service`; `.service` may be left out
+    pub units: Vec<String>,
+}
+
+/// Globs (`*`, `?`) per field, a mount matches when any of them does
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct DiskMatch {
+    pub mounts: Vec<String>,
+    pub fs_types: Vec<String>,
+    pub devices: Vec<String>,
+}
+
+impl DiskMatch {
+    fn is_empty(&self) -> bool {
+        self.mounts.is_empty() && self.fs_types.is_empty() && self.devices.is_empty()
+    }
+
+    fn matches(&self, mount_point: &str, fs_type: &str, device: &str) -> bool {
+        let any = |patterns: &[String], text: &str| {
+            patterns.iter().any(|pattern| glob_match(pattern, text))
+        };
+        any(&self.mounts, mount_point) || any(&self.fs_types, fs_type) || any(&self.devices, device)
+    }
+}
+
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    // Where the last `*` was and the text position it currently swallows up to
+    let mut star: Option<(usize, usize)> = None;
+    let (mut p, mut t) = (0, 0);
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// One `[[widgets]]` entry
+#[derive(Debug, Deserialize, Clone)]
+pub struct WidgetConfig {
+    pub widget_type: WidgetType,
+    /// Grid cell as `[row, column]`
+    pub position: [u16; 2],
+    /// Refresh interval in seconds for this widget's data, defaults to the global one
+    pub update_interval: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WidgetType {
+    Cpu,
+    Memory,
+    Swap,
+    Disk,
+    Network,
+    Processes,
+    Uptime,
+    Load,
+    CpuGraph,
+    MemoryGraph,
+    NetworkGraph,
+    DiskIoGraph,
+    Battery,
+    Sensors,
+    Cgroups,
+    Systemd,
+}
+
+/// One `[[alerts]]` entry: fires once `metric` has stayed above `above` for `for` seconds
+#[derive(Debug, Deserialize, Clone)]
+pub struct AlertRule {
+    pub metric: AlertMetric,
+    /// Percent used, or the 1 minute load average for `load`
+    pub above: f64,
+    /// Only for `disk`: the mount point to watch, any disk when unset
+    pub mount: Option<String>,
+    /// How long the value must stay above the threshold before the alert fires
+    #[serde(default, rename = "for")]
+    pub for_secs: u64,
+    /// Shell command run when the alert fires or clears
+    pub command: Option<String>,
+    /// Also report the alert on stderr
+    #[serde(default)]
+    pub stderr: bool,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    Cpu,
+    Memory,
+    Swap,
+    Disk,
+    Load,
+}
+
+impl AlertMetric {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AlertMetric::Cpu => "cpu",
+            AlertMetric::Memory => "memory",
+            AlertMetric::Swap => "swap",
+            AlertMetric::Disk => "disk",
+            AlertMetric::Load => "load",
+        }
+    }
+
+    /// What must be refreshed to check this metric, whether or not a widget shows it
+    pub fn sources(self) -> &'static [DataSource] {
+        match self {
+            AlertMetric::Cpu => &[DataSource::Cpu],
+            AlertMetric::Memory | AlertMetric::Swap => &[DataSource::Memory],
+            AlertMetric::Disk => &[DataSource::Disks],
+            // Read live with every snapshot
+            AlertMetric::Load => &[DataSource::Clock],
+        }
+    }
+
+    /// Widgets that show this metric and get highlighted while it alerts
+    pub fn widgets(self) -> &'static [WidgetType] {
+        match self {
+            AlertMetric::Cpu => &[WidgetType::Cpu, WidgetType::CpuGraph],
+            AlertMetric::Memory => &[WidgetType::Memory, WidgetType::MemoryGraph],
+            AlertMetric::Swap => &[WidgetType::Swap, WidgetType::MemoryGraph],
+            AlertMetric::Disk => &[WidgetType::Disk],
+            AlertMetric::Load => &[WidgetType::Load],
+        }
+    }
+}
+
+impl Config {
+    pub fn history_window(&self) -> Duration {
+        Duration::from_secs(self.history_minutes.max(1) * 60)
+    }
+}
+
+/// `--interval` for the commands that sample on their own schedule
+#[derive(Debug, Clone, clap::Args)]
+pub struct IntervalArg {
+    /// Seconds between samples, defaults to update_interval from the config
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub interval: Option<u64>,
+}
+
+impl IntervalArg {
+    /// The interval given, or the config's
+    pub fn seconds(&self, config: &Config) -> u64 {
+        self.interval.unwrap_or(config.update_interval).max(1)
+    }
+}
+
+impl WidgetType {
+    pub const ALL: [WidgetType; 16] = [
+        WidgetType::Cpu,
+        WidgetType::Memory,
+        WidgetType::Swap,
+        WidgetType::Disk,
+        WidgetType::Network,
+        WidgetType::Processes,
+        WidgetType::Uptime,
+        WidgetType::Load,
+        WidgetType::CpuGraph,
+        WidgetType::MemoryGraph,
+        WidgetType::NetworkGraph,
+        WidgetType::DiskIoGraph,
+        WidgetType::Battery,
+        WidgetType::Sensors,
+        WidgetType::Cgroups,
+        WidgetType::Systemd,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WidgetType::Cpu => "cpu",
+            WidgetType::Memory => "memory",
+            WidgetType::Swap => "swap",
+            WidgetType::Disk => "disk",
+            WidgetType::Network => "network",
+            WidgetType::Processes => "processes",
+            WidgetType::Uptime => "uptime",
+            WidgetType::Load => "load",
+            WidgetType::CpuGraph => "cpu_graph",
+            WidgetType::MemoryGraph => "memory_graph",
+            WidgetType::NetworkGraph => "network_graph",
+            WidgetType::DiskIoGraph => "disk_io_graph",
+            WidgetType::Battery => "battery",
+            WidgetType::Sensors => "sensors",
+            WidgetType::Cgroups => "cgroups",
+            WidgetType::Systemd => "systemd",
+        }
+    }
+}
+
+impl std::str::FromStr for WidgetType {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|widget_type| widget_type.as_str() == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL
+                    .iter()
+                    .map(|widget_type| widget_type.as_str())
+                    .collect();
+                format!(
+                    "unknown widget `{}`, expected one of: {}",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
 }
 
 pub async fn load_config(path: &str) -> anyhow::Result<Config> {
     match fs::read_to_string(path) {
         Ok(content) => {
-            let config: Config = toml::from_str(&content)?;
-            println!("📄 Loaded config from: {}", path);
+            let config: Config = toml::from_str(&content)
+                .with_context(|| format!("Invalid config file: {}", path))?;
+            eprintln!("📄 Loaded config from: {}", path);
             Ok(config)
         }
         Err(_) => {
-            println!("⚠️  Config file not found, using defaults");
-            Ok(Config { update_interval: 1 })
+            eprintln!("⚠️  Config file not found, using defaults");
+            Ok(Config::default())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs() {
+        assert!(glob_match("/snap/*", "/snap/core/123"));
+        assert!(glob_match("/dev/sd?", "/dev/sdb"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("/mnt/*/data", "/mnt/usb/data"));
+        assert!(!glob_match("/snap/*", "/home"));
+        assert!(!glob_match("/dev/sd?", "/dev/sdb1"));
+    }
+
+    #[test]
+    fn disk_include_and_exclude() {
+        let defaults = DiskConfig::default();
+        assert!(defaults.allows("/", "ext4", "/dev/nvme0n1p2"));
+        assert!(!defaults.allows("/snap/core/123", "squashfs", "/dev/loop3"));
+        assert!(!defaults.allows("/run", "tmpfs", "tmpfs"));
+
+        let config: DiskConfig = toml::from_str(
+            r#"
+            [include]
+            devices = ["/dev/sd*"]
+            [exclude]
+            mounts = ["/boot*"]
+            "#,
+        )
+        .unwrap();
+        assert!(config.allows("/data", "xfs", "/dev/sda1"));
+        assert!(!config.allows("/boot/efi", "vfat", "/dev/sda2"));
+        assert!(!config.allows("/", "ext4", "/dev/nvme0n1p2"));
+        // Setting the section replaces the default exclusions
+        assert!(config.allows("/dev/shm", "tmpfs", "/dev/sdtmp"));
+    }
+}
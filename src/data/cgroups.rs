@@ -0,0 +1,384 @@
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use super::meminfo;
+use super::snapshot::{CpuData, MemoryData, MemoryPressure};
+use super::sysfs::read_number;
+
+pub const CGROUP_PATH: &str = "/sys/fs/cgroup";
+pub const PROC_PATH: &str = "/proc";
+
+/// Only the unified (v2) hierarchy is supported, its root lists the controllers
+pub fn is_unified(root: &Path) -> bool {
+    root.join("cgroup.controllers").exists()
+}
+
+/// The v2 cgroup of a process, e.g. `/system.slice/nginx.service`; `pid` may be `self`
+pub fn process_cgroup(proc_root: &Path, pid: &str) -> Option<String> {
+    let content = fs::read_to_string(proc_root.join(pid).join("cgroup")).ok()?;
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(|path| path.trim().to_string())
+}
+
+/// Short container ID in a docker, podman, containerd or cri-o cgroup path
+pub fn container_id(path: &str) -> Option<String> {
+    path.rsplit('/').find_map(|part| {
+        // docker-<id>.scope, libpod-<id>.scope, cri-containerd-<id>.scope, or a bare <id>
+        let name = part.strip_suffix(".scope").unwrap_or(part);
+        let id = name.rsplit_once('-').map_or(name, |(_, id)| id);
+        (id.len() == 64 && id.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .then(|| id[..12].to_string())
+    })
+}
+
+/// Cumulative usage of one cgroup, children included
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CgroupCounters {
+    pub cpu_usec: u64,
+    pub read_bytes: u64,
+    pub written_bytes: u64,
+}
+
+impl CgroupCounters {
+    /// Missing files (controller not enabled) count as zero
+    pub fn read(root: &Path, path: &str) -> Self {
+        let dir = cgroup_dir(root, path);
+        let cpu_usec = fs::read_to_string(dir.join("cpu.stat"))
+            .ok()
+            .and_then(|stat| {
+                stat.lines()
+                    .find_map(|line| line.strip_prefix("usage_usec "))
+                    .and_then(|value| value.trim().parse().ok())
+            })
+            .unwrap_or(0);
+
+        // One line per device: `8:0 rbytes=1024 wbytes=512 rios=2 wios=1 ...`
+        let (mut read_bytes, mut written_bytes) = (0, 0);
+        let io = fs::read_to_string(dir.join("io.stat")).unwrap_or_default();
+        for field in io.split_whitespace() {
+            let (key, value) = field.split_once('=').unwrap_or((field, ""));
+            let value: u64 = value.parse().unwrap_or(0);
+            match key {
+                "rbytes" => read_bytes += value,
+                "wbytes" => written_bytes += value,
+                _ => {}
+            }
+        }
+
+        Self {
+            cpu_usec,
+            read_bytes,
+            written_bytes,
+        }
+    }
+
+    /// CPU percent of one core, bytes read and written per second
+    pub fn rates_since(&self, previous: &CgroupCounters, elapsed: f64) -> (f64, f64, f64) {
+        if elapsed <= 0.0 {
+            return (0.0, 0.0, 0.0);
+        }
+        let per_second =
+            |current: u64, previous: u64| current.saturating_sub(previous) as f64 / elapsed;
+        (
+            per_second(self.cpu_usec, previous.cpu_usec) / 10_000.0,
+            per_second(self.read_bytes, previous.read_bytes),
+            per_second(self.written_bytes, previous.written_bytes),
+        )
+    }
+}
+
+/// Current memory use of a cgroup, children included
+pub fn memory_current(root: &Path, path: &str) -> Option<u64> {
+    read_number(&cgroup_dir(root, path), "memory.current")
+}
+
+/// `memory.max` of the cgroup itself, None when unlimited
+pub fn memory_max(root: &Path, path: &str) -> Option<u64> {
+    read_number(&cgroup_dir(root, path), "memory.max")
+}
+
+/// Limits that apply to a cgroup: the tightest set on it or any cgroup above it
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CgroupLimits {
+    pub memory: Option<u64>,
+    pub swap: Option<u64>,
+    /// CPUs worth of time per period, from `cpu.max`
+    pub cpus: Option<f64>,
+}
+
+impl CgroupLimits {
+    pub fn read(root: &Path, path: &str) -> Self {
+        let mut limits = Self::default();
+        for ancestor in Path::new(path.trim_start_matches('/')).ancestors() {
+            let dir = root.join(ancestor);
+            limits.memory = tightest(limits.memory, read_number(&dir, "memory.max"));
+            limits.swap = tightest(limits.swap, read_number(&dir, "memory.swap.max"));
+            limits.cpus = tightest(limits.cpus, read_cpu_max(&dir));
+        }
+        limits
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The cgroup we run in, when it is limited: a container, or a slice with MemoryMax and friends
+pub struct OwnCgroup {
+    path: String,
+    limits: CgroupLimits,
+    memory: u64,
+    swap: u64,
+    pressure: Option<MemoryPressure>,
+    cpu_usec: u64,
+    cpu_read: Instant,
+    /// Percent of the CPU limit
+    cpu_usage: Option<f32>,
+}
+
+impl OwnCgroup {
+    /// None without cgroup v2 or without any limit, the host totals are right then
+    pub fn detect(root: &Path, proc_root: &Path) -> Option<Self> {
+        if !is_unified(root) {
+            return None;
+        }
+        let path = process_cgroup(proc_root, "self")?;
+        let limits = CgroupLimits::read(root, &path);
+        if limits.is_empty() {
+            return None;
+        }
+        let mut own = Self {
+            cpu_usec: CgroupCounters::read(root, &path).cpu_usec,
+            path,
+            limits,
+            memory: 0,
+            swap: 0,
+            pressure: None,
+            cpu_read: Instant::now(),
+            cpu_usage: None,
+        };
+        own.refresh_memory(root);
+        Some(own)
+    }
+
+    pub fn refresh_memory(&mut self, root: &Path) {
+        let dir = cgroup_dir(root, &self.path);
+        self.memory = read_number(&dir, "memory.current").unwrap_or(0);
+        self.swap = read_number(&dir, "memory.swap.current").unwrap_or(0);
+        self.pressure = meminfo::read_pressure(&dir.join("memory.pressure"));
+    }
+
+    pub fn refresh_cpu(&mut self, root: &Path) {
+        let Some(cpus) = self.limits.cpus else {
+            return;
+        };
+        let now = Instant::now();
+        let cpu_usec = CgroupCounters::read(root, &self.path).cpu_usec;
+        let elapsed = now.duration_since(self.cpu_read).as_secs_f64();
+        if elapsed > 0.0 {
+            let used = cpu_usec.saturating_sub(self.cpu_usec) as f64 / 1_000_000.0 / elapsed;
+            self.cpu_usage = Some((used / cpus * 100.0).min(100.0) as f32);
+        }
+        self.cpu_usec = cpu_usec;
+        self.cpu_read = now;
+    }
+
+    /// Replace the host totals with our limits, and host usage with ours
+    pub fn apply(&self, cpu: &mut CpuData, memory: &mut MemoryData) {
+        if let Some(limit) = self.limits.memory {
+            memory.total = memory.total.min(limit);
+            memory.used = self.memory;
+            // The host breakdown doesn't add up to our limit, and the host isn't what stalls us
+            memory.details = None;
+            memory.pressure = self.pressure.or(memory.pressure);
+        }
+        if let Some(limit) = self.limits.swap {
+            memory.total_swap = memory.total_swap.min(limit);
+            memory.used_swap = self.swap;
+        }
+        if let Some(cpus) = self.limits.cpus {
+            cpu.limit = Some(cpus);
+            if let Some(usage) = self.cpu_usage {
+                cpu.usage = usage;
+            }
+        }
+    }
+}
+
+fn cgroup_dir(root: &Path, path: &str) -> std::path::PathBuf {
+    root.join(path.trim_start_matches('/'))
+}
+
+/// `cpu.max` is `<quota> <period>` in microseconds, or `max <period>`
+fn read_cpu_max(dir: &Path) -> Option<f64> {
+    let content = fs::read_to_string(dir.join("cpu.max")).ok()?;
+    let mut parts = content.split_whitespace();
+    let quota: f64 = parts.next()?.parse().ok()?;
+    let period: f64 = parts.next()?.parse().ok()?;
+    (period > 0.0).then(|| quota / period)
+}
+
+fn tightest<T: PartialOrd>(a: Option<T>, b: Option<T>) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if b < a { b } else { a }),
+        (a, b) => a.or(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fake::FakeSysfs;
+    use crate::data::snapshot::MemoryDetails;
+
+    /// A cgroup v2 hierarchy under `cgroup` and processes under `proc`
+    fn hierarchy(name: &str) -> FakeSysfs {
+        let sysfs = FakeSysfs::new(&format!("cgroup-{}", name));
+        sysfs.files("cgroup", &[("cgroup.controllers", "cpu io memory")]);
+        sysfs
+    }
+
+    fn cgroup(sysfs: &FakeSysfs, path: &str, files: &[(&str, &str)]) {
+        sysfs.files(&format!("cgroup{}", path), files);
+    }
+
+    fn process(sysfs: &FakeSysfs, pid: &str, cgroup: &str) {
+        sysfs.files(
+            &format!("proc/{}", pid),
+            &[("cgroup", &format!("0::{}", cgroup))],
+        );
+    }
+
+    const ID: &str = "4f6e5d0c3b2a19081726354453627180f9e8d7c6b5a4938271605f4e3d2c1b0a";
+
+    #[test]
+    fn container_ids() {
+        let docker = format!("/system.slice/docker-{}.scope", ID);
+        assert_eq!(container_id(&docker).as_deref(), Some("4f6e5d0c3b2a"));
+        let kubernetes = format!(
+            "/kubepods.slice/kubepods-burstable.slice/kubepods-burstable-pod1234.slice/cri-containerd-{}.scope",
+            ID
+        );
+        assert_eq!(container_id(&kubernetes).as_deref(), Some("4f6e5d0c3b2a"));
+        assert_eq!(
+            container_id(&format!("/docker/{}", ID)).as_deref(),
+            Some("4f6e5d0c3b2a")
+        );
+        assert_eq!(container_id("/system.slice/nginx.service"), None);
+        assert_eq!(
+            container_id("/user.slice/user-1000.slice/session-2.scope"),
+            None
+        );
+    }
+
+    #[test]
+    fn counters_and_rates() {
+        let sysfs = hierarchy("counters");
+        cgroup(
+            &sysfs,
+            "/system.slice/nginx.service",
+            &[
+                ("cpu.stat", "usage_usec 3000000\nuser_usec 2000000\nsystem_usec 1000000"),
+                (
+                    "io.stat",
+                    "8:0 rbytes=4096 wbytes=1024 rios=1 wios=1 dbytes=0 dios=0\n259:0 rbytes=4096 wbytes=0 rios=1 wios=0 dbytes=0 dios=0",
+                ),
+                ("memory.current", "52428800"),
+                ("memory.max", "max"),
+            ],
+        );
+        process(&sysfs, "42", "/system.slice/nginx.service");
+
+        let path = process_cgroup(&sysfs.path("proc"), "42").unwrap();
+        assert_eq!(path, "/system.slice/nginx.service");
+        let counters = CgroupCounters::read(&sysfs.path("cgroup"), &path);
+        assert_eq!(
+            counters,
+            CgroupCounters {
+                cpu_usec: 3_000_000,
+                read_bytes: 8192,
+                written_bytes: 1024,
+            }
+        );
+        assert_eq!(
+            memory_current(&sysfs.path("cgroup"), &path),
+            Some(52_428_800)
+        );
+        assert_eq!(memory_max(&sysfs.path("cgroup"), &path), None);
+
+        // Half a core and 4 KiB/s read over two seconds
+        let previous = CgroupCounters {
+            cpu_usec: 2_000_000,
+            read_bytes: 0,
+            written_bytes: 1024,
+        };
+        assert_eq!(counters.rates_since(&previous, 2.0), (50.0, 4096.0, 0.0));
+    }
+
+    #[test]
+    fn limits_from_ancestors() {
+        let sysfs = hierarchy("limits");
+        cgroup(
+            &sysfs,
+            "/kubepods.slice/pod1",
+            &[("memory.max", "1073741824"), ("cpu.max", "150000 100000")],
+        );
+        cgroup(
+            &sysfs,
+            "/kubepods.slice/pod1/app",
+            &[
+                ("memory.max", "max"),
+                ("memory.swap.max", "0"),
+                ("cpu.max", "max 100000"),
+                ("memory.current", "268435456"),
+                (
+                    "memory.pressure",
+                    "some avg10=2.50 avg60=1.00 avg300=0.10 total=1\nfull avg10=0.00 avg60=0.00 avg300=0.00 total=0",
+                ),
+            ],
+        );
+        process(&sysfs, "self", "/kubepods.slice/pod1/app");
+
+        let limits = CgroupLimits::read(&sysfs.path("cgroup"), "/kubepods.slice/pod1/app");
+        assert_eq!(
+            limits,
+            CgroupLimits {
+                memory: Some(1_073_741_824),
+                swap: Some(0),
+                cpus: Some(1.5),
+            }
+        );
+
+        let own = OwnCgroup::detect(&sysfs.path("cgroup"), &sysfs.path("proc")).unwrap();
+        let mut cpu = CpuData {
+            usage: 80.0,
+            ..CpuData::default()
+        };
+        let mut memory = MemoryData {
+            used: 12 << 30,
+            total: 64 << 30,
+            used_swap: 1 << 30,
+            total_swap: 8 << 30,
+            details: Some(MemoryDetails::default()),
+            pressure: None,
+        };
+        own.apply(&mut cpu, &mut memory);
+        assert_eq!(cpu.limit, Some(1.5));
+        assert_eq!((memory.used, memory.total), (256 << 20, 1 << 30));
+        assert_eq!((memory.used_swap, memory.total_swap), (0, 0));
+        assert_eq!(memory.details, None);
+        assert_eq!(
+            memory.pressure.map(|pressure| pressure.some_avg10),
+            Some(2.5)
+        );
+
+        // Unlimited and v1-only hosts keep the host totals
+        process(&sysfs, "self", "/kubepods.slice");
+        assert!(OwnCgroup::detect(&sysfs.path("cgroup"), &sysfs.path("proc")).is_none());
+        fs::remove_file(sysfs.path("cgroup").join("cgroup.controllers")).unwrap();
+        assert!(!is_unified(&sysfs.path("cgroup")));
+    }
+}
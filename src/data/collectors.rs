@@ -1,10 +1,68 @@
-use chrono;
-use sysinfo::{CpuRefreshKind, Disks, MemoryRefreshKind, Networks, System};
+use super::cgroups::{self, CgroupCounters, OwnCgroup, CGROUP_PATH, PROC_PATH};
+use super::diskstats::{self, DiskActivity, DiskCounters, DISKSTATS_PATH};
+use super::meminfo::{self, BLOCK_PATH, MEMINFO_PATH, PRESSURE_PATH};
+use super::power::{self, POWER_SUPPLY_PATH};
+use super::sensors::{self, HWMON_PATH};
+use super::snapshot::{
+    CgroupData, CoreData, CpuData, DiskData, LoadAverage, MemoryData, MemoryDetails,
+    MemoryPressure, NetworkData, PowerData, ProcessData, SensorData, Snapshot, SystemInfoData,
+};
+use super::systemd::{self, Systemctl, UnitWatcher};
+use super::{DataSource, RefreshScheduler};
+use crate::config::{Config, DiskConfig};
+use anyhow::{anyhow, bail};
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use sysinfo::{
+    CpuRefreshKind, DiskRefreshKind, Disks, MemoryRefreshKind, Networks, Pid, Signal, System, Users,
+};
 
 pub struct SystemInfo {
     system: System,
     networks: Networks,
     disks: Disks,
+    users: Users,
+    // sysinfo doesn't cover batteries or fans, read from sysfs on refresh
+    power: PowerData,
+    sensors: SensorData,
+    // sysinfo only has used and total, the breakdown comes from /proc
+    memory_details: Option<MemoryDetails>,
+    memory_pressure: Option<MemoryPressure>,
+    last_network_refresh: Instant,
+    // Time covered by the per-refresh network counters
+    network_elapsed: Duration,
+    // Received and sent byte totals per interface as of the last refresh
+    network_totals: HashMap<String, (u64, u64)>,
+    // Received and sent bytes per second over the last refresh
+    network_rates: HashMap<String, (f64, f64)>,
+    // Received and sent drops per interface, read with the other network counters
+    network_drops: HashMap<String, (u64, u64)>,
+    last_disk_refresh: Instant,
+    // Time covered by the per-refresh disk I/O counters
+    disk_elapsed: Duration,
+    // sysinfo has no request counts or busy time, /proc/diskstats does
+    disk_counters: HashMap<String, DiskCounters>,
+    // Keyed by sysinfo's device name, already matched to the kernel's
+    disk_activity: HashMap<String, DiskActivity>,
+    disk_filter: DiskConfig,
+    // Space and inodes per mount point, from our own statvfs so network mounts can be
+    // left out: on a dead server it blocks until the server comes back
+    disk_stats: HashMap<String, FsStats>,
+    // cgroup v2 only; the mapping and accounting refresh with the process list
+    cgroups_enabled: bool,
+    process_cgroups: HashMap<u32, String>,
+    cgroup_counters: HashMap<String, CgroupCounters>,
+    cgroups: Vec<CgroupData>,
+    last_process_refresh: Instant,
+    // Our own limits, reported as the totals when set
+    own_cgroup: Option<OwnCgroup>,
+    // Configured systemd units, asked through systemctl off the refresh loop
+    units: UnitWatcher,
 }
 
 impl SystemInfo {
@@ -13,217 +71,538 @@ impl SystemInfo {
         system.refresh_cpu_specifics(CpuRefreshKind::everything());
         system.refresh_memory_specifics(MemoryRefreshKind::everything());
 
+        let networks = Networks::new_with_refreshed_list();
+        let network_totals = network_totals(&networks);
+
         Self {
             system,
-            networks: Networks::new_with_refreshed_list(),
-            disks: Disks::new_with_refreshed_list(),
+            networks,
+            disks: Disks::new_with_refreshed_list_specifics(disk_refresh_kind()),
+            users: Users::new_with_refreshed_list(),
+            power: PowerData::default(),
+            sensors: SensorData::default(),
+            memory_details: meminfo::read_meminfo(Path::new(MEMINFO_PATH), Path::new(BLOCK_PATH)),
+            memory_pressure: meminfo::read_pressure(Path::new(PRESSURE_PATH)),
+            last_network_refresh: Instant::now(),
+            network_elapsed: Duration::ZERO,
+            network_totals,
+            network_rates: HashMap::new(),
+            network_drops: HashMap::new(),
+            last_disk_refresh: Instant::now(),
+            disk_elapsed: Duration::ZERO,
+            disk_counters: diskstats::read_diskstats(Path::new(DISKSTATS_PATH)),
+            disk_activity: HashMap::new(),
+            disk_filter: DiskConfig::default(),
+            disk_stats: HashMap::new(),
+            cgroups_enabled: cgroups::is_unified(Path::new(CGROUP_PATH)),
+            process_cgroups: HashMap::new(),
+            cgroup_counters: HashMap::new(),
+            cgroups: Vec::new(),
+            last_process_refresh: Instant::now(),
+            own_cgroup: OwnCgroup::detect(Path::new(CGROUP_PATH), Path::new(PROC_PATH)),
+            units: UnitWatcher::new(Arc::new(Systemctl), Vec::new()),
         }
     }
 
-    pub fn refresh(&mut self) {
-        // Refresh CPU, memory, processes, networks, and disks
-        self.system
-            .refresh_cpu_specifics(CpuRefreshKind::everything());
-        self.system
-            .refresh_memory_specifics(MemoryRefreshKind::everything());
-        self.system
-            .refresh_processes(sysinfo::ProcessesToUpdate::All, true); // Corrected variant
-        self.networks.refresh(false);
-        self.disks.refresh(false);
+    /// Only the mounts and systemd units the config asks for
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            disk_filter: config.disks.clone(),
+            units: UnitWatcher::new(Arc::new(Systemctl), config.systemd.units.clone()),
+            ..Self::new()
+        }
     }
 
-    // CPU Information
-    pub fn cpu_usage(&self) -> f32 {
-        self.system.global_cpu_usage()
+    /// From the config, with everything `scheduler` covers refreshed twice so even the
+    /// first snapshot has CPU usage and rates
+    pub async fn primed(config: &Config, scheduler: &mut RefreshScheduler) -> Self {
+        let mut system_info = Self::from_config(config);
+        scheduler.refresh_all(&mut system_info);
+        tokio::time::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL).await;
+        scheduler.refresh_all(&mut system_info);
+        system_info
+    }
+
+    pub fn refresh_source(&mut self, source: DataSource) {
+        match source {
+            DataSource::Cpu => {
+                self.system
+                    .refresh_cpu_specifics(CpuRefreshKind::everything());
+                if let Some(own) = &mut self.own_cgroup {
+                    own.refresh_cpu(Path::new(CGROUP_PATH));
+                }
+            }
+            DataSource::Memory => {
+                self.system
+                    .refresh_memory_specifics(MemoryRefreshKind::everything());
+                self.memory_details =
+                    meminfo::read_meminfo(Path::new(MEMINFO_PATH), Path::new(BLOCK_PATH));
+                self.memory_pressure = meminfo::read_pressure(Path::new(PRESSURE_PATH));
+                if let Some(own) = &mut self.own_cgroup {
+                    own.refresh_memory(Path::new(CGROUP_PATH));
+                }
+            }
+            DataSource::Processes => {
+                self.system
+                    .refresh_processes(sysinfo::ProcessesToUpdate::All, true);
+                if self.cgroups_enabled {
+                    self.refresh_cgroups();
+                }
+            }
+            DataSource::Networks => {
+                self.networks.refresh(false);
+                let now = Instant::now();
+                self.network_elapsed = now.duration_since(self.last_network_refresh);
+                self.last_network_refresh = now;
+
+                let totals = network_totals(&self.networks);
+                let elapsed = self.network_elapsed.as_secs_f64();
+                self.network_rates = totals
+                    .iter()
+                    .filter_map(|(interface, &(received, transmitted))| {
+                        let &(previous_received, previous_transmitted) =
+                            self.network_totals.get(interface)?;
+                        let rates = (
+                            counter_rate(previous_received, received, elapsed),
+                            counter_rate(previous_transmitted, transmitted, elapsed),
+                        );
+                        Some((interface.clone(), rates))
+                    })
+                    .collect();
+                self.network_totals = totals;
+
+                self.network_drops = self
+                    .networks
+                    .list()
+                    .keys()
+                    .map(|interface| {
+                        let drops = (
+                            read_interface_counter(Path::new(NET_PATH), interface, "rx_dropped"),
+                            read_interface_counter(Path::new(NET_PATH), interface, "tx_dropped"),
+                        );
+                        (interface.clone(), drops)
+                    })
+                    .collect();
+            }
+            DataSource::Disks => {
+                self.disks.refresh_specifics(false, disk_refresh_kind());
+                let now = Instant::now();
+                self.disk_elapsed = now.duration_since(self.last_disk_refresh);
+                self.last_disk_refresh = now;
+
+                let counters = diskstats::read_diskstats(Path::new(DISKSTATS_PATH));
+                let elapsed = self.disk_elapsed.as_secs_f64();
+                self.disk_activity = self
+                    .disks
+                    .iter()
+                    .filter_map(|disk| {
+                        let name = disk.name().to_string_lossy().to_string();
+                        let device = diskstats::kernel_name(&name);
+                        let current = counters.get(&device)?;
+                        let previous = self.disk_counters.get(&device)?;
+                        Some((name, current.activity_since(previous, elapsed)))
+                    })
+                    .collect();
+                self.disk_counters = counters;
+
+                self.disk_stats = self
+                    .disks
+                    .iter()
+                    .filter(|disk| self.is_listed(disk))
+                    .filter(|disk| !is_network_fs(&disk.file_system().to_string_lossy()))
+                    .filter_map(|disk| {
+                        let mount_point = disk.mount_point().to_string_lossy().to_string();
+                        Some((mount_point, fs_stats(disk.mount_point())?))
+                    })
+                    .collect();
+            }
+            DataSource::Power => {
+                self.power = power::read_power_supplies(Path::new(POWER_SUPPLY_PATH));
+            }
+            DataSource::Sensors => {
+                self.sensors = sensors::read_hwmon(Path::new(HWMON_PATH));
+            }
+            DataSource::Units => self.units.refresh(),
+            DataSource::Clock => {}
+        }
     }
 
-    pub fn cpu_count(&self) -> usize {
-        self.system.cpus().len()
+    /// Map every process to its cgroup, then read the accounting of those cgroups
+    fn refresh_cgroups(&mut self) {
+        let root = Path::new(CGROUP_PATH);
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_process_refresh).as_secs_f64();
+        self.last_process_refresh = now;
+
+        self.process_cgroups = self
+            .system
+            .processes()
+            .values()
+            .filter(|process| process.thread_kind().is_none())
+            .filter_map(|process| {
+                let pid = process.pid().as_u32();
+                let cgroup = cgroups::process_cgroup(Path::new(PROC_PATH), &pid.to_string())?;
+                Some((pid, cgroup))
+            })
+            .collect();
+
+        let mut processes: HashMap<&str, usize> = HashMap::new();
+        for cgroup in self.process_cgroups.values() {
+            *processes.entry(cgroup).or_default() += 1;
+        }
+
+        let mut counters = HashMap::new();
+        self.cgroups = processes
+            .into_iter()
+            .map(|(path, processes)| {
+                let current = CgroupCounters::read(root, path);
+                // A cgroup seen for the first time has no rates yet
+                let (cpu_usage, read_per_sec, written_per_sec) = self
+                    .cgroup_counters
+                    .get(path)
+                    .map(|previous| current.rates_since(previous, elapsed))
+                    .unwrap_or_default();
+                counters.insert(path.to_string(), current);
+                CgroupData {
+                    path: path.to_string(),
+                    container: cgroups::container_id(path),
+                    processes,
+                    cpu_usage,
+                    memory: cgroups::memory_current(root, path).unwrap_or(0),
+                    memory_limit: cgroups::memory_max(root, path),
+                    read_per_sec,
+                    written_per_sec,
+                }
+            })
+            .collect();
+        self.cgroups.sort_by(|a, b| a.path.cmp(&b.path));
+        self.cgroup_counters = counters;
     }
 
-    // Memory Information
-    pub fn memory_usage(&self) -> (u64, u64) {
-        (self.system.used_memory(), self.system.total_memory())
+    /// Copy the current state out of the live sysinfo handles
+    pub fn snapshot(&self) -> Snapshot {
+        let mut cpu = self.cpu_data();
+        let mut memory = MemoryData {
+            used: self.system.used_memory(),
+            total: self.system.total_memory(),
+            used_swap: self.system.used_swap(),
+            total_swap: self.system.total_swap(),
+            details: self.memory_details,
+            pressure: self.memory_pressure,
+        };
+        if let Some(own) = &self.own_cgroup {
+            own.apply(&mut cpu, &mut memory);
+        }
+
+        let processes = self.process_data();
+        let units = systemd::join_processes(&self.units.units(), &processes);
+        Snapshot {
+            cpu,
+            memory,
+            disks: self.disk_data(),
+            network: self.network_data(),
+            processes,
+            power: self.power.clone(),
+            sensors: self.sensors.clone(),
+            cgroups: self.cgroups.clone(),
+            units,
+            system: SystemInfoData {
+                uptime: System::uptime(),
+            },
+            timestamp: chrono::Utc::now().timestamp(),
+        }
     }
 
-    pub fn swap_usage(&self) -> (u64, u64) {
-        (self.system.used_swap(), self.system.total_swap())
+    // CPU Information
+    fn cpu_data(&self) -> CpuData {
+        let cpus = self.system.cpus();
+        let load_avg = System::load_average();
+
+        CpuData {
+            usage: self.system.global_cpu_usage(),
+            count: cpus.len(),
+            physical_count: System::physical_core_count(),
+            brand: cpus
+                .first()
+                .map(|cpu| cpu.brand().trim().to_string())
+                .unwrap_or_default(),
+            vendor: cpus
+                .first()
+                .map(|cpu| cpu.vendor_id().to_string())
+                .unwrap_or_default(),
+            cores: cpus
+                .iter()
+                .map(|cpu| CoreData {
+                    name: cpu.name().to_string(),
+                    usage: cpu.cpu_usage(),
+                    frequency: cpu.frequency(),
+                })
+                .collect(),
+            load_average: LoadAverage {
+                one: load_avg.one,
+                five: load_avg.five,
+                fifteen: load_avg.fifteen,
+            },
+            limit: None,
+        }
     }
 
     // Network Information
-    pub fn network_stats(&self) -> Vec<(String, u64, u64)> {
+    fn network_data(&self) -> Vec<NetworkData> {
         self.networks
             .iter()
-            .map(|(interface, data)| (interface.clone(), data.received(), data.transmitted()))
+            .map(|(interface, data)| {
+                let (dropped_received, dropped_transmitted) = self
+                    .network_drops
+                    .get(interface)
+                    .copied()
+                    .unwrap_or_default();
+                let (received_per_sec, transmitted_per_sec) = self
+                    .network_rates
+                    .get(interface)
+                    .copied()
+                    .unwrap_or_default();
+                NetworkData {
+                    interface: interface.clone(),
+                    received_per_sec,
+                    transmitted_per_sec,
+                    total_received: data.total_received(),
+                    total_transmitted: data.total_transmitted(),
+                    packets_received: data.total_packets_received(),
+                    packets_transmitted: data.total_packets_transmitted(),
+                    errors_received: data.total_errors_on_received(),
+                    errors_transmitted: data.total_errors_on_transmitted(),
+                    dropped_received,
+                    dropped_transmitted,
+                }
+            })
             .collect()
     }
 
     // Disk Information
-    pub fn disk_stats(&self) -> Vec<(String, u64, u64, String)> {
+    fn disk_data(&self) -> Vec<DiskData> {
+        let elapsed = self.disk_elapsed.as_secs_f64();
+
         self.disks
             .iter()
+            .filter(|disk| self.is_listed(disk))
             .map(|disk| {
-                (
-                    disk.name().to_string_lossy().to_string(),
-                    disk.total_space(),
-                    disk.available_space(),
-                    disk.mount_point().to_string_lossy().to_string(),
-                )
+                let usage = disk.usage();
+                let name = disk.name().to_string_lossy().to_string();
+                let mount_point = disk.mount_point().to_string_lossy().to_string();
+                let stats = self
+                    .disk_stats
+                    .get(&mount_point)
+                    .copied()
+                    .unwrap_or_default();
+                let activity = self.disk_activity.get(&name).copied().unwrap_or_default();
+                DiskData {
+                    name,
+                    total: stats.total,
+                    available: stats.available,
+                    mount_point,
+                    file_system: disk.file_system().to_string_lossy().to_string(),
+                    removable: disk.is_removable(),
+                    read_only: stats.read_only,
+                    inodes_total: stats.inodes_total,
+                    inodes_free: stats.inodes_free,
+                    read_per_sec: per_second(usage.read_bytes, elapsed),
+                    written_per_sec: per_second(usage.written_bytes, elapsed),
+                    read_iops: activity.read_iops,
+                    write_iops: activity.write_iops,
+                    busy_percent: activity.busy_percent,
+                }
             })
             .collect()
     }
 
+    /// Whether the mount filter lets this disk through
+    fn is_listed(&self, disk: &sysinfo::Disk) -> bool {
+        self.disk_filter.allows(
+            &disk.mount_point().to_string_lossy(),
+            &disk.file_system().to_string_lossy(),
+            &disk.name().to_string_lossy(),
+        )
+    }
+
     // Processes Information
-    pub fn top_processes(&self, count: usize) -> Vec<(String, u32, f32, u64)> {
-        let mut processes: Vec<_> = self
-            .system
+    fn process_data(&self) -> Vec<ProcessData> {
+        self.system
             .processes()
             .values()
+            // Threads share their process' memory, listing them would count it twice
+            .filter(|process| process.thread_kind().is_none())
             .map(|process| {
-                (
-                    process.name().to_string_lossy().to_string(),
-                    process.pid().as_u32(),
-                    process.cpu_usage(),
-                    process.memory(),
-                )
+                let pid = process.pid().as_u32();
+                let cgroup = self.process_cgroups.get(&pid);
+                ProcessData {
+                    name: process.name().to_string_lossy().to_string(),
+                    pid,
+                    parent: process.parent().map(|pid| pid.as_u32()),
+                    user: process
+                        .user_id()
+                        .and_then(|uid| self.users.get_user_by_id(uid))
+                        .map(|user| user.name().to_string())
+                        .unwrap_or_default(),
+                    cpu_usage: process.cpu_usage(),
+                    memory: process.memory(),
+                    cgroup: cgroup.cloned(),
+                    container: cgroup.and_then(|cgroup| cgroups::container_id(cgroup)),
+                }
             })
-            .collect();
-
-        // Sort by CPU usage in descending order
-        processes.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap());
-
-        // Return the top `count` processes
-        processes.into_iter().take(count).collect()
+            .collect()
     }
 
-    // System Information
-    pub fn uptime(&self) -> u64 {
-        System::uptime()
-    }
+    /// Send SIGTERM, or SIGKILL when `force` is set
+    pub fn signal_process(&self, pid: u32, force: bool) -> anyhow::Result<()> {
+        let (signal, label) = if force {
+            (Signal::Kill, "SIGKILL")
+        } else {
+            (Signal::Term, "SIGTERM")
+        };
+        let process = self
+            .system
+            .process(Pid::from_u32(pid))
+            .ok_or_else(|| anyhow!("No process with PID {}", pid))?;
 
-    pub fn load_average(&self) -> (f64, f64, f64) {
-        let load_avg = System::load_average();
-        (load_avg.one, load_avg.five, load_avg.fifteen)
+        match process.kill_with(signal) {
+            Some(true) => Ok(()),
+            Some(false) => bail!("Failed to send {} to PID {}", label, pid),
+            None => bail!("{} is not supported on this platform", label),
+        }
     }
 }
 
-// Struct for JSON serialization
-#[derive(serde::Serialize)]
-pub struct SystemData {
-    pub cpu: CpuData,
-    pub memory: MemoryData,
-    pub disks: Vec<DiskData>,
-    pub network: Vec<NetworkData>,
-    pub processes: Vec<ProcessData>,
-    pub system: SystemInfoData,
-    pub timestamp: i64,
+fn per_second(bytes: u64, elapsed: f64) -> f64 {
+    if elapsed > 0.0 {
+        bytes as f64 / elapsed
+    } else {
+        0.0
+    }
 }
 
-#[derive(serde::Serialize)]
-pub struct CpuData {
-    pub usage: f32,
-    pub count: usize,
-    pub load_average: LoadAverage,
+/// Received and sent byte totals per interface
+fn network_totals(networks: &Networks) -> HashMap<String, (u64, u64)> {
+    networks
+        .iter()
+        .map(|(interface, data)| {
+            let totals = (data.total_received(), data.total_transmitted());
+            (interface.clone(), totals)
+        })
+        .collect()
 }
 
-#[derive(serde::Serialize)]
-pub struct LoadAverage {
-    pub one: f64,
-    pub five: f64,
-    pub fifteen: f64,
+/// Per second rate between two readings of a counter; one that went backwards, from a
+/// wraparound or the interface being reset, counts as nothing rather than a huge rate
+fn counter_rate(previous: u64, current: u64, elapsed: f64) -> f64 {
+    per_second(current.saturating_sub(previous), elapsed)
 }
 
-#[derive(serde::Serialize)]
-pub struct MemoryData {
-    pub used: u64,
-    pub total: u64,
-    pub used_swap: u64,
-    pub total_swap: u64,
+/// Filesystems served over the network, by type as in /proc/mounts
+const NETWORK_FS_TYPES: [&str; 9] = [
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "sshfs",
+    "9p",
+    "ceph",
+    "glusterfs",
+];
+
+fn is_network_fs(fs_type: &str) -> bool {
+    // FUSE mounts carry their driver after the dot, e.g. `fuse.sshfs`
+    let fs_type = fs_type.strip_prefix("fuse.").unwrap_or(fs_type);
+    NETWORK_FS_TYPES.contains(&fs_type)
 }
 
-#[derive(serde::Serialize)]
-pub struct DiskData {
-    pub name: String,
-    pub total: u64,
-    pub available: u64,
-    pub mount_point: String,
+/// Mount list and I/O counters only: sysinfo's space figures come from a statvfs on
+/// every mount, network ones included, so `fs_stats` asks for the local ones instead
+fn disk_refresh_kind() -> DiskRefreshKind {
+    DiskRefreshKind::nothing().with_io_usage()
 }
 
-#[derive(serde::Serialize)]
-pub struct NetworkData {
-    pub interface: String,
-    pub received: u64,
-    pub transmitted: u64,
+/// What statvfs says about a mounted filesystem
+#[derive(Clone, Copy, Default)]
+struct FsStats {
+    total: u64,
+    available: u64,
+    read_only: bool,
+    inodes_total: u64,
+    inodes_free: u64,
 }
 
-#[derive(serde::Serialize)]
-pub struct ProcessData {
-    pub name: String,
-    pub pid: u32,
-    pub cpu_usage: f32,
-    pub memory: u64,
+// sysinfo has no inode counts, so space and inodes both come from one statvfs
+fn fs_stats(mount_point: &Path) -> Option<FsStats> {
+    let path = CString::new(mount_point.as_os_str().as_bytes()).ok()?;
+    // SAFETY: `path` is NUL terminated and `stat` is a plain struct statvfs fills in
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    let block_size = stat.f_frsize as u64;
+    Some(FsStats {
+        total: stat.f_blocks as u64 * block_size,
+        available: stat.f_bavail as u64 * block_size,
+        read_only: stat.f_flag & libc::ST_RDONLY != 0,
+        inodes_total: stat.f_files as u64,
+        inodes_free: stat.f_ffree as u64,
+    })
 }
 
-#[derive(serde::Serialize)]
-pub struct SystemInfoData {
-    pub uptime: u64,
+const NET_PATH: &str = "/sys/class/net";
+
+// sysinfo has no drop counters, so read them from sysfs (0 when unavailable)
+fn read_interface_counter(root: &Path, interface: &str, counter: &str) -> u64 {
+    fs::read_to_string(root.join(interface).join("statistics").join(counter))
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(0)
 }
 
-impl<'a> From<&'a SystemInfo> for SystemData {
-    fn from(sys_info: &'a SystemInfo) -> Self {
-        let (used_mem, total_mem) = sys_info.memory_usage();
-        let (used_swap, total_swap) = sys_info.swap_usage();
-        let load_avg = sys_info.load_average();
-
-        SystemData {
-            cpu: CpuData {
-                usage: sys_info.cpu_usage(),
-                count: sys_info.cpu_count(),
-                load_average: LoadAverage {
-                    one: load_avg.0,
-                    five: load_avg.1,
-                    fifteen: load_avg.2,
-                },
-            },
-            memory: MemoryData {
-                used: used_mem,
-                total: total_mem,
-                used_swap,
-                total_swap,
-            },
-            disks: sys_info
-                .disk_stats()
-                .iter()
-                .map(|(name, total, available, mount_point)| DiskData {
-                    name: name.clone(),
-                    total: *total,
-                    available: *available,
-                    mount_point: mount_point.clone(),
-                })
-                .collect(),
-            network: sys_info
-                .network_stats()
-                .iter()
-                .map(|(interface, received, transmitted)| NetworkData {
-                    interface: interface.clone(),
-                    received: *received,
-                    transmitted: *transmitted,
-                })
-                .collect(),
-            processes: sys_info
-                .top_processes(5) // Call the top_processes method here
-                .iter()
-                .map(|(name, pid, cpu, memory)| ProcessData {
-                    name: name.clone(),
-                    pid: *pid,
-                    cpu_usage: *cpu,
-                    memory: *memory,
-                })
-                .collect(),
-            system: SystemInfoData {
-                uptime: sys_info.uptime(),
-            },
-            timestamp: chrono::Utc::now().timestamp(),
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fake::FakeSysfs;
+
+    #[test]
+    fn network_counter_rates() {
+        // 3 MB received and 1 MB sent over 2 seconds
+        assert_eq!(counter_rate(1_000_000, 7_000_000, 2.0), 3_000_000.0);
+        assert_eq!(counter_rate(500_000, 2_500_000, 2.0), 1_000_000.0);
+        // Counters that went backwards: a reset interface and a wrapped 32 bit counter
+        assert_eq!(counter_rate(9_000_000_000, 4_096, 2.0), 0.0);
+        assert_eq!(counter_rate(u32::MAX as u64 - 10, 20, 1.0), 0.0);
+        // No time passed yet
+        assert_eq!(counter_rate(0, 1_000, 0.0), 0.0);
+    }
+
+    #[test]
+    fn interface_drop_counters() {
+        let sysfs = FakeSysfs::new("net-drops");
+        sysfs.files(
+            "eth0/statistics",
+            &[("rx_dropped", "12"), ("tx_dropped", "0")],
+        );
+
+        assert_eq!(
+            read_interface_counter(&sysfs.root, "eth0", "rx_dropped"),
+            12
+        );
+        assert_eq!(read_interface_counter(&sysfs.root, "eth0", "tx_dropped"), 0);
+        assert_eq!(
+            read_interface_counter(&sysfs.root, "wlan0", "rx_dropped"),
+            0
+        );
+    }
+
+    #[test]
+    fn network_filesystems() {
+        assert!(is_network_fs("nfs4"));
+        assert!(is_network_fs("cifs"));
+        assert!(is_network_fs("fuse.sshfs"));
+        assert!(!is_network_fs("ext4"));
+        assert!(!is_network_fs("fuse.portal"));
     }
 }
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+pub const DISKSTATS_PATH: &str = "/proc/diskstats";
+
+/// Cumulative counters for one block device, since boot
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DiskCounters {
+    pub reads: u64,
+    pub writes: u64,
+    /// Milliseconds spent with at least one request in flight
+    pub io_ms: u64,
+}
+
+/// Activity between two readings of the counters
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiskActivity {
+    pub read_iops: f64,
+    pub write_iops: f64,
+    pub busy_percent: f64,
+}
+
+impl DiskCounters {
+    pub fn activity_since(&self, previous: &DiskCounters, elapsed: f64) -> DiskActivity {
+        if elapsed <= 0.0 {
+            return DiskActivity::default();
+        }
+        DiskActivity {
+            read_iops: self.reads.saturating_sub(previous.reads) as f64 / elapsed,
+            write_iops: self.writes.saturating_sub(previous.writes) as f64 / elapsed,
+            busy_percent: (self.io_ms.saturating_sub(previous.io_ms) as f64 / (elapsed * 10.0))
+                .min(100.0),
+        }
+    }
+}
+
+pub fn read_diskstats(path: &Path) -> HashMap<String, DiskCounters> {
+    fs::read_to_string(path)
+        .map(|content| parse_diskstats(&content))
+        .unwrap_or_default()
+}
+
+/// https://www.kernel.org/doc/html/latest/admin-guide/iostats.html
+pub fn parse_diskstats(content: &str) -> HashMap<String, DiskCounters> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let number = |index: usize| fields.get(index)?.parse::<u64>().ok();
+            Some((
+                fields.get(2)?.to_string(),
+                DiskCounters {
+                    reads: number(3)?,
+                    writes: number(7)?,
+                    io_ms: number(12)?,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Kernel name for a device path, e.g. `/dev/mapper/root` -> `dm-0`; anything outside
+/// /dev, like `tmpfs`, is no path to resolve and stays as it is
+pub fn kernel_name(device: &str) -> String {
+    if !device.starts_with("/dev/") {
+        return device.to_string();
+    }
+    let path = fs::canonicalize(device).unwrap_or_else(|_| device.into());
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| device.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DISKSTATS: &str = "\
+ 259       0 nvme0n1 120 3 9000 50 400 20 16000 300 0 500 350 0 0 0 0 10 2
+ 259       1 nvme0n1p1 10 0 80 5 0 0 0 0 0 5 5 0 0 0 0 0 0
+   7       0 loop0 0 0 0 0 0 0 0 0 0 0 0
+";
+
+    #[test]
+    fn parses_counters() {
+        let stats = parse_diskstats(DISKSTATS);
+        assert_eq!(stats.len(), 3);
+        assert_eq!(
+            stats["nvme0n1"],
+            DiskCounters {
+                reads: 120,
+                writes: 400,
+                io_ms: 500,
+            }
+        );
+        assert_eq!(stats["nvme0n1p1"].io_ms, 5);
+    }
+
+    #[test]
+    fn activity_between_readings() {
+        let before = DiskCounters {
+            reads: 100,
+            writes: 50,
+            io_ms: 1000,
+        };
+        let after = DiskCounters {
+            reads: 300,
+            writes: 70,
+            io_ms: 1500,
+        };
+
+        let activity = after.activity_since(&before, 2.0);
+        assert_eq!(activity.read_iops, 100.0);
+        assert_eq!(activity.write_iops, 10.0);
+        // 500ms busy out of 2000ms
+        assert_eq!(activity.busy_percent, 25.0);
+    }
+
+    #[test]
+    fn kernel_names() {
+        assert_eq!(kernel_name("/dev/no-such-disk0p1"), "no-such-disk0p1");
+        // Not looked up relative to the working directory
+        assert_eq!(kernel_name("tmpfs"), "tmpfs");
+    }
+}
@@ -0,0 +1,309 @@
+// Canned snapshots, a scripted source and throwaway sysfs trees, so tests don't need a real host
+use std::cell::RefCell;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+use super::snapshot::{
+    BatteryData, CgroupData, CoreData, CpuData, DiskData, FanData, LoadAverage, MemoryData,
+    MemoryDetails, MemoryPressure, NetworkData, PowerData, ProcessData, SensorData, SystemInfoData,
+    TemperatureData, UnitData,
+};
+use super::{MetricsSource, Snapshot};
+
+/// Plays a fixed list of snapshots, one per `update`, then stays on the last
+pub struct ScriptedSource {
+    samples: Vec<Snapshot>,
+    position: usize,
+    /// PIDs passed to `signal_process` with the force flag, shared so tests can look after boxing
+    pub signals: Rc<RefCell<Vec<(u32, bool)>>>,
+}
+
+impl ScriptedSource {
+    pub fn new(samples: Vec<Snapshot>) -> Self {
+        assert!(!samples.is_empty(), "a scripted source needs a sample");
+        Self {
+            samples,
+            position: 0,
+            signals: Default::default(),
+        }
+    }
+}
+
+impl MetricsSource for ScriptedSource {
+    fn update(&mut self) -> bool {
+        if self.position + 1 < self.samples.len() {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn refresh_all(&mut self) {}
+
+    fn snapshot(&self) -> Snapshot {
+        self.samples[self.position].clone()
+    }
+
+    fn until_next(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    fn signal_process(&self, pid: u32, force: bool) -> anyhow::Result<()> {
+        self.signals.borrow_mut().push((pid, force));
+        Ok(())
+    }
+}
+
+/// A throwaway directory tree standing in for /sys or /proc, removed on drop
+pub struct FakeSysfs {
+    pub root: PathBuf,
+}
+
+impl FakeSysfs {
+    /// `name` must be unique across tests, they run in parallel
+    pub fn new(name: &str) -> Self {
+        let root =
+            std::env::temp_dir().join(format!("rust-conky-sysfs-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        Self { root }
+    }
+
+    pub fn path(&self, dir: &str) -> PathBuf {
+        self.root.join(dir.trim_start_matches('/'))
+    }
+
+    /// Create `dir` under the root with one line in each file
+    pub fn files(&self, dir: &str, files: &[(&str, &str)]) {
+        let dir = self.path(dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (file, content) in files {
+            fs::write(dir.join(file), format!("{}\n", content)).unwrap();
+        }
+    }
+}
+
+impl Drop for FakeSysfs {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+const GB: u64 = 1024 * 1024 * 1024;
+const CONTAINER_ID: &str = "4f6e5d0c3b2a19081726354453627180f9e8d7c6b5a4938271605f4e3d2c1b0a";
+const MB: u64 = 1024 * 1024;
+
+/// A small but complete machine; `step` nudges the busy figures so samples differ
+pub fn snapshot(step: u32) -> Snapshot {
+    let busy = step as f32 * 5.0;
+    Snapshot {
+        cpu: CpuData {
+            usage: 25.0 + busy,
+            count: 4,
+            physical_count: Some(2),
+            brand: "Fake CPU 3000".to_string(),
+            vendor: "FakeVendor".to_string(),
+            cores: (0..4)
+                .map(|core| CoreData {
+                    name: format!("cpu{}", core),
+                    usage: 10.0 + core as f32 * 10.0 + busy,
+                    frequency: 2400 + core as u64 * 100,
+                })
+                .collect(),
+            load_average: LoadAverage {
+                one: 1.25,
+                five: 0.75,
+                fifteen: 0.5,
+            },
+            limit: None,
+        },
+        memory: MemoryData {
+            used: 6 * GB + step as u64 * 256 * MB,
+            total: 16 * GB,
+            used_swap: GB / 2,
+            total_swap: 2 * GB,
+            details: Some(MemoryDetails {
+                available: 10 * GB,
+                free: 3 * GB,
+                cached: 6 * GB,
+                buffers: 512 * MB,
+                shared: GB,
+                dirty: 12 * MB,
+                zswap: 64 * MB,
+                zswapped: 256 * MB,
+                ..MemoryDetails::default()
+            }),
+            pressure: Some(MemoryPressure {
+                some_avg10: 1.5,
+                some_avg60: 0.75,
+                full_avg10: 0.25,
+                ..MemoryPressure::default()
+            }),
+        },
+        disks: vec![
+            DiskData {
+                name: "/dev/nvme0n1p2".to_string(),
+                total: 500 * GB,
+                available: 200 * GB,
+                mount_point: "/".to_string(),
+                file_system: "ext4".to_string(),
+                removable: false,
+                read_only: false,
+                inodes_total: 32_000_000,
+                inodes_free: 24_000_000,
+                read_per_sec: 2.0 * MB as f64,
+                written_per_sec: 512.0 * 1024.0,
+                read_iops: 120.0,
+                write_iops: 30.0,
+                busy_percent: 12.0,
+            },
+            DiskData {
+                name: "/dev/sda1".to_string(),
+                total: 64 * GB,
+                available: 60 * GB,
+                mount_point: "/media/usb".to_string(),
+                file_system: "vfat".to_string(),
+                removable: true,
+                read_only: true,
+                ..DiskData::default()
+            },
+        ],
+        network: vec![
+            NetworkData {
+                interface: "eth0".to_string(),
+                received_per_sec: 1.5 * MB as f64,
+                transmitted_per_sec: 200.0 * 1024.0,
+                total_received: 3 * GB,
+                total_transmitted: GB,
+                packets_received: 2_000_000,
+                packets_transmitted: 1_000_000,
+                ..NetworkData::default()
+            },
+            NetworkData {
+                interface: "lo".to_string(),
+                received_per_sec: 1024.0,
+                transmitted_per_sec: 1024.0,
+                total_received: 10 * MB,
+                total_transmitted: 10 * MB,
+                ..NetworkData::default()
+            },
+        ],
+        processes: vec![
+            process(1, None, "init", "root", 0.0, 12 * MB),
+            process(400, Some(1), "sshd", "root", 0.5, 8 * MB),
+            process(1200, Some(1), "postgres", "postgres", 12.5, 900 * MB),
+            process(1210, Some(1200), "postgres", "postgres", 3.0, 150 * MB),
+            ProcessData {
+                cgroup: Some(format!("/system.slice/docker-{}.scope", CONTAINER_ID)),
+                container: Some(CONTAINER_ID[..12].to_string()),
+                ..process(2000, Some(400), "cargo", "dev", 45.0 + busy, 1200 * MB)
+            },
+        ],
+        power: PowerData {
+            batteries: vec![BatteryData {
+                name: "BAT0".to_string(),
+                capacity: Some(80.0),
+                status: "Discharging".to_string(),
+                energy_now: Some(40.0),
+                energy_full: Some(50.0),
+                power_now: Some(10.0),
+                time_to_empty: Some(4 * 3600),
+                time_to_full: None,
+            }],
+            ac_online: Some(false),
+        },
+        sensors: SensorData {
+            temperatures: vec![TemperatureData {
+                chip: "coretemp".to_string(),
+                device: "hwmon4".to_string(),
+                label: "Package id 0".to_string(),
+                celsius: 55.0,
+                max: Some(80.0),
+                critical: Some(100.0),
+            }],
+            fans: vec![FanData {
+                chip: "thinkpad".to_string(),
+                device: "hwmon2".to_string(),
+                label: "fan1".to_string(),
+                rpm: 2400,
+            }],
+        },
+        cgroups: vec![
+            CgroupData {
+                path: "/system.slice/postgresql.service".to_string(),
+                processes: 2,
+                cpu_usage: 15.5,
+                memory: 1050 * MB,
+                read_per_sec: 64.0 * 1024.0,
+                written_per_sec: 256.0 * 1024.0,
+                ..CgroupData::default()
+            },
+            CgroupData {
+                path: format!("/system.slice/docker-{}.scope", CONTAINER_ID),
+                container: Some(CONTAINER_ID[..12].to_string()),
+                processes: 1,
+                cpu_usage: 45.0 + busy as f64,
+                memory: 1200 * MB,
+                memory_limit: Some(2 * GB),
+                ..CgroupData::default()
+            },
+            CgroupData {
+                path: "/user.slice/user-1000.slice/session-2.scope".to_string(),
+                processes: 1,
+                cpu_usage: 0.5,
+                memory: 8 * MB,
+                ..CgroupData::default()
+            },
+        ],
+        units: vec![
+            UnitData {
+                name: "postgresql.service".to_string(),
+                state: "active".to_string(),
+                sub_state: "running".to_string(),
+                since: Some(1_700_000_000 - 2 * 3600),
+                main_pid: Some(1200),
+                cpu_usage: Some(12.5),
+                memory: Some(900 * MB),
+            },
+            UnitData {
+                name: "backup.service".to_string(),
+                state: "failed".to_string(),
+                sub_state: "failed".to_string(),
+                since: Some(1_700_000_000 - 725),
+                ..UnitData::default()
+            },
+            UnitData {
+                name: "cups.socket".to_string(),
+                state: "inactive".to_string(),
+                sub_state: "dead".to_string(),
+                ..UnitData::default()
+            },
+        ],
+        system: SystemInfoData {
+            uptime: 3 * 86400 + 4 * 3600 + 5 * 60,
+        },
+        timestamp: 1_700_000_000 + step as i64,
+    }
+}
+
+fn process(
+    pid: u32,
+    parent: Option<u32>,
+    name: &str,
+    user: &str,
+    cpu_usage: f32,
+    memory: u64,
+) -> ProcessData {
+    ProcessData {
+        name: name.to_string(),
+        pid,
+        parent,
+        user: user.to_string(),
+        cpu_usage,
+        memory,
+        ..ProcessData::default()
+    }
+}
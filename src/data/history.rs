@@ -0,0 +1,242 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use super::Snapshot;
+use crate::widgets::percentage;
+
+/// Bounded ring buffer of timestamped samples, pruned to a time window
+pub struct Series {
+    /// `(unix seconds, value)`, oldest first
+    samples: VecDeque<(f64, f64)>,
+    window: Duration,
+    /// Refreshes are whole seconds apart, so the window never needs more than one sample a second;
+    /// also bounds samples that arrive with the same timestamp
+    capacity: usize,
+}
+
+impl Series {
+    fn new(window: Duration) -> Self {
+        Self {
+            samples: VecDeque::new(),
+            window,
+            capacity: window.as_secs() as usize + 1,
+        }
+    }
+
+    pub fn push(&mut self, time: f64, value: f64) {
+        self.samples.push_back((time, value));
+
+        while self.samples.len() > self.capacity {
+            self.samples.pop_front();
+        }
+        while let Some((oldest, _)) = self.samples.front() {
+            if time - oldest <= self.window.as_secs_f64() {
+                break;
+            }
+            self.samples.pop_front();
+        }
+    }
+
+    /// Values from oldest to newest
+    pub fn values(&self) -> impl Iterator<Item = f64> + '_ {
+        self.samples.iter().map(|(_, value)| *value)
+    }
+
+    /// `(seconds relative to the newest sample, value)` pairs for charts, oldest first
+    pub fn points(&self) -> Vec<(f64, f64)> {
+        let newest = self.samples.back().map(|(time, _)| *time).unwrap_or(0.0);
+        self.samples
+            .iter()
+            .map(|(time, value)| (time - newest, *value))
+            .collect()
+    }
+
+    /// Time of the newest sample
+    fn newest(&self) -> Option<f64> {
+        self.samples.back().map(|(time, _)| *time)
+    }
+
+    pub fn max(&self) -> f64 {
+        self.values().fold(0.0, f64::max)
+    }
+}
+
+pub struct NetworkSeries {
+    pub received: Series,
+    pub transmitted: Series,
+}
+
+/// Recent samples of everything the graph widgets plot, built up from snapshots
+pub struct History {
+    window: Duration,
+    /// Global CPU usage in percent
+    pub cpu: Series,
+    /// RAM usage in percent
+    pub memory: Series,
+    /// Swap usage in percent
+    pub swap: Series,
+    /// Bytes per second, by interface
+    pub network: BTreeMap<String, NetworkSeries>,
+    /// Bytes per second, summed over all block devices
+    pub disk_read: Series,
+    pub disk_write: Series,
+}
+
+impl History {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            cpu: Series::new(window),
+            memory: Series::new(window),
+            swap: Series::new(window),
+            network: BTreeMap::new(),
+            disk_read: Series::new(window),
+            disk_write: Series::new(window),
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub fn record(&mut self, snapshot: &Snapshot) {
+        let time = snapshot.timestamp as f64;
+        let memory = &snapshot.memory;
+
+        self.cpu.push(time, snapshot.cpu.usage as f64);
+        self.memory
+            .push(time, percentage(memory.used, memory.total));
+        self.swap
+            .push(time, percentage(memory.used_swap, memory.total_swap));
+
+        for network in &snapshot.network {
+            let window = self.window;
+            let series = self
+                .network
+                .entry(network.interface.clone())
+                .or_insert_with(|| NetworkSeries {
+                    received: Series::new(window),
+                    transmitted: Series::new(window),
+                });
+            series.received.push(time, network.received_per_sec);
+            series.transmitted.push(time, network.transmitted_per_sec);
+        }
+        // Interfaces come and go with containers, forget the ones gone for a whole window
+        let window = self.window.as_secs_f64();
+        self.network.retain(|_, series| {
+            series
+                .received
+                .newest()
+                .is_some_and(|newest| time - newest <= window)
+        });
+
+        let (read, write) = snapshot.disk_io_rates();
+        self.disk_read.push(time, read);
+        self.disk_write.push(time, write);
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(5 * 60))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::snapshot::{MemoryData, NetworkData};
+
+    #[test]
+    fn keeps_the_window() {
+        let mut series = Series::new(Duration::from_secs(10));
+        for second in 0..30 {
+            series.push(second as f64, second as f64);
+        }
+        // Both ends of the window are in
+        assert_eq!(
+            series.values().collect::<Vec<_>>(),
+            (19..30).map(f64::from).collect::<Vec<_>>()
+        );
+        assert_eq!(series.points().first(), Some(&(-10.0, 19.0)));
+        assert_eq!(series.max(), 29.0);
+
+        // A gap longer than the window leaves only the new sample
+        series.push(100.0, 1.0);
+        assert_eq!(series.points(), [(0.0, 1.0)]);
+    }
+
+    #[test]
+    fn capacity_follows_the_window() {
+        // Two hours at one sample a second, more than a fixed cap of 3600 would keep
+        let window = Duration::from_secs(2 * 3600);
+        let mut series = Series::new(window);
+        for second in 0..3 * 3600 {
+            series.push(second as f64, 0.0);
+        }
+        assert_eq!(series.values().count(), 2 * 3600 + 1);
+
+        // Samples arriving faster than the clock still can't pile up
+        let mut series = Series::new(Duration::from_secs(10));
+        for _ in 0..100 {
+            series.push(5.0, 1.0);
+        }
+        assert_eq!(series.values().count(), 11);
+    }
+
+    #[test]
+    fn records_snapshots() {
+        let mut history = History::new(Duration::from_secs(60));
+        for (second, usage) in [(0, 25.0), (1, 30.0)] {
+            let mut snapshot = Snapshot {
+                memory: MemoryData {
+                    used: 3,
+                    total: 8,
+                    used_swap: 1,
+                    total_swap: 4,
+                    ..Default::default()
+                },
+                network: vec![NetworkData {
+                    interface: "eth0".to_string(),
+                    ..Default::default()
+                }],
+                timestamp: second,
+                ..Default::default()
+            };
+            snapshot.cpu.usage = usage;
+            history.record(&snapshot);
+        }
+
+        assert_eq!(history.cpu.values().collect::<Vec<_>>(), [25.0, 30.0]);
+        assert_eq!(history.memory.values().next(), Some(37.5));
+        assert_eq!(history.swap.values().next(), Some(25.0));
+        assert_eq!(history.network["eth0"].received.values().count(), 2);
+    }
+
+    #[test]
+    fn forgets_interfaces_gone_for_a_window() {
+        let mut history = History::new(Duration::from_secs(60));
+        let snapshot = |timestamp, interfaces: &[&str]| Snapshot {
+            network: interfaces
+                .iter()
+                .map(|interface| NetworkData {
+                    interface: interface.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            timestamp,
+            ..Default::default()
+        };
+
+        history.record(&snapshot(0, &["eth0", "veth1a2b"]));
+        history.record(&snapshot(60, &["eth0"]));
+        // Still in the window, the chart keeps its tail
+        assert!(history.network.contains_key("veth1a2b"));
+
+        history.record(&snapshot(61, &["eth0", "veth3c4d"]));
+        assert_eq!(
+            history.network.keys().collect::<Vec<_>>(),
+            ["eth0", "veth3c4d"]
+        );
+    }
+}
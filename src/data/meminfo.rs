@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::snapshot::{MemoryDetails, MemoryPressure};
+
+pub const MEMINFO_PATH: &str = "/proc/meminfo";
+pub const PRESSURE_PATH: &str = "/proc/pressure/memory";
+pub const BLOCK_PATH: &str = "/sys/block";
+
+/// The breakdown `free` and `top` show, plus zram usage from `block_root`
+pub fn read_meminfo(path: &Path, block_root: &Path) -> Option<MemoryDetails> {
+    let mut details = parse_meminfo(&fs::read_to_string(path).ok()?)?;
+    (details.zram_original, details.zram_used) = read_zram(block_root);
+    Some(details)
+}
+
+/// https://www.kernel.org/doc/html/latest/filesystems/proc.html#meminfo
+pub fn parse_meminfo(content: &str) -> Option<MemoryDetails> {
+    // `Key:  value kB`, except the HugePages_ counts which have no unit
+    let fields: HashMap<&str, u64> = content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let mut parts = value.split_whitespace();
+            let number: u64 = parts.next()?.parse().ok()?;
+            let bytes = match parts.next() {
+                Some("kB") => number * 1024,
+                _ => number,
+            };
+            Some((key, bytes))
+        })
+        .collect();
+    let field = |key: &str| fields.get(key).copied().unwrap_or(0);
+    let huge_page = field("Hugepagesize");
+
+    Some(MemoryDetails {
+        // Kernels before 3.14 don't report it, free plus what could be dropped comes close
+        available: fields.get("MemAvailable").copied().unwrap_or_else(|| {
+            field("MemFree") + field("Buffers") + field("Cached") + field("SReclaimable")
+        }),
+        free: *fields.get("MemFree")?,
+        cached: field("Cached") + field("SReclaimable"),
+        buffers: field("Buffers"),
+        shared: field("Shmem"),
+        dirty: field("Dirty"),
+        writeback: field("Writeback"),
+        hugepages_total: field("HugePages_Total") * huge_page,
+        hugepages_free: field("HugePages_Free") * huge_page,
+        zswap: field("Zswap"),
+        zswapped: field("Zswapped"),
+        ..MemoryDetails::default()
+    })
+}
+
+/// Original and in-memory size of the data in every zram device, 0 without any
+fn read_zram(block_root: &Path) -> (u64, u64) {
+    let Ok(entries) = fs::read_dir(block_root) else {
+        return (0, 0);
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("zram"))
+        .filter_map(|entry| fs::read_to_string(entry.path().join("mm_stat")).ok())
+        .filter_map(|mm_stat| parse_mm_stat(&mm_stat))
+        .fold(
+            (0, 0),
+            |(original, used), (device_original, device_used)| {
+                (original + device_original, used + device_used)
+            },
+        )
+}
+
+/// `orig_data_size compr_data_size mem_used_total ...`, in bytes
+fn parse_mm_stat(content: &str) -> Option<(u64, u64)> {
+    let fields: Vec<u64> = content
+        .split_whitespace()
+        .take(3)
+        .map(|field| field.parse().ok())
+        .collect::<Option<_>>()?;
+    Some((*fields.first()?, *fields.get(2)?))
+}
+
+/// Pressure stall information, None on kernels without it (before 4.20 or built without PSI)
+pub fn read_pressure(path: &Path) -> Option<MemoryPressure> {
+    parse_pressure(&fs::read_to_string(path).ok()?)
+}
+
+/// `some avg10=0.12 avg60=0.05 avg300=0.01 total=1234` and the same for `full`
+pub fn parse_pressure(content: &str) -> Option<MemoryPressure> {
+    let mut pressure = MemoryPressure::default();
+    let mut found = false;
+    for line in content.lines() {
+        let mut parts = line.split_whitespace();
+        let averages = match parts.next() {
+            Some("some") => [
+                &mut pressure.some_avg10,
+                &mut pressure.some_avg60,
+                &mut pressure.some_avg300,
+            ],
+            Some("full") => [
+                &mut pressure.full_avg10,
+                &mut pressure.full_avg60,
+                &mut pressure.full_avg300,
+            ],
+            _ => continue,
+        };
+        found = true;
+        for part in parts {
+            let Some((key, value)) = part.split_once('=') else {
+                continue;
+            };
+            let index = match key {
+                "avg10" => 0,
+                "avg60" => 1,
+                "avg300" => 2,
+                _ => continue,
+            };
+            *averages[index] = value.parse().unwrap_or(0.0);
+        }
+    }
+    found.then_some(pressure)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEMINFO: &str = "\
+MemTotal:       16303428 kB
+MemFree:         1843212 kB
+MemAvailable:    9876544 kB
+Buffers:          301240 kB
+Cached:          7012344 kB
+SwapCached:        10240 kB
+Shmem:            612300 kB
+SReclaimable:     512000 kB
+Dirty:              1024 kB
+Writeback:             0 kB
+Zswap:             20480 kB
+Zswapped:          81920 kB
+HugePages_Total:       4
+HugePages_Free:        1
+Hugepagesize:       2048 kB
+";
+
+    #[test]
+    fn parses_meminfo() {
+        let details = parse_meminfo(MEMINFO).unwrap();
+        assert_eq!(details.available, 9876544 * 1024);
+        assert_eq!(details.free, 1843212 * 1024);
+        assert_eq!(details.cached, (7012344 + 512000) * 1024);
+        assert_eq!(details.buffers, 301240 * 1024);
+        assert_eq!(details.shared, 612300 * 1024);
+        assert_eq!(details.dirty, 1024 * 1024);
+        assert_eq!(details.hugepages_total, 4 * 2048 * 1024);
+        assert_eq!(details.hugepages_free, 2048 * 1024);
+        assert_eq!(details.zswap, 20480 * 1024);
+        assert_eq!(details.zswapped, 81920 * 1024);
+
+        // Used by programs is what's left after free memory, buffers and cache
+        assert_eq!(
+            details.program_use(16303428 * 1024),
+            (16303428 - 1843212 - 301240 - 7012344 - 512000) * 1024
+        );
+
+        // Old kernels without MemAvailable
+        let old = parse_meminfo("MemFree: 100 kB\nBuffers: 20 kB\nCached: 30 kB\n").unwrap();
+        assert_eq!(old.available, 150 * 1024);
+        assert!(parse_meminfo("").is_none());
+    }
+
+    #[test]
+    fn parses_pressure() {
+        let pressure = parse_pressure(
+            "some avg10=1.50 avg60=0.75 avg300=0.20 total=123456\n\
+             full avg10=0.50 avg60=0.25 avg300=0.00 total=65432\n",
+        )
+        .unwrap();
+        assert_eq!(pressure.some_avg10, 1.5);
+        assert_eq!(pressure.some_avg60, 0.75);
+        assert_eq!(pressure.some_avg300, 0.2);
+        assert_eq!(pressure.full_avg10, 0.5);
+        assert_eq!(pressure.full_avg300, 0.0);
+        assert!(parse_pressure("").is_none());
+    }
+
+    #[test]
+    fn parses_zram_mm_stat() {
+        assert_eq!(
+            parse_mm_stat(
+                "  4096000  1024000  1200000        0  1300000      12      0      0      0\n"
+            ),
+            Some((4096000, 1200000))
+        );
+        assert_eq!(parse_mm_stat("garbage"), None);
+    }
+}
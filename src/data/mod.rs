@@ -1,3 +1,21 @@
+pub mod cgroups;
 pub mod collectors;
+pub mod diskstats;
+#[cfg(test)]
+pub mod fake;
+pub mod history;
+pub mod meminfo;
+pub mod power;
+pub mod process_tree;
+pub mod scheduler;
+pub mod sensors;
+pub mod snapshot;
+pub mod source;
+pub mod sysfs;
+pub mod systemd;
 
-pub use collectors::{SystemData, SystemInfo};
+pub use collectors::SystemInfo;
+pub use history::History;
+pub use scheduler::{DataSource, RefreshScheduler};
+pub use snapshot::Snapshot;
+pub use source::{LiveSource, MetricsSource};
@@ -0,0 +1,210 @@
+use std::fs;
+use std::path::Path;
+
+use super::snapshot::{BatteryData, PowerData};
+use super::sysfs::{self, read_string};
+
+pub const POWER_SUPPLY_PATH: &str = "/sys/class/power_supply";
+
+/// Read every battery and mains adapter under a `power_supply` class directory
+pub fn read_power_supplies(root: &Path) -> PowerData {
+    let mut power = PowerData::default();
+    let Ok(entries) = fs::read_dir(root) else {
+        return power;
+    };
+
+    let mut supplies: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+    supplies.sort();
+
+    for supply in supplies {
+        match read_string(&supply, "type").as_deref() {
+            // Mice, keyboards and the like report scope=Device
+            Some("Battery") if read_string(&supply, "scope").as_deref() != Some("Device") => {
+                power.batteries.push(read_battery(&supply));
+            }
+            Some("Mains") => {
+                let online = read_number(&supply, "online").map(|online| online == 1);
+                power.ac_online = match (power.ac_online, online) {
+                    // Any adapter plugged in counts
+                    (Some(true), _) => Some(true),
+                    (_, Some(online)) => Some(online),
+                    (current, None) => current,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    power
+}
+
+fn read_battery(path: &Path) -> BatteryData {
+    let name = sysfs::file_name(path);
+    let status = read_string(path, "status").unwrap_or_else(|| "Unknown".to_string());
+
+    // Batteries report either energy (µWh, µW) or charge (µAh, µA) plus voltage (µV)
+    let voltage = read_number(path, "voltage_now").map(|microvolts| microvolts as f64 / 1e6);
+    let energy = |energy_file: &str, charge_file: &str| {
+        read_number(path, energy_file)
+            .map(|microwatt_hours| microwatt_hours as f64 / 1e6)
+            .or_else(|| Some(read_number(path, charge_file)? as f64 / 1e6 * voltage?))
+    };
+    let energy_now = energy("energy_now", "charge_now");
+    let energy_full = energy("energy_full", "charge_full");
+    let power_now = read_number(path, "power_now")
+        .map(|microwatts| microwatts as f64 / 1e6)
+        .or_else(|| Some(read_number(path, "current_now")? as f64 / 1e6 * voltage?));
+
+    let capacity = read_number(path, "capacity")
+        .map(|capacity| capacity as f64)
+        .or_else(|| match (energy_now, energy_full) {
+            (Some(now), Some(full)) if full > 0.0 => Some(now / full * 100.0),
+            _ => None,
+        });
+
+    // Prefer what the driver estimates, otherwise work it out from the draw
+    let hours_to = |remaining: Option<f64>| match (remaining, power_now) {
+        (Some(remaining), Some(power)) if power > 0.0 => Some((remaining / power * 3600.0) as u64),
+        _ => None,
+    };
+    let (time_to_empty, time_to_full) = match status.as_str() {
+        "Discharging" => (
+            read_number(path, "time_to_empty_now").or_else(|| hours_to(energy_now)),
+            None,
+        ),
+        "Charging" => (
+            None,
+            read_number(path, "time_to_full_now").or_else(|| {
+                hours_to(
+                    energy_full
+                        .zip(energy_now)
+                        .map(|(full, now)| (full - now).max(0.0)),
+                )
+            }),
+        ),
+        _ => (None, None),
+    };
+
+    BatteryData {
+        name,
+        capacity,
+        status,
+        energy_now,
+        energy_full,
+        power_now,
+        time_to_empty,
+        time_to_full,
+    }
+}
+
+fn read_number(path: &Path, file: &str) -> Option<u64> {
+    // Some drivers report negative currents while discharging
+    sysfs::read_number::<i64>(path, file).map(i64::unsigned_abs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fake::FakeSysfs;
+
+    #[test]
+    fn energy_battery_discharging() {
+        let sysfs = FakeSysfs::new("power-energy");
+        sysfs.files(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("capacity", "80"),
+                ("energy_now", "40000000"),
+                ("energy_full", "50000000"),
+                ("power_now", "10000000"),
+            ],
+        );
+        sysfs.files("AC", &[("type", "Mains"), ("online", "0")]);
+
+        let power = read_power_supplies(&sysfs.root);
+        assert_eq!(power.ac_online, Some(false));
+        assert_eq!(power.batteries.len(), 1);
+
+        let battery = &power.batteries[0];
+        assert_eq!(battery.name, "BAT0");
+        assert_eq!(battery.status, "Discharging");
+        assert_eq!(battery.capacity, Some(80.0));
+        assert_eq!(battery.energy_now, Some(40.0));
+        assert_eq!(battery.power_now, Some(10.0));
+        // 40 Wh at 10 W
+        assert_eq!(battery.time_to_empty, Some(4 * 3600));
+        assert_eq!(battery.time_to_full, None);
+    }
+
+    #[test]
+    fn charge_battery_charging() {
+        let sysfs = FakeSysfs::new("power-charge");
+        sysfs.files(
+            "BAT1",
+            &[
+                ("type", "Battery"),
+                ("status", "Charging"),
+                ("charge_now", "2000000"),
+                ("charge_full", "4000000"),
+                ("current_now", "-1000000"),
+                ("voltage_now", "10000000"),
+            ],
+        );
+        sysfs.files("ADP1", &[("type", "Mains"), ("online", "1")]);
+
+        let power = read_power_supplies(&sysfs.root);
+        assert_eq!(power.ac_online, Some(true));
+
+        let battery = &power.batteries[0];
+        // 2 Ah * 10 V, capacity derived from energy
+        assert_eq!(battery.energy_now, Some(20.0));
+        assert_eq!(battery.energy_full, Some(40.0));
+        assert_eq!(battery.capacity, Some(50.0));
+        assert_eq!(battery.power_now, Some(10.0));
+        // 20 Wh missing at 10 W
+        assert_eq!(battery.time_to_full, Some(2 * 3600));
+        assert_eq!(battery.time_to_empty, None);
+    }
+
+    #[test]
+    fn driver_estimate_wins() {
+        let sysfs = FakeSysfs::new("power-estimate");
+        sysfs.files(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("capacity", "50"),
+                ("energy_now", "40000000"),
+                ("power_now", "10000000"),
+                ("time_to_empty_now", "1234"),
+            ],
+        );
+
+        let battery = &read_power_supplies(&sysfs.root).batteries[0];
+        assert_eq!(battery.time_to_empty, Some(1234));
+    }
+
+    #[test]
+    fn skips_peripherals_and_unknown_supplies() {
+        let sysfs = FakeSysfs::new("power-peripherals");
+        sysfs.files(
+            "hidpp_battery_0",
+            &[("type", "Battery"), ("scope", "Device"), ("capacity", "30")],
+        );
+        sysfs.files("ucsi-source-psy-1", &[("type", "USB"), ("online", "1")]);
+
+        let power = read_power_supplies(&sysfs.root);
+        assert!(power.batteries.is_empty());
+        assert_eq!(power.ac_online, None);
+    }
+
+    #[test]
+    fn missing_directory_is_empty() {
+        let power = read_power_supplies(Path::new("/nonexistent/power_supply"));
+        assert!(power.batteries.is_empty());
+        assert_eq!(power.ac_online, None);
+    }
+}
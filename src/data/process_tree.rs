@@ -0,0 +1,173 @@
+use std::collections::{HashMap, HashSet};
+
+use super::snapshot::ProcessData;
+
+/// A process with its descendants; the totals cover the whole subtree
+pub struct ProcessNode {
+    pub process: ProcessData,
+    pub children: Vec<ProcessNode>,
+    pub total_cpu: f32,
+    pub total_memory: u64,
+}
+
+/// Nest processes under their parents; orphans and init-like processes become roots
+pub fn build_tree(processes: Vec<ProcessData>) -> Vec<ProcessNode> {
+    let pids: HashSet<u32> = processes.iter().map(|process| process.pid).collect();
+
+    let mut roots = Vec::new();
+    let mut children: HashMap<u32, Vec<ProcessData>> = HashMap::new();
+    for process in processes {
+        match process.parent {
+            Some(parent) if parent != process.pid && pids.contains(&parent) => {
+                children.entry(parent).or_default().push(process)
+            }
+            _ => roots.push(process),
+        }
+    }
+
+    let mut nodes: Vec<ProcessNode> = roots
+        .into_iter()
+        .map(|process| build_node(process, &mut children))
+        .collect();
+    // Whatever is left has a parent chain that loops back on itself, e.g. after PID reuse,
+    // so no root reaches it; break each loop at its lowest PID
+    while let Some(parent) = children.keys().min().copied() {
+        for process in children.remove(&parent).unwrap_or_default() {
+            nodes.push(build_node(process, &mut children));
+        }
+    }
+    nodes
+}
+
+/// A node still waiting for some of its children to be built
+struct Pending {
+    process: ProcessData,
+    unbuilt: std::vec::IntoIter<ProcessData>,
+    built: Vec<ProcessNode>,
+}
+
+impl Pending {
+    fn new(process: ProcessData, children: &mut HashMap<u32, Vec<ProcessData>>) -> Self {
+        Self {
+            unbuilt: children
+                .remove(&process.pid)
+                .unwrap_or_default()
+                .into_iter(),
+            process,
+            built: Vec::new(),
+        }
+    }
+
+    fn finish(self) -> ProcessNode {
+        let nodes = self.built;
+        ProcessNode {
+            total_cpu: self.process.cpu_usage
+                + nodes.iter().map(|node| node.total_cpu).sum::<f32>(),
+            total_memory: self.process.memory
+                + nodes.iter().map(|node| node.total_memory).sum::<u64>(),
+            process: self.process,
+            children: nodes,
+        }
+    }
+}
+
+// Depth first with a stack of its own, a long parent chain would overflow the thread's
+fn build_node(process: ProcessData, children: &mut HashMap<u32, Vec<ProcessData>>) -> ProcessNode {
+    let mut current = Pending::new(process, children);
+    let mut parents = Vec::new();
+    loop {
+        if let Some(child) = current.unbuilt.next() {
+            let child = Pending::new(child, children);
+            parents.push(std::mem::replace(&mut current, child));
+            continue;
+        }
+
+        let node = current.finish();
+        match parents.pop() {
+            Some(mut parent) => {
+                parent.built.push(node);
+                current = parent;
+            }
+            None => return node,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, parent: Option<u32>, cpu_usage: f32, memory: u64) -> ProcessData {
+        ProcessData {
+            pid,
+            parent,
+            cpu_usage,
+            memory,
+            ..ProcessData::default()
+        }
+    }
+
+    /// `pid(child child ...)` for each root, to compare shapes at a glance
+    fn shape(nodes: &[ProcessNode]) -> String {
+        nodes
+            .iter()
+            .map(|node| {
+                if node.children.is_empty() {
+                    node.process.pid.to_string()
+                } else {
+                    format!("{}({})", node.process.pid, shape(&node.children))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn nests_children_and_sums_subtrees() {
+        let tree = build_tree(vec![
+            process(1, None, 0.5, 10),
+            process(20, Some(10), 4.0, 200),
+            process(10, Some(1), 1.0, 100),
+            process(21, Some(10), 2.5, 300),
+            process(30, Some(1), 0.0, 50),
+        ]);
+
+        assert_eq!(shape(&tree), "1(10(20 21) 30)");
+        assert_eq!(tree[0].total_cpu, 8.0);
+        assert_eq!(tree[0].total_memory, 660);
+        let shell = &tree[0].children[0];
+        assert_eq!(shell.total_cpu, 7.5);
+        assert_eq!(shell.total_memory, 600);
+    }
+
+    #[test]
+    fn orphans_become_roots() {
+        // The parent of 50 exited before the snapshot, kthreadd's parent is 0, and
+        // the idle task on some systems claims to be its own parent
+        let tree = build_tree(vec![
+            process(1, None, 0.0, 0),
+            process(2, Some(0), 0.0, 0),
+            process(50, Some(49), 1.0, 10),
+            process(51, Some(50), 1.0, 10),
+            process(60, Some(60), 0.0, 0),
+        ]);
+
+        assert_eq!(shape(&tree), "1 2 50(51) 60");
+        assert_eq!(tree[2].total_cpu, 2.0);
+    }
+
+    #[test]
+    fn breaks_parent_loops() {
+        // 70 and 71 claim each other as parent after their real parents exited and
+        // the PIDs were reused, and 72 hangs off the loop
+        let tree = build_tree(vec![
+            process(1, None, 0.0, 0),
+            process(71, Some(70), 1.0, 10),
+            process(70, Some(71), 1.0, 10),
+            process(72, Some(71), 1.0, 10),
+        ]);
+
+        assert_eq!(shape(&tree), "1 71(70 72)");
+        assert_eq!(tree[1].total_memory, 30);
+    }
+}
@@ -0,0 +1,186 @@
+use std::time::{Duration, Instant};
+
+use super::SystemInfo;
+
+/// Parts of the system that can be refreshed independently
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataSource {
+    Cpu,
+    Memory,
+    Processes,
+    Disks,
+    Networks,
+    Power,
+    Sensors,
+    /// systemd unit state, only when units are configured
+    Units,
+    /// Nothing to refresh: read live (uptime, load, time) but still needs a redraw
+    Clock,
+}
+
+impl DataSource {
+    pub const ALL: [DataSource; 9] = [
+        DataSource::Cpu,
+        DataSource::Memory,
+        DataSource::Processes,
+        DataSource::Disks,
+        DataSource::Networks,
+        DataSource::Power,
+        DataSource::Sensors,
+        DataSource::Units,
+        DataSource::Clock,
+    ];
+}
+
+struct ScheduledSource {
+    source: DataSource,
+    interval: Duration,
+    last_refresh: Option<Instant>,
+}
+
+/// Refreshes each data source on its own interval; sources nobody asked for are never refreshed
+#[derive(Default)]
+pub struct RefreshScheduler {
+    entries: Vec<ScheduledSource>,
+}
+
+impl RefreshScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Refresh every source on the same interval, for outputs that export the whole snapshot
+    pub fn all(interval: Duration) -> Self {
+        let mut scheduler = Self::new();
+        for source in DataSource::ALL {
+            scheduler.every(source, interval);
+        }
+        scheduler
+    }
+
+    /// Refresh `source` at least every `interval` (the shortest request wins)
+    pub fn every(&mut self, source: DataSource, interval: Duration) {
+        match self.entries.iter_mut().find(|entry| entry.source == source) {
+            Some(entry) => entry.interval = entry.interval.min(interval),
+            None => self.entries.push(ScheduledSource {
+                source,
+                interval,
+                last_refresh: None,
+            }),
+        }
+    }
+
+    /// Refresh every source whose interval has elapsed, returns true if any was refreshed
+    pub fn refresh_due(&mut self, system_info: &mut SystemInfo) -> bool {
+        let due = self.take_due(Instant::now());
+        for source in &due {
+            system_info.refresh_source(*source);
+        }
+        !due.is_empty()
+    }
+
+    /// Sources due at `now`, marked as refreshed then
+    fn take_due(&mut self, now: Instant) -> Vec<DataSource> {
+        self.entries
+            .iter_mut()
+            .filter(|entry| match entry.last_refresh {
+                Some(last) => now.duration_since(last) >= entry.interval,
+                None => true,
+            })
+            .map(|entry| {
+                entry.last_refresh = Some(now);
+                entry.source
+            })
+            .collect()
+    }
+
+    /// Refresh every scheduled source right away
+    pub fn refresh_all(&mut self, system_info: &mut SystemInfo) {
+        let now = Instant::now();
+        for entry in &mut self.entries {
+            system_info.refresh_source(entry.source);
+            entry.last_refresh = Some(now);
+        }
+    }
+
+    /// How often `source` is refreshed, None when it isn't
+    #[cfg(test)]
+    pub fn interval(&self, source: DataSource) -> Option<Duration> {
+        self.entries
+            .iter()
+            .find(|entry| entry.source == source)
+            .map(|entry| entry.interval)
+    }
+
+    /// Time left until the next source is due
+    pub fn until_next(&self) -> Duration {
+        self.until_next_at(Instant::now())
+    }
+
+    fn until_next_at(&self, now: Instant) -> Duration {
+        self.entries
+            .iter()
+            .map(|entry| match entry.last_refresh {
+                Some(last) => entry.interval.saturating_sub(now.duration_since(last)),
+                None => Duration::ZERO,
+            })
+            .min()
+            .unwrap_or(Duration::from_secs(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn refreshes_each_source_on_its_interval() {
+        let mut scheduler = RefreshScheduler::new();
+        scheduler.every(DataSource::Cpu, SECOND);
+        scheduler.every(DataSource::Disks, 5 * SECOND);
+        let start = Instant::now();
+
+        // Everything is due the first time round
+        assert_eq!(
+            scheduler.take_due(start),
+            [DataSource::Cpu, DataSource::Disks]
+        );
+        assert_eq!(scheduler.take_due(start), []);
+        assert_eq!(scheduler.until_next_at(start), SECOND);
+
+        assert_eq!(scheduler.take_due(start + SECOND), [DataSource::Cpu]);
+        assert_eq!(
+            scheduler.until_next_at(start + SECOND + SECOND / 4),
+            SECOND * 3 / 4
+        );
+        assert_eq!(
+            scheduler.take_due(start + 5 * SECOND),
+            [DataSource::Cpu, DataSource::Disks]
+        );
+    }
+
+    #[test]
+    fn shortest_interval_wins() {
+        let mut scheduler = RefreshScheduler::new();
+        scheduler.every(DataSource::Memory, 5 * SECOND);
+        scheduler.every(DataSource::Memory, 2 * SECOND);
+        scheduler.every(DataSource::Memory, 10 * SECOND);
+        assert_eq!(scheduler.interval(DataSource::Memory), Some(2 * SECOND));
+        assert_eq!(scheduler.interval(DataSource::Cpu), None);
+    }
+
+    #[test]
+    fn nothing_scheduled() {
+        let mut scheduler = RefreshScheduler::new();
+        assert_eq!(scheduler.take_due(Instant::now()), []);
+        // Still wakes up now and then
+        assert_eq!(scheduler.until_next(), SECOND);
+
+        let all = RefreshScheduler::all(3 * SECOND);
+        assert!(DataSource::ALL
+            .iter()
+            .all(|source| all.interval(*source) == Some(3 * SECOND)));
+    }
+}
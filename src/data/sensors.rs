@@ -0,0 +1,176 @@
+use std::fs;
+use std::path::Path;
+
+use super::snapshot::{FanData, SensorData, TemperatureData};
+use super::sysfs::{self, read_number, read_string};
+
+pub const HWMON_PATH: &str = "/sys/class/hwmon";
+
+/// Read every temperature and fan channel under a `hwmon` class directory
+pub fn read_hwmon(root: &Path) -> SensorData {
+    let mut sensors = SensorData::default();
+    let Ok(entries) = fs::read_dir(root) else {
+        return sensors;
+    };
+
+    let mut chips: Vec<_> = entries.flatten().map(|entry| entry.path()).collect();
+    chips.sort_by_key(|path| chip_index(path));
+
+    for chip_path in chips {
+        let device = sysfs::file_name(&chip_path);
+        let chip = read_string(&chip_path, "name").unwrap_or_else(|| device.clone());
+
+        for channel in channels(&chip_path, "temp") {
+            // Values are in millidegrees Celsius
+            let Some(input) = read_number::<i64>(&chip_path, &format!("temp{}_input", channel))
+            else {
+                continue;
+            };
+            let threshold = |name: &str| {
+                read_number::<i64>(&chip_path, &format!("temp{}_{}", channel, name))
+                    .map(|value| value as f64 / 1000.0)
+                    // Drivers without a real limit often report 0 or absurd values
+                    .filter(|value| *value > 0.0 && *value < 200.0)
+            };
+            sensors.temperatures.push(TemperatureData {
+                chip: chip.clone(),
+                device: device.clone(),
+                label: read_label(&chip_path, "temp", channel),
+                celsius: input as f64 / 1000.0,
+                max: threshold("max"),
+                critical: threshold("crit"),
+            });
+        }
+
+        for channel in channels(&chip_path, "fan") {
+            if let Some(rpm) = read_number::<i64>(&chip_path, &format!("fan{}_input", channel)) {
+                sensors.fans.push(FanData {
+                    chip: chip.clone(),
+                    device: device.clone(),
+                    label: read_label(&chip_path, "fan", channel),
+                    rpm: rpm.max(0) as u64,
+                });
+            }
+        }
+    }
+
+    sensors
+}
+
+// hwmon10 sorts after hwmon9
+fn chip_index(path: &Path) -> (u64, String) {
+    let name = sysfs::file_name(path);
+    let index = name
+        .trim_start_matches(|c: char| !c.is_ascii_digit())
+        .parse()
+        .unwrap_or(u64::MAX);
+    (index, name)
+}
+
+/// Channel numbers with an `<kind>N_input` file, in order
+fn channels(chip_path: &Path, kind: &str) -> Vec<u32> {
+    let Ok(entries) = fs::read_dir(chip_path) else {
+        return vec![];
+    };
+    let mut channels: Vec<u32> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            name.strip_prefix(kind)?
+                .strip_suffix("_input")?
+                .parse()
+                .ok()
+        })
+        .collect();
+    channels.sort_unstable();
+    channels
+}
+
+fn read_label(chip_path: &Path, kind: &str, channel: u32) -> String {
+    read_string(chip_path, &format!("{}{}_label", kind, channel))
+        .unwrap_or_else(|| format!("{}{}", kind, channel))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fake::FakeSysfs;
+
+    #[test]
+    fn temperatures_with_thresholds() {
+        let sysfs = FakeSysfs::new("hwmon-temps");
+        sysfs.files(
+            "hwmon3",
+            &[
+                ("name", "coretemp"),
+                ("temp1_input", "54000"),
+                ("temp1_label", "Package id 0"),
+                ("temp1_max", "80000"),
+                ("temp1_crit", "100000"),
+                ("temp10_input", "49500"),
+                ("temp10_label", "Core 8"),
+                ("temp2_input", "51000"),
+                ("temp2_label", "Core 0"),
+            ],
+        );
+        sysfs.files(
+            "hwmon10",
+            &[
+                ("name", "nvme"),
+                ("temp1_input", "38850"),
+                ("temp1_crit", "0"),
+            ],
+        );
+
+        let sensors = read_hwmon(&sysfs.root);
+        let labels: Vec<_> = sensors
+            .temperatures
+            .iter()
+            .map(|temperature| (temperature.chip.as_str(), temperature.label.as_str()))
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                ("coretemp", "Package id 0"),
+                ("coretemp", "Core 0"),
+                ("coretemp", "Core 8"),
+                ("nvme", "temp1"),
+            ]
+        );
+
+        let package = &sensors.temperatures[0];
+        assert_eq!(package.device, "hwmon3");
+        assert_eq!(package.celsius, 54.0);
+        assert_eq!(package.max, Some(80.0));
+        assert_eq!(package.critical, Some(100.0));
+
+        let nvme = &sensors.temperatures[3];
+        assert_eq!(nvme.device, "hwmon10");
+        assert_eq!(nvme.celsius, 38.85);
+        // A zero limit means the drive doesn't report one
+        assert_eq!(nvme.critical, None);
+    }
+
+    #[test]
+    fn fans() {
+        let sysfs = FakeSysfs::new("hwmon-fans");
+        sysfs.files(
+            "hwmon1",
+            &[
+                ("name", "thinkpad"),
+                ("fan1_input", "2450"),
+                ("fan2_input", "0"),
+                ("fan2_label", "GPU"),
+            ],
+        );
+
+        let sensors = read_hwmon(&sysfs.root);
+        assert!(sensors.temperatures.is_empty());
+        assert_eq!(sensors.fans.len(), 2);
+        assert_eq!(sensors.fans[0].device, "hwmon1");
+        assert_eq!(sensors.fans[0].label, "fan1");
+        assert_eq!(sensors.fans[0].rpm, 2450);
+        assert_eq!(sensors.fans[1].label, "GPU");
+        assert_eq!(sensors.fans[1].rpm, 0);
+    }
+}
@@ -0,0 +1,391 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use super::process_tree::{self, ProcessNode};
+
+/// Everything known about the system at one point in time, detached from sysinfo
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct Snapshot {
+    pub cpu: CpuData,
+    pub memory: MemoryData,
+    pub disks: Vec<DiskData>,
+    pub network: Vec<NetworkData>,
+    pub processes: Vec<ProcessData>,
+    pub power: PowerData,
+    pub sensors: SensorData,
+    /// cgroups with processes in them, empty without cgroup v2
+    #[serde(default)]
+    pub cgroups: Vec<CgroupData>,
+    /// The configured systemd units, in config order
+    #[serde(default)]
+    pub units: Vec<UnitData>,
+    pub system: SystemInfoData,
+    pub timestamp: i64,
+}
+
+impl Snapshot {
+    /// The `count` processes using the most CPU, lowest PID first on a tie
+    pub fn top_processes(&self, count: usize) -> Vec<ProcessData> {
+        let mut processes = self.processes.clone();
+        processes.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage).then(a.pid.cmp(&b.pid)));
+        processes.truncate(count);
+        processes
+    }
+
+    /// Usage per container or unit, busiest first
+    pub fn cgroup_groups(&self) -> Vec<CgroupGroup> {
+        let mut groups: Vec<CgroupGroup> = Vec::new();
+        for cgroup in &self.cgroups {
+            let name = cgroup.group();
+            let index = match groups.iter().position(|group| group.name == name) {
+                Some(index) => index,
+                None => {
+                    groups.push(CgroupGroup {
+                        name: name.to_string(),
+                        container: cgroup.container.is_some(),
+                        memory_limit: Some(0),
+                        ..CgroupGroup::default()
+                    });
+                    groups.len() - 1
+                }
+            };
+            let group = &mut groups[index];
+            group.processes += cgroup.processes;
+            group.cpu_usage += cgroup.cpu_usage;
+            group.memory += cgroup.memory;
+            group.memory_limit = group
+                .memory_limit
+                .zip(cgroup.memory_limit)
+                .map(|(a, b)| a + b);
+            group.read_per_sec += cgroup.read_per_sec;
+            group.written_per_sec += cgroup.written_per_sec;
+        }
+        groups.sort_by(|a, b| {
+            b.cpu_usage
+                .total_cmp(&a.cpu_usage)
+                .then(b.memory.cmp(&a.memory))
+        });
+        groups
+    }
+
+    /// Processes nested under their parents
+    pub fn process_tree(&self) -> Vec<ProcessNode> {
+        process_tree::build_tree(self.processes.clone())
+    }
+
+    /// Read and write bytes per second, summed over block devices
+    pub fn disk_io_rates(&self) -> (f64, f64) {
+        // A device mounted in several places reports the same counters each time
+        let mut seen = HashSet::new();
+        self.disks
+            .iter()
+            .filter(|disk| seen.insert(disk.name.as_str()))
+            .fold((0.0, 0.0), |(read, written), disk| {
+                (read + disk.read_per_sec, written + disk.written_per_sec)
+            })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct CpuData {
+    pub usage: f32,
+    /// Logical cores
+    pub count: usize,
+    pub physical_count: Option<usize>,
+    pub brand: String,
+    pub vendor: String,
+    pub cores: Vec<CoreData>,
+    pub load_average: LoadAverage,
+    /// CPUs worth of time our cgroup may use; `usage` is then relative to it
+    #[serde(default)]
+    pub limit: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct CoreData {
+    pub name: String,
+    pub usage: f32,
+    /// MHz
+    pub frequency: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct LoadAverage {
+    pub one: f64,
+    pub five: f64,
+    pub fifteen: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct MemoryData {
+    pub used: u64,
+    pub total: u64,
+    pub used_swap: u64,
+    pub total_swap: u64,
+    /// From /proc/meminfo, None elsewhere or under a cgroup memory limit
+    #[serde(default)]
+    pub details: Option<MemoryDetails>,
+    #[serde(default)]
+    pub pressure: Option<MemoryPressure>,
+}
+
+/// Where the memory goes, in bytes
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq)]
+pub struct MemoryDetails {
+    /// What can still be handed out without swapping
+    pub available: u64,
+    pub free: u64,
+    /// Page cache and reclaimable slab, `shared` included
+    pub cached: u64,
+    pub buffers: u64,
+    /// tmpfs and shared memory segments
+    pub shared: u64,
+    /// Waiting to be written back to disk, and being written
+    pub dirty: u64,
+    pub writeback: u64,
+    pub hugepages_total: u64,
+    pub hugepages_free: u64,
+    /// Compressed size of the pages in zswap, and their original size
+    pub zswap: u64,
+    pub zswapped: u64,
+    /// Memory used by zram devices, and the data they hold
+    pub zram_used: u64,
+    pub zram_original: u64,
+}
+
+impl MemoryDetails {
+    /// Memory neither free nor cache, the way `free` computes "used"
+    pub fn program_use(&self, total: u64) -> u64 {
+        total
+            .saturating_sub(self.free)
+            .saturating_sub(self.buffers)
+            .saturating_sub(self.cached)
+    }
+}
+
+/// Percent of time tasks were stalled waiting for memory, averaged over 10s, 60s and 300s
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq)]
+pub struct MemoryPressure {
+    /// At least one task stalled
+    pub some_avg10: f64,
+    pub some_avg60: f64,
+    pub some_avg300: f64,
+    /// Every task stalled at once
+    pub full_avg10: f64,
+    pub full_avg60: f64,
+    pub full_avg300: f64,
+}
+
+/// Rates are bytes or requests per second over the last refresh, for this partition alone
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct DiskData {
+    pub name: String,
+    pub total: u64,
+    pub available: u64,
+    pub mount_point: String,
+    /// Filesystem type, e.g. ext4 or vfat
+    pub file_system: String,
+    pub removable: bool,
+    pub read_only: bool,
+    pub inodes_total: u64,
+    pub inodes_free: u64,
+    pub read_per_sec: f64,
+    pub written_per_sec: f64,
+    pub read_iops: f64,
+    pub write_iops: f64,
+    /// Share of the refresh interval the device spent serving requests
+    pub busy_percent: f64,
+}
+
+/// Rates are bytes per second over the last refresh, everything else counts since boot
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct NetworkData {
+    pub interface: String,
+    pub received_per_sec: f64,
+    pub transmitted_per_sec: f64,
+    pub total_received: u64,
+    pub total_transmitted: u64,
+    pub packets_received: u64,
+    pub packets_transmitted: u64,
+    pub errors_received: u64,
+    pub errors_transmitted: u64,
+    pub dropped_received: u64,
+    pub dropped_transmitted: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct ProcessData {
+    pub name: String,
+    pub pid: u32,
+    pub parent: Option<u32>,
+    pub user: String,
+    pub cpu_usage: f32,
+    pub memory: u64,
+    /// cgroup v2 path, e.g. `/system.slice/nginx.service`
+    #[serde(default)]
+    pub cgroup: Option<String>,
+    /// Short ID of the container the process runs in
+    #[serde(default)]
+    pub container: Option<String>,
+}
+
+/// Usage of one cgroup, its children included; rates are per second over the last refresh
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct CgroupData {
+    pub path: String,
+    pub container: Option<String>,
+    /// Listed processes in it
+    pub processes: usize,
+    /// Percent of one core, like process CPU usage
+    pub cpu_usage: f64,
+    pub memory: u64,
+    pub memory_limit: Option<u64>,
+    pub read_per_sec: f64,
+    pub written_per_sec: f64,
+}
+
+impl CgroupData {
+    /// What usage is grouped by: the container, or else the unit, e.g. `nginx.service`
+    pub fn group(&self) -> &str {
+        match &self.container {
+            Some(container) => container,
+            None => self
+                .path
+                .rsplit('/')
+                .find(|part| !part.is_empty())
+                .unwrap_or("/"),
+        }
+    }
+}
+
+/// cgroups summed per container or unit
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct CgroupGroup {
+    pub name: String,
+    pub container: bool,
+    pub processes: usize,
+    pub cpu_usage: f64,
+    pub memory: u64,
+    /// Only when every cgroup in the group has one
+    pub memory_limit: Option<u64>,
+    pub read_per_sec: f64,
+    pub written_per_sec: f64,
+}
+
+/// A systemd unit; CPU and memory are those of its main process
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct UnitData {
+    /// As configured, e.g. `nginx` or `backup.timer`
+    pub name: String,
+    /// active, inactive, failed, activating, deactivating, not-found or unknown
+    pub state: String,
+    /// Unit type specific, e.g. running, exited or dead
+    pub sub_state: String,
+    /// Unix time of the last state change
+    pub since: Option<i64>,
+    pub main_pid: Option<u32>,
+    pub cpu_usage: Option<f32>,
+    pub memory: Option<u64>,
+}
+
+impl UnitData {
+    pub fn is_failed(&self) -> bool {
+        self.state == "failed"
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct PowerData {
+    pub batteries: Vec<BatteryData>,
+    /// None when the machine has no mains adapter to report
+    pub ac_online: Option<bool>,
+}
+
+/// Energy in Wh, power in W, times in seconds
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct BatteryData {
+    pub name: String,
+    /// Percent
+    pub capacity: Option<f64>,
+    /// Charging, Discharging, Full, Not charging or Unknown
+    pub status: String,
+    pub energy_now: Option<f64>,
+    pub energy_full: Option<f64>,
+    pub power_now: Option<f64>,
+    pub time_to_empty: Option<u64>,
+    pub time_to_full: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct SensorData {
+    pub temperatures: Vec<TemperatureData>,
+    pub fans: Vec<FanData>,
+}
+
+/// Degrees Celsius; thresholds only when the driver reports them
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct TemperatureData {
+    /// hwmon chip name, e.g. coretemp, k10temp or nvme
+    pub chip: String,
+    /// hwmon directory, e.g. hwmon3; tells apart two chips with the same name
+    #[serde(default)]
+    pub device: String,
+    pub label: String,
+    pub celsius: f64,
+    pub max: Option<f64>,
+    pub critical: Option<f64>,
+}
+
+impl TemperatureData {
+    /// The temperature to worry about, critical if known
+    pub fn limit(&self) -> Option<f64> {
+        self.critical.or(self.max)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct FanData {
+    pub chip: String,
+    #[serde(default)]
+    pub device: String,
+    pub label: String,
+    pub rpm: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct SystemInfoData {
+    pub uptime: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fake;
+
+    fn pids(processes: &[ProcessData]) -> Vec<u32> {
+        processes.iter().map(|process| process.pid).collect()
+    }
+
+    #[test]
+    fn top_processes_busiest_first() {
+        let mut snapshot = fake::snapshot(0);
+        // Same usage as postgres, listed before it
+        snapshot.processes.insert(
+            0,
+            ProcessData {
+                pid: 3000,
+                cpu_usage: 12.5,
+                ..ProcessData::default()
+            },
+        );
+
+        assert_eq!(pids(&snapshot.top_processes(3)), [2000, 1200, 3000]);
+        assert_eq!(pids(&snapshot.top_processes(1)), [2000]);
+        assert!(snapshot.top_processes(0).is_empty());
+        // Asking for more than there are lists them all
+        assert_eq!(
+            pids(&snapshot.top_processes(10)),
+            [2000, 1200, 3000, 1210, 400, 1]
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use anyhow::{bail, Result};
+use std::time::Duration;
+
+use super::{RefreshScheduler, Snapshot, SystemInfo};
+
+/// Where the TUI gets its snapshots from: the live system or a recording
+pub trait MetricsSource {
+    /// Refresh or advance whatever is due, returns true when there is a new snapshot
+    fn update(&mut self) -> bool;
+
+    /// Bring everything up to date right away
+    fn refresh_all(&mut self);
+
+    fn snapshot(&self) -> Snapshot;
+
+    /// Time left until `update` has something new
+    fn until_next(&self) -> Duration;
+
+    /// Send SIGTERM, or SIGKILL when `force` is set
+    fn signal_process(&self, pid: u32, _force: bool) -> Result<()> {
+        bail!("Cannot signal PID {}, not a live system", pid)
+    }
+
+    /// Playback controls, for sources that have them
+    fn playback(&mut self) -> Option<&mut dyn Playback> {
+        None
+    }
+
+    /// Extra text for the status bar
+    fn status(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Moving around in recorded snapshots
+pub trait Playback {
+    fn set_paused(&mut self, paused: bool);
+
+    /// Move by `samples` samples, negative goes back
+    fn step(&mut self, samples: isize);
+
+    /// Move by `seconds` of recorded time, negative goes back
+    fn seek(&mut self, seconds: i64);
+
+    /// Double or halve the playback speed
+    fn change_speed(&mut self, faster: bool);
+
+    /// Samples up to and including the current one, no older than `window`
+    fn recent(&self, window: Duration) -> &[Snapshot];
+}
+
+/// The machine we're running on, refreshed by a scheduler
+pub struct LiveSource {
+    system_info: SystemInfo,
+    scheduler: RefreshScheduler,
+}
+
+impl LiveSource {
+    pub fn new(system_info: SystemInfo, scheduler: RefreshScheduler) -> Self {
+        Self {
+            system_info,
+            scheduler,
+        }
+    }
+}
+
+impl MetricsSource for LiveSource {
+    fn update(&mut self) -> bool {
+        self.scheduler.refresh_due(&mut self.system_info)
+    }
+
+    fn refresh_all(&mut self) {
+        self.scheduler.refresh_all(&mut self.system_info);
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        self.system_info.snapshot()
+    }
+
+    fn until_next(&self) -> Duration {
+        self.scheduler.until_next()
+    }
+
+    fn signal_process(&self, pid: u32, force: bool) -> Result<()> {
+        self.system_info.signal_process(pid, force)
+    }
+}
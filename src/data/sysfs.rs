@@ -0,0 +1,24 @@
+// Small readers for the one-value-per-file layout of /sys and /proc
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Trimmed content of `dir/file`, None when missing or empty
+pub fn read_string(dir: &Path, file: &str) -> Option<String> {
+    fs::read_to_string(dir.join(file))
+        .ok()
+        .map(|content| content.trim().to_string())
+        .filter(|content| !content.is_empty())
+}
+
+/// `dir/file` parsed as a number, None when missing or not a number (e.g. `max`)
+pub fn read_number<T: FromStr>(dir: &Path, file: &str) -> Option<T> {
+    read_string(dir, file)?.parse().ok()
+}
+
+/// Last component of a path, e.g. `hwmon3` or `BAT0`
+pub fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
@@ -0,0 +1,331 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::snapshot::{ProcessData, UnitData};
+
+/// Properties asked from `systemctl show`; the monotonic timestamp needs no `--timestamp=unix`,
+/// which systemd before v251 rejects
+const PROPERTIES: &str =
+    "Id,Names,LoadState,ActiveState,SubState,MainPID,StateChangeTimestampMonotonic";
+
+/// Where unit state comes from, `systemctl` on a real host
+pub trait UnitSource: Send + Sync {
+    /// `systemctl show` output for `units`: `Key=Value` lines, a blank line between units
+    fn show(&self, units: &[String]) -> Result<String>;
+}
+
+pub struct Systemctl;
+
+impl UnitSource for Systemctl {
+    fn show(&self, units: &[String]) -> Result<String> {
+        let output = Command::new("systemctl")
+            .args(["show", "--no-pager", "--property", PROPERTIES, "--"])
+            .args(units)
+            .output()
+            .context("Cannot run systemctl")?;
+        if !output.status.success() {
+            bail!(
+                "systemctl show failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// Asks systemctl on its own thread, a slow D-Bus would otherwise stall the refresh loop
+pub struct UnitWatcher {
+    source: Arc<dyn UnitSource>,
+    names: Vec<String>,
+    units: Arc<Mutex<Vec<UnitData>>>,
+    running: Arc<AtomicBool>,
+    // A failing systemctl is reported once, not on every refresh
+    failing: Arc<AtomicBool>,
+}
+
+impl UnitWatcher {
+    /// Every unit starts out `unknown` until systemctl answers
+    pub fn new(source: Arc<dyn UnitSource>, names: Vec<String>) -> Self {
+        let units = names.iter().map(|name| unknown_unit(name)).collect();
+        Self {
+            source,
+            names,
+            units: Arc::new(Mutex::new(units)),
+            running: Arc::new(AtomicBool::new(false)),
+            failing: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Ask systemctl again, unless nothing is configured or the last query is still running
+    pub fn refresh(&self) {
+        if self.names.is_empty() || self.running.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let source = Arc::clone(&self.source);
+        let names = self.names.clone();
+        let units = Arc::clone(&self.units);
+        let running = Arc::clone(&self.running);
+        let failing = Arc::clone(&self.failing);
+        std::thread::spawn(move || {
+            // On failure the units keep their last known state
+            match read_units(source.as_ref(), &names, monotonic_epoch()) {
+                Ok(read) => {
+                    *units
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner()) = read;
+                    failing.store(false, Ordering::Release);
+                }
+                Err(error) => {
+                    if !failing.swap(true, Ordering::AcqRel) {
+                        eprintln!("⚠️  Cannot read systemd units: {:#}", error);
+                    }
+                }
+            }
+            running.store(false, Ordering::Release);
+        });
+    }
+
+    /// Latest known state of the configured units
+    pub fn units(&self) -> Vec<UnitData> {
+        self.units
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
+
+/// Unix time in microseconds when the monotonic clock systemd stamps state changes with was 0
+pub fn monotonic_epoch() -> i64 {
+    // SAFETY: `now` is a plain struct clock_gettime fills in
+    let mut now: libc::timespec = unsafe { std::mem::zeroed() };
+    if unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) } != 0 {
+        return 0;
+    }
+    let monotonic = now.tv_sec as i64 * 1_000_000 + now.tv_nsec as i64 / 1_000;
+    chrono::Utc::now().timestamp_micros() - monotonic
+}
+
+fn unknown_unit(name: &str) -> UnitData {
+    UnitData {
+        name: name.to_string(),
+        state: "unknown".to_string(),
+        ..UnitData::default()
+    }
+}
+
+/// State of each unit in `units`, in the same order; `unknown` when systemctl has nothing on it.
+/// `epoch` is [`monotonic_epoch`], to turn state change timestamps into Unix time
+pub fn read_units(source: &dyn UnitSource, units: &[String], epoch: i64) -> Result<Vec<UnitData>> {
+    let output = source.show(units)?;
+    let blocks: Vec<HashMap<&str, &str>> = output
+        .split("\n\n")
+        .filter(|block| !block.trim().is_empty())
+        .map(|block| {
+            block
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .collect()
+        })
+        .collect();
+
+    Ok(units
+        .iter()
+        .map(|name| {
+            // Blocks come back under the unit's own name, `nginx` is `nginx.service`
+            // and an alias answers with the unit it points to
+            let full_name = if name.contains('.') {
+                name.clone()
+            } else {
+                format!("{}.service", name)
+            };
+            let block = blocks.iter().find(|properties| {
+                properties.get("Id") == Some(&full_name.as_str())
+                    || properties.get("Names").is_some_and(|names| {
+                        names.split_whitespace().any(|alias| alias == full_name)
+                    })
+            });
+            match block {
+                Some(properties) => parse_unit(name, properties, epoch),
+                None => unknown_unit(name),
+            }
+        })
+        .collect())
+}
+
+fn parse_unit(name: &str, properties: &HashMap<&str, &str>, epoch: i64) -> UnitData {
+    let property = |key: &str| properties.get(key).copied().unwrap_or_default();
+
+    // Unknown units still get a block, reported as inactive
+    let state = match property("LoadState") {
+        "not-found" => "not-found",
+        _ => property("ActiveState"),
+    };
+    // Microseconds on the monotonic clock, 0 when the unit never changed state
+    let since = property("StateChangeTimestampMonotonic")
+        .parse::<i64>()
+        .ok()
+        .filter(|&micros| micros > 0)
+        .map(|micros| (epoch + micros) / 1_000_000);
+
+    UnitData {
+        name: name.to_string(),
+        state: state.to_string(),
+        sub_state: property("SubState").to_string(),
+        since,
+        main_pid: property("MainPID").parse().ok().filter(|&pid| pid != 0),
+        ..UnitData::default()
+    }
+}
+
+/// Fill in CPU and memory of each unit's main process
+pub fn join_processes(units: &[UnitData], processes: &[ProcessData]) -> Vec<UnitData> {
+    units
+        .iter()
+        .map(|unit| {
+            let process = unit
+                .main_pid
+                .and_then(|pid| processes.iter().find(|process| process.pid == pid));
+            UnitData {
+                cpu_usage: process.map(|process| process.cpu_usage),
+                memory: process.map(|process| process.memory),
+                ..unit.clone()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Canned `systemctl show` output, or a failure when None
+    struct FakeSystemctl(Option<&'static str>);
+
+    impl UnitSource for FakeSystemctl {
+        fn show(&self, _units: &[String]) -> Result<String> {
+            match self.0 {
+                Some(output) => Ok(output.to_string()),
+                None => bail!("Cannot run systemctl"),
+            }
+        }
+    }
+
+    // Booted at Unix time 1_700_000_000
+    const EPOCH: i64 = 1_700_000_000_000_000;
+
+    fn units(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_systemctl_show() {
+        // Not in the order asked: matched up by Id, or by alias through Names
+        let systemctl = FakeSystemctl(Some(
+            "Id=backup.service\nNames=backup.service\nLoadState=loaded\nActiveState=failed\n\
+             SubState=failed\nMainPID=0\nStateChangeTimestampMonotonic=90000000\n\n\
+             Id=nginx.service\nNames=nginx.service www.service\nLoadState=loaded\n\
+             ActiveState=active\nSubState=running\nMainPID=1200\n\
+             StateChangeTimestampMonotonic=7500000000\n\n\
+             Id=nope.service\nNames=nope.service\nLoadState=not-found\nActiveState=inactive\n\
+             SubState=dead\nMainPID=0\nStateChangeTimestampMonotonic=0\n",
+        ));
+        let units = read_units(
+            &systemctl,
+            &units(&[
+                "nginx",
+                "backup.service",
+                "nope.service",
+                "www.service",
+                "gone.timer",
+            ]),
+            EPOCH,
+        )
+        .unwrap();
+
+        assert_eq!(units.len(), 5);
+        assert_eq!(units[0].name, "nginx");
+        assert_eq!(units[0].state, "active");
+        assert_eq!(units[0].sub_state, "running");
+        assert_eq!(units[0].main_pid, Some(1200));
+        assert_eq!(units[0].since, Some(1_700_007_500));
+        assert!(units[1].is_failed());
+        assert_eq!(units[1].main_pid, None);
+        assert_eq!(units[1].since, Some(1_700_000_090));
+        assert_eq!(units[2].state, "not-found");
+        assert_eq!(units[2].since, None);
+        assert_eq!(units[3].name, "www.service");
+        assert_eq!(units[3].main_pid, Some(1200));
+        assert_eq!(units[4].state, "unknown");
+    }
+
+    #[test]
+    fn error_without_systemd() {
+        assert!(read_units(&FakeSystemctl(None), &units(&["nginx.service"]), EPOCH).is_err());
+    }
+
+    /// Wait for the watcher's systemctl thread to finish
+    fn settle(watcher: &UnitWatcher) {
+        while watcher.running.load(Ordering::Acquire) {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn watcher_keeps_units_when_systemctl_fails() {
+        let watcher = UnitWatcher::new(Arc::new(FakeSystemctl(None)), units(&["nginx.service"]));
+        watcher.refresh();
+        settle(&watcher);
+
+        let units = watcher.units();
+        assert_eq!(units.len(), 1);
+        assert_eq!(units[0].name, "nginx.service");
+        assert_eq!(units[0].state, "unknown");
+        assert!(watcher.failing.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn watcher_reads_units_in_the_background() {
+        let systemctl = FakeSystemctl(Some(
+            "Id=nginx.service\nNames=nginx.service\nLoadState=loaded\nActiveState=active\n\
+             SubState=running\nMainPID=1200\nStateChangeTimestampMonotonic=0\n",
+        ));
+        let watcher = UnitWatcher::new(Arc::new(systemctl), units(&["nginx"]));
+        assert_eq!(watcher.units()[0].state, "unknown");
+
+        watcher.refresh();
+        settle(&watcher);
+        assert_eq!(watcher.units()[0].state, "active");
+        assert_eq!(watcher.units()[0].main_pid, Some(1200));
+    }
+
+    #[test]
+    fn main_process_usage() {
+        let units = [
+            UnitData {
+                name: "nginx.service".to_string(),
+                main_pid: Some(1200),
+                ..UnitData::default()
+            },
+            UnitData {
+                name: "gone.service".to_string(),
+                main_pid: Some(999),
+                ..UnitData::default()
+            },
+        ];
+        let processes = [ProcessData {
+            pid: 1200,
+            cpu_usage: 2.5,
+            memory: 64 * 1024 * 1024,
+            ..ProcessData::default()
+        }];
+
+        let joined = join_processes(&units, &processes);
+        assert_eq!(joined[0].cpu_usage, Some(2.5));
+        assert_eq!(joined[0].memory, Some(64 * 1024 * 1024));
+        assert_eq!(joined[1].cpu_usage, None);
+        assert_eq!(joined[1].memory, None);
+    }
+}
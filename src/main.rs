@@ -1,65 +1,250 @@
+mod alerts;
 mod config;
 mod data;
+mod record;
+mod remote;
 mod render;
 mod widgets;
 
 use anyhow::Result;
-use clap::Parser;
-use std::time::Duration;
+use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
 #[command(version, about = "A system monitor written in Rust")]
 struct Cli {
-    #[arg(short, long, default_value = "config.toml")]
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(short, long, global = true, default_value = "config.toml")]
     config: String,
 
     #[arg(short, long, help = "Output JSON format for shell script")]
     json: bool,
 
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "json",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Emit N JSON samples, then exit"
+    )]
+    count: Option<u64>,
+
+    #[arg(
+        long,
+        requires = "json",
+        conflicts_with = "count",
+        help = "Emit a single JSON sample"
+    )]
+    once: bool,
+
+    #[arg(
+        long,
+        requires = "json",
+        overrides_with = "compact",
+        help = "Indent the JSON output"
+    )]
+    pretty: bool,
+
+    #[arg(
+        long,
+        requires = "json",
+        overrides_with = "pretty",
+        help = "One JSON object per line, even when the config sets pretty"
+    )]
+    compact: bool,
+
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "json",
+        help = "Number of processes in the JSON output"
+    )]
+    processes: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "FIELDS",
+        value_delimiter = ',',
+        requires = "json",
+        help = "Only output these JSON fields, e.g. cpu.usage,memory"
+    )]
+    include: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "FIELDS",
+        value_delimiter = ',',
+        requires = "json",
+        help = "Leave these JSON fields out, e.g. processes,cpu.cores"
+    )]
+    exclude: Vec<String>,
+
     #[arg(long, help = "Use TUI interface (ratatui)")]
     tui: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["json", "serve"],
+        help = "Play a log written by `record` back in the TUI"
+    )]
+    replay: Option<std::path::PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "ADDR",
+        conflicts_with_all = ["json", "tui"],
+        help = "Serve Prometheus metrics over HTTP, e.g. 127.0.0.1:9101"
+    )]
+    serve: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with_all = ["json", "tui", "serve", "replay"],
+        help = "Feed a status bar using its protocol"
+    )]
+    bar: Option<render::bar::BarProtocol>,
+
+    #[arg(
+        long,
+        value_name = "WIDGETS",
+        value_delimiter = ',',
+        requires = "bar",
+        help = "Widgets to show in the bar, e.g. cpu,memory (defaults to the config layout)"
+    )]
+    widgets: Vec<config::WidgetType>,
+}
+
+impl Cli {
+    /// `--pretty` or `--compact`, whichever came last; None keeps the config's choice
+    fn pretty(&self) -> Option<bool> {
+        match (self.pretty, self.compact) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Append snapshots to a newline-delimited JSON log, without any display
+    Record(record::RecordOptions),
+    /// Stream snapshots to dashboards over TCP or a Unix socket
+    Agent(remote::AgentOptions),
+    /// Watch several agents, one summary row per host
+    Dashboard(remote::DashboardOptions),
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    match (cli.json, cli.tui) {
-        (true, _) => {
-            // JSON mode for scripts
-            launch_json_output(1).await?;
+    if let Some(command) = &cli.command {
+        let config = config::load_config(&cli.config).await?;
+        return match command {
+            Command::Record(options) => record::record(&config, options).await,
+            Command::Agent(options) => remote::agent(&config, options).await,
+            Command::Dashboard(options) => {
+                let hosts = options
+                    .hosts
+                    .iter()
+                    .cloned()
+                    .map(remote::client::Host::connect)
+                    .collect();
+                render::dashboard::launch_dashboard(&config, hosts).await
+            }
+        };
+    }
+
+    if let Some(protocol) = cli.bar {
+        let config = config::load_config(&cli.config).await?;
+        return launch_bar(&config, protocol, &cli.widgets).await;
+    }
+
+    match (&cli.serve, cli.json, cli.tui) {
+        (Some(addr), _, _) => {
+            // Exporter mode for Prometheus scrapers
+            let config = config::load_config(&cli.config).await?;
+            render::prometheus::serve(addr, &config).await?;
+        }
+        (None, true, _) => {
+            // JSON mode for scripts, command line options win over the config
+            let mut config = config::load_config(&cli.config).await?;
+            let json = &mut config.json;
+            if let Some(pretty) = cli.pretty() {
+                json.pretty = pretty;
+            }
+            if let Some(processes) = cli.processes {
+                json.processes = processes;
+            }
+            if !cli.include.is_empty() {
+                json.include = cli.include.clone();
+            }
+            if !cli.exclude.is_empty() {
+                json.exclude = cli.exclude.clone();
+            }
+            let count = if cli.once { Some(1) } else { cli.count };
+            launch_json_output(&config, count).await?;
         }
-        (false, true) => {
-            // NEW: TUI mode with ratatui
-            launch_tui(&cli.config).await?;
+        (None, false, tui) if tui || cli.replay.is_some() => {
+            // Full screen interactive interface
+            launch_tui(&cli.config, cli.replay.as_deref()).await?;
         }
-        (false, false) => {
-            // Legacy terminal mode (your current simple render)
+        (None, false, _) => {
+            // Plain text redrawn in the terminal on every update
             let config = config::load_config(&cli.config).await?;
-            let update_interval = config.update_interval;
-            launch_terminal(update_interval).await?;
+            launch_terminal(&config).await?;
         }
     }
 
     Ok(())
 }
 
-async fn launch_json_output(update_interval: u64) -> Result<()> {
-    let mut system_info = data::SystemInfo::new();
+/// Print a JSON sample every `update_interval`, stopping after `count` samples if given
+async fn launch_json_output(config: &config::Config, count: Option<u64>) -> Result<()> {
+    render::json::validate(&config.json)?;
 
+    let mut scheduler = data::RefreshScheduler::all(std::time::Duration::from_secs(
+        config.update_interval.max(1),
+    ));
+    let mut system_info = data::SystemInfo::primed(config, &mut scheduler).await;
+
+    let mut emitted = 0;
     loop {
-        system_info.refresh();
-        let system_data = data::SystemData::from(&system_info);
-        let json_output = serde_json::to_string(&system_data)?;
+        let json_output = render::json::encode(&system_info.snapshot(), &config.json)?;
         println!("{}", json_output);
-
         std::io::Write::flush(&mut std::io::stdout())?;
-        tokio::time::sleep(Duration::from_secs(update_interval)).await;
+
+        emitted += 1;
+        if count.is_some_and(|count| emitted >= count) {
+            return Ok(());
+        }
+
+        while !scheduler.refresh_due(&mut system_info) {
+            tokio::time::sleep(scheduler.until_next()).await;
+        }
     }
 }
 
-async fn launch_terminal(update_interval: u64) -> Result<()> {
-    let mut system_info = data::SystemInfo::new();
+async fn launch_terminal(config: &config::Config) -> Result<()> {
+    let update_interval = config.update_interval;
+    let grid = widgets::WidgetGrid::from_config(&config.widgets)?;
+    let mut system_info = data::SystemInfo::from_config(config);
+    let mut history = data::History::new(config.history_window());
+    let mut alerts = alerts::AlertMonitor::new(&config.alerts)?;
+    let template = config
+        .template
+        .as_deref()
+        .map(render::template::Template::parse)
+        .transpose()?;
+    let mut scheduler = match &template {
+        Some(template) => template.scheduler(update_interval),
+        None => grid.scheduler(update_interval),
+    };
+    alerts::schedule(&config.alerts, &mut scheduler, update_interval);
     render::clear_screen()?;
     println!(
         "🚀 Rust Conky System Monitor - Update every {}s - Ctrl+C to stop",
@@ -68,20 +253,105 @@ async fn launch_terminal(update_interval: u64) -> Result<()> {
     println!();
 
     loop {
-        system_info.refresh();
-        render::draw(&system_info)?;
-        tokio::time::sleep(Duration::from_secs(update_interval)).await;
+        if scheduler.refresh_due(&mut system_info) {
+            let snapshot = system_info.snapshot();
+            history.record(&snapshot);
+            alerts.check(&snapshot);
+            match &template {
+                Some(template) => render::simple::draw_template(template, &snapshot)?,
+                None => render::draw(&grid, &snapshot, &history, &alerts)?,
+            }
+        }
+        tokio::time::sleep(scheduler.until_next()).await;
     }
 }
 
-// NEW: TUI mode function
-async fn launch_tui(config_path: &str) -> Result<()> {
-    // Load config to get update interval
+async fn launch_bar(
+    config: &config::Config,
+    protocol: render::bar::BarProtocol,
+    widget_types: &[config::WidgetType],
+) -> Result<()> {
+    let grid = if widget_types.is_empty() {
+        widgets::WidgetGrid::from_config(&config.widgets)?
+    } else {
+        // Command line widgets go left to right in the order given
+        let configs: Vec<config::WidgetConfig> = widget_types
+            .iter()
+            .enumerate()
+            .map(|(index, widget_type)| config::WidgetConfig {
+                widget_type: *widget_type,
+                position: [0, index as u16],
+                update_interval: None,
+            })
+            .collect();
+        widgets::WidgetGrid::from_config(&configs)?
+    };
+    let mut system_info = data::SystemInfo::from_config(config);
+    let mut history = data::History::new(config.history_window());
+    let mut alerts = alerts::AlertMonitor::new(&config.alerts)?;
+    let mut scheduler = grid.scheduler(config.update_interval);
+    alerts::schedule(&config.alerts, &mut scheduler, config.update_interval);
+
+    render::bar::start(protocol)?;
+    loop {
+        if scheduler.refresh_due(&mut system_info) {
+            let snapshot = system_info.snapshot();
+            history.record(&snapshot);
+            alerts.check(&snapshot);
+            render::bar::draw(protocol, &grid, &snapshot, &history, &alerts)?;
+        }
+        tokio::time::sleep(scheduler.until_next()).await;
+    }
+}
+
+/// Run the ratatui interface until the user quits
+async fn launch_tui(config_path: &str, replay: Option<&std::path::Path>) -> Result<()> {
+    // Load config to get update interval and widget layout
     let config = config::load_config(config_path).await?;
-    let update_interval = config.update_interval;
+    let grid = widgets::WidgetGrid::from_config(&config.widgets)?;
 
-    // Launch TUI using the new render::tui module
-    render::tui::launch_tui(update_interval).await?;
+    // Live data, or a recording when replaying
+    let source: Box<dyn data::MetricsSource> = match replay {
+        Some(path) => Box::new(record::replay::ReplaySource::open(path)?),
+        None => {
+            let mut scheduler = grid.scheduler(config.update_interval);
+            alerts::schedule(&config.alerts, &mut scheduler, config.update_interval);
+            Box::new(data::LiveSource::new(
+                data::SystemInfo::from_config(&config),
+                scheduler,
+            ))
+        }
+    };
+
+    render::tui::launch_tui(&config, grid, source).await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pretty_or_compact() {
+        let pretty = |args: &[&str]| {
+            Cli::try_parse_from(["rust-conky", "--json"].iter().chain(args))
+                .unwrap()
+                .pretty()
+        };
+        assert_eq!(pretty(&[]), None);
+        assert_eq!(pretty(&["--pretty"]), Some(true));
+        assert_eq!(pretty(&["--compact"]), Some(false));
+        // The last one given wins
+        assert_eq!(pretty(&["--pretty", "--compact"]), Some(false));
+        assert_eq!(pretty(&["--compact", "--pretty"]), Some(true));
+    }
+
+    #[test]
+    fn one_output_mode() {
+        let parse = |args: &[&str]| Cli::try_parse_from(["rust-conky"].iter().chain(args));
+        assert!(parse(&["--serve", "127.0.0.1:9101"]).is_ok());
+        assert!(parse(&["--serve", "127.0.0.1:9101", "--tui"]).is_err());
+        assert!(parse(&["--serve", "127.0.0.1:9101", "--json"]).is_err());
+    }
+}
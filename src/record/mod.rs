@@ -0,0 +1,320 @@
+// Record mode - append snapshots to a newline-delimited JSON log with rotation
+pub mod replay;
+
+use anyhow::{bail, Context, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::{Config, IntervalArg};
+use crate::data::{RefreshScheduler, Snapshot, SystemInfo};
+
+/// `record` subcommand options
+#[derive(Debug, Clone, clap::Args)]
+pub struct RecordOptions {
+    /// Log file, one JSON snapshot per line
+    #[arg(value_name = "FILE")]
+    pub path: PathBuf,
+
+    #[command(flatten)]
+    pub interval: IntervalArg,
+
+    /// Rotate once the log reaches this size, e.g. 512K, 10M or 1G
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_size: Option<u64>,
+
+    /// Rotate once the log covers this much time, e.g. 30m, 12h or 1d
+    #[arg(long, value_name = "AGE", value_parser = parse_age)]
+    pub max_age: Option<Duration>,
+
+    /// Rotated logs to keep next to the current one (FILE.1 is the newest)
+    #[arg(long, value_name = "N", default_value_t = 5)]
+    pub keep: usize,
+
+    /// Busiest processes stored per sample
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    pub processes: usize,
+}
+
+/// Sample the system until interrupted, appending every sample to the log
+pub async fn record(config: &Config, options: &RecordOptions) -> Result<()> {
+    let interval = options.interval.seconds(config);
+    let mut log = RecordLog::open(options)?;
+    let mut scheduler = RefreshScheduler::all(Duration::from_secs(interval));
+    let mut system_info = SystemInfo::primed(config, &mut scheduler).await;
+
+    eprintln!(
+        "⏺  Recording to {} every {}s - Ctrl+C to stop",
+        options.path.display(),
+        interval
+    );
+    loop {
+        let snapshot = system_info.snapshot();
+        log.append(&Snapshot {
+            processes: snapshot.top_processes(options.processes),
+            ..snapshot
+        })?;
+
+        while !scheduler.refresh_due(&mut system_info) {
+            tokio::time::sleep(scheduler.until_next()).await;
+        }
+    }
+}
+
+/// The current log file plus what's needed to decide when to rotate it
+struct RecordLog {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    /// Timestamp of the first sample in the current file
+    started: Option<i64>,
+    max_size: Option<u64>,
+    max_age: Option<Duration>,
+    keep: usize,
+}
+
+impl RecordLog {
+    fn open(options: &RecordOptions) -> Result<Self> {
+        let (file, size) = open_append(&options.path)?;
+        Ok(Self {
+            path: options.path.clone(),
+            file,
+            size,
+            started: first_timestamp(&options.path),
+            max_size: options.max_size,
+            max_age: options.max_age,
+            keep: options.keep,
+        })
+    }
+
+    fn append(&mut self, snapshot: &Snapshot) -> Result<()> {
+        let mut line = serde_json::to_string(snapshot)?;
+        line.push('\n');
+
+        let too_big = self
+            .max_size
+            .is_some_and(|max_size| self.size + line.len() as u64 > max_size);
+        let too_old = match (self.max_age, self.started) {
+            (Some(max_age), Some(started)) => {
+                snapshot.timestamp - started >= max_age.as_secs() as i64
+            }
+            _ => false,
+        };
+        // A single sample bigger than max_size still gets a file of its own
+        if self.size > 0 && (too_big || too_old) {
+            self.rotate()?;
+        }
+
+        // One write per line so readers never see half a sample
+        self.file
+            .write_all(line.as_bytes())
+            .with_context(|| format!("Cannot write to {}", self.path.display()))?;
+        self.size += line.len() as u64;
+        self.started.get_or_insert(snapshot.timestamp);
+        Ok(())
+    }
+
+    /// FILE -> FILE.1 -> FILE.2 ..., dropping whatever falls past `keep`
+    fn rotate(&mut self) -> Result<()> {
+        if self.keep == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let _ = fs::remove_file(rotated_path(&self.path, self.keep));
+            for index in (1..self.keep).rev() {
+                let from = rotated_path(&self.path, index);
+                if from.exists() {
+                    fs::rename(&from, rotated_path(&self.path, index + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        }
+
+        let (file, size) = open_append(&self.path)?;
+        self.file = file;
+        self.size = size;
+        self.started = None;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> Result<(File, u64)> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Cannot open {}", path.display()))?;
+    let size = file.metadata()?.len();
+    Ok((file, size))
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// When an existing log was started, so age based rotation survives restarts
+fn first_timestamp(path: &Path) -> Option<i64> {
+    let mut line = String::new();
+    BufReader::new(File::open(path).ok()?)
+        .read_line(&mut line)
+        .ok()?;
+    let snapshot: Snapshot = serde_json::from_str(&line).ok()?;
+    Some(snapshot.timestamp)
+}
+
+/// Bytes with an optional K, M or G suffix (powers of 1024)
+fn parse_size(value: &str) -> Result<u64> {
+    let value = value.trim();
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((index, 'K' | 'k')) => (&value[..index], 1 << 10),
+        Some((index, 'M' | 'm')) => (&value[..index], 1 << 20),
+        Some((index, 'G' | 'g')) => (&value[..index], 1 << 30),
+        _ => (value, 1),
+    };
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("invalid size `{}`, expected e.g. 512K or 10M", value))?;
+    if number == 0 {
+        bail!("size must be greater than zero");
+    }
+    number
+        .checked_mul(multiplier)
+        .with_context(|| format!("size `{}` is too large", value))
+}
+
+/// Seconds with an optional s, m, h or d suffix
+fn parse_age(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((index, 's')) => (&value[..index], 1),
+        Some((index, 'm')) => (&value[..index], 60),
+        Some((index, 'h')) => (&value[..index], 3600),
+        Some((index, 'd')) => (&value[..index], 86400),
+        _ => (value, 1),
+    };
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("invalid age `{}`, expected e.g. 30m or 12h", value))?;
+    if number == 0 {
+        bail!("age must be greater than zero");
+    }
+    // Compared against i64 timestamps when rotating
+    let seconds = number
+        .checked_mul(multiplier)
+        .filter(|seconds| i64::try_from(*seconds).is_ok())
+        .with_context(|| format!("age `{}` is too large", value))?;
+    Ok(Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(name: &str) -> RecordOptions {
+        let dir =
+            std::env::temp_dir().join(format!("rust-conky-record-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        RecordOptions {
+            path: dir.join("conky.ndjson"),
+            interval: IntervalArg { interval: None },
+            max_size: None,
+            max_age: None,
+            keep: 2,
+            processes: 10,
+        }
+    }
+
+    fn sample(timestamp: i64) -> Snapshot {
+        Snapshot {
+            timestamp,
+            ..Snapshot::default()
+        }
+    }
+
+    fn timestamps(path: &Path) -> Vec<i64> {
+        fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .map(|line| serde_json::from_str::<Snapshot>(line).unwrap().timestamp)
+            .collect()
+    }
+
+    #[test]
+    fn rotates_by_size_and_keeps_the_newest() {
+        let mut options = options("size");
+        let line_size = serde_json::to_string(&sample(1)).unwrap().len() as u64 + 1;
+        options.max_size = Some(line_size * 2);
+
+        let mut log = RecordLog::open(&options).unwrap();
+        for timestamp in 1..=7 {
+            log.append(&sample(timestamp)).unwrap();
+        }
+
+        assert_eq!(timestamps(&options.path), [7]);
+        assert_eq!(timestamps(&rotated_path(&options.path, 1)), [5, 6]);
+        assert_eq!(timestamps(&rotated_path(&options.path, 2)), [3, 4]);
+        assert!(!rotated_path(&options.path, 3).exists());
+        fs::remove_dir_all(options.path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn rotates_by_age_across_restarts() {
+        let mut options = options("age");
+        options.max_age = Some(Duration::from_secs(60));
+
+        let mut log = RecordLog::open(&options).unwrap();
+        log.append(&sample(1000)).unwrap();
+        log.append(&sample(1030)).unwrap();
+        drop(log);
+
+        // The reopened log still knows its first sample is from 1000
+        let mut log = RecordLog::open(&options).unwrap();
+        log.append(&sample(1059)).unwrap();
+        log.append(&sample(1060)).unwrap();
+
+        assert_eq!(
+            timestamps(&rotated_path(&options.path, 1)),
+            [1000, 1030, 1059]
+        );
+        assert_eq!(timestamps(&options.path), [1060]);
+        fs::remove_dir_all(options.path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(parse_size("4096").unwrap(), 4096);
+        assert_eq!(parse_size("512K").unwrap(), 512 * 1024);
+        assert_eq!(parse_size("10M").unwrap(), 10 * 1024 * 1024);
+        assert_eq!(parse_size(" 2g ").unwrap(), 2 << 30);
+        assert!(parse_size("0").is_err());
+        assert!(parse_size("ten").is_err());
+        assert!(parse_size("-1K").is_err());
+        assert!(parse_size("K").is_err());
+        assert_eq!(parse_size("18446744073709551615").unwrap(), u64::MAX);
+        assert_eq!(
+            parse_size("18446744073709551615G").unwrap_err().to_string(),
+            "size `18446744073709551615G` is too large"
+        );
+        assert!(parse_size("99999999999999999999").is_err());
+    }
+
+    #[test]
+    fn ages() {
+        assert_eq!(parse_age("45").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_age("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_age("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_age("12h").unwrap(), Duration::from_secs(12 * 3600));
+        assert_eq!(parse_age("1d").unwrap(), Duration::from_secs(86400));
+        assert!(parse_age("1w").is_err());
+        assert!(parse_age("0m").is_err());
+        assert_eq!(
+            parse_age("999999999999999999d").unwrap_err().to_string(),
+            "age `999999999999999999d` is too large"
+        );
+        // Fits a u64 but not the i64 timestamps it is compared with
+        assert!(parse_age("18446744073709551615").is_err());
+    }
+}
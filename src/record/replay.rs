@@ -0,0 +1,254 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::data::source::{MetricsSource, Playback};
+use crate::data::Snapshot;
+
+const MIN_SPEED: f64 = 0.25;
+const MAX_SPEED: f64 = 64.0;
+
+/// Plays back a log written by `record`, following the recorded timestamps
+pub struct ReplaySource {
+    samples: Vec<Snapshot>,
+    position: usize,
+    /// Recorded time being shown, moves with the wall clock times `speed`
+    clock: f64,
+    last_tick: Instant,
+    speed: f64,
+    paused: bool,
+}
+
+impl ReplaySource {
+    pub fn open(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Cannot read recording {}", path.display()))?;
+        Self::from_log(&content).with_context(|| format!("Cannot replay {}", path.display()))
+    }
+
+    pub fn from_log(content: &str) -> Result<Self> {
+        // A recording cut off mid-write ends in half a line, skip anything unreadable
+        let mut samples: Vec<Snapshot> = content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        if samples.is_empty() {
+            bail!("No snapshots found");
+        }
+        // Rotated logs concatenated out of order still play back in time order
+        samples.sort_by_key(|sample| sample.timestamp);
+
+        Ok(Self {
+            clock: samples[0].timestamp as f64,
+            samples,
+            position: 0,
+            last_tick: Instant::now(),
+            speed: 1.0,
+            paused: false,
+        })
+    }
+
+    fn current(&self) -> &Snapshot {
+        &self.samples[self.position]
+    }
+
+    fn at_end(&self) -> bool {
+        self.position + 1 >= self.samples.len()
+    }
+
+    /// Jump to a sample and restart the clock from its timestamp
+    fn go_to(&mut self, position: usize) {
+        self.position = position.min(self.samples.len() - 1);
+        self.clock = self.current().timestamp as f64;
+        self.last_tick = Instant::now();
+    }
+}
+
+impl MetricsSource for ReplaySource {
+    fn update(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_tick).as_secs_f64();
+        self.last_tick = now;
+        if self.paused || self.at_end() {
+            return false;
+        }
+
+        self.clock += elapsed * self.speed;
+        // Latest sample not in the future of the replay clock
+        let ahead = self.samples[self.position + 1..]
+            .iter()
+            .take_while(|sample| sample.timestamp as f64 <= self.clock)
+            .count();
+        self.position += ahead;
+        ahead > 0
+    }
+
+    fn refresh_all(&mut self) {}
+
+    fn snapshot(&self) -> Snapshot {
+        self.current().clone()
+    }
+
+    fn until_next(&self) -> Duration {
+        if self.paused || self.at_end() {
+            return Duration::from_millis(250);
+        }
+        let next = self.samples[self.position + 1].timestamp as f64;
+        Duration::from_secs_f64(((next - self.clock) / self.speed).max(0.0))
+    }
+
+    fn playback(&mut self) -> Option<&mut dyn Playback> {
+        Some(self)
+    }
+
+    fn status(&self) -> Option<String> {
+        let time = chrono::DateTime::from_timestamp(self.current().timestamp, 0)
+            .map(|time| {
+                time.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        let state = if self.paused {
+            "PAUSED"
+        } else if self.at_end() {
+            "END"
+        } else {
+            "PLAYING"
+        };
+        Some(format!(
+            "REPLAY {} {} {}/{} {}x",
+            state,
+            time,
+            self.position + 1,
+            self.samples.len(),
+            self.speed
+        ))
+    }
+}
+
+impl Playback for ReplaySource {
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.last_tick = Instant::now();
+    }
+
+    fn step(&mut self, samples: isize) {
+        self.go_to(self.position.saturating_add_signed(samples));
+    }
+
+    fn seek(&mut self, seconds: i64) {
+        let target = self.current().timestamp + seconds;
+        let position = if seconds < 0 {
+            // First sample at or after the target
+            self.samples
+                .partition_point(|sample| sample.timestamp < target)
+        } else {
+            // Last sample at or before the target
+            self.samples
+                .partition_point(|sample| sample.timestamp <= target)
+                .saturating_sub(1)
+        };
+        self.go_to(position);
+    }
+
+    fn change_speed(&mut self, faster: bool) {
+        let speed = if faster {
+            self.speed * 2.0
+        } else {
+            self.speed / 2.0
+        };
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    fn recent(&self, window: Duration) -> &[Snapshot] {
+        let oldest = self.current().timestamp - window.as_secs() as i64;
+        let start =
+            self.samples[..self.position].partition_point(|sample| sample.timestamp < oldest);
+        &self.samples[start..=self.position]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(timestamps: &[i64]) -> String {
+        timestamps
+            .iter()
+            .map(|&timestamp| {
+                serde_json::to_string(&Snapshot {
+                    timestamp,
+                    ..Snapshot::default()
+                })
+                .unwrap()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn skips_a_truncated_last_line() {
+        let mut content = log(&[10, 11]);
+        content.push_str("\n{\"cpu\":{\"usa");
+
+        let replay = ReplaySource::from_log(&content).unwrap();
+        assert_eq!(replay.samples.len(), 2);
+        assert!(ReplaySource::from_log("garbage").is_err());
+    }
+
+    #[test]
+    fn step_and_seek() {
+        let mut replay = ReplaySource::from_log(&log(&[100, 101, 102, 110, 170, 171])).unwrap();
+
+        replay.step(2);
+        assert_eq!(replay.snapshot().timestamp, 102);
+        replay.step(-5);
+        assert_eq!(replay.snapshot().timestamp, 100);
+        replay.step(100);
+        assert_eq!(replay.snapshot().timestamp, 171);
+
+        replay.go_to(0);
+        replay.seek(60);
+        assert_eq!(replay.snapshot().timestamp, 110);
+        replay.seek(60);
+        assert_eq!(replay.snapshot().timestamp, 170);
+        replay.seek(-65);
+        assert_eq!(replay.snapshot().timestamp, 110);
+        replay.seek(-1000);
+        assert_eq!(replay.snapshot().timestamp, 100);
+    }
+
+    #[test]
+    fn plays_at_speed() {
+        let mut replay = ReplaySource::from_log(&log(&[0, 1, 2, 3, 4, 5, 6, 7, 8])).unwrap();
+        for _ in 0..3 {
+            replay.change_speed(true);
+        }
+        assert_eq!(replay.speed, 8.0);
+
+        // Half a second of wall time is four recorded seconds
+        replay.last_tick = Instant::now() - Duration::from_millis(500);
+        assert!(replay.update());
+        assert_eq!(replay.snapshot().timestamp, 4);
+
+        replay.set_paused(true);
+        replay.last_tick = Instant::now() - Duration::from_secs(10);
+        assert!(!replay.update());
+        assert_eq!(replay.snapshot().timestamp, 4);
+    }
+
+    #[test]
+    fn recent_samples_for_graphs() {
+        let mut replay = ReplaySource::from_log(&log(&[0, 10, 20, 30, 40])).unwrap();
+        replay.step(3);
+
+        let recent: Vec<i64> = replay
+            .recent(Duration::from_secs(15))
+            .iter()
+            .map(|sample| sample.timestamp)
+            .collect();
+        assert_eq!(recent, [20, 30]);
+    }
+}
@@ -0,0 +1,325 @@
+use anyhow::{bail, Context, Result};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
+use tokio::sync::watch;
+
+use super::Endpoint;
+use crate::data::{MetricsSource, Snapshot};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Three missed heartbeats and the agent is considered gone
+const STALE_AFTER: Duration = Duration::from_secs(15);
+
+/// Longest snapshot line accepted, the connection is dropped past it
+const MAX_LINE: usize = 16 * 1024 * 1024;
+
+/// Reconnect delay, doubled after every failed attempt up to the maximum
+const RECONNECT_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(30);
+
+/// A host on the dashboard command line: `NAME=ADDR`, or just `ADDR`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostSpec {
+    pub name: String,
+    pub endpoint: Endpoint,
+}
+
+impl FromStr for HostSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        match spec.split_once('=') {
+            Some((name, address)) if !name.is_empty() => Ok(HostSpec {
+                name: name.to_string(),
+                endpoint: address.parse()?,
+            }),
+            Some(_) => Err("empty host name before `=`".to_string()),
+            None => Ok(HostSpec {
+                name: spec.to_string(),
+                endpoint: spec.parse()?,
+            }),
+        }
+    }
+}
+
+/// What we know about an agent, updated by its connection task
+#[derive(Debug, Clone, Default)]
+pub struct HostState {
+    /// Latest snapshot, kept after the agent goes away
+    pub snapshot: Option<Snapshot>,
+    pub connected: bool,
+    /// Why the last connection failed or ended
+    pub error: Option<String>,
+    /// When `connected` last changed
+    pub since: Option<Instant>,
+}
+
+/// One agent on the dashboard, kept up to date in the background
+pub struct Host {
+    pub name: String,
+    pub endpoint: Endpoint,
+    pub state: watch::Receiver<HostState>,
+}
+
+impl Host {
+    /// Start following the agent; reconnects on its own until the host is dropped
+    pub fn connect(spec: HostSpec) -> Self {
+        let (sender, state) = watch::channel(HostState::default());
+        tokio::spawn(follow(spec.endpoint.clone(), sender));
+        Self {
+            name: spec.name,
+            endpoint: spec.endpoint,
+            state,
+        }
+    }
+}
+
+async fn follow(endpoint: Endpoint, state: watch::Sender<HostState>) {
+    let mut delay = RECONNECT_MIN;
+    while !state.is_closed() {
+        let result = match tokio::time::timeout(CONNECT_TIMEOUT, endpoint.connect()).await {
+            Ok(Ok(stream)) => {
+                delay = RECONNECT_MIN;
+                read_snapshots(stream, &state).await
+            }
+            Ok(Err(error)) => Err(error),
+            Err(_) => Err(anyhow::anyhow!("Connection timed out")),
+        };
+        let error = match result {
+            Ok(()) => "Agent closed the connection".to_string(),
+            Err(error) => format!("{:#}", error),
+        };
+        state.send_modify(|state| {
+            if state.connected || state.since.is_none() {
+                state.since = Some(Instant::now());
+            }
+            state.connected = false;
+            state.error = Some(error);
+        });
+
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(RECONNECT_MAX);
+    }
+}
+
+/// Publish every snapshot the agent sends, until it hangs up or goes quiet
+async fn read_snapshots(
+    stream: impl AsyncRead + Unpin,
+    state: &watch::Sender<HostState>,
+) -> Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut line = Vec::new();
+    while !state.is_closed() {
+        line.clear();
+        // One byte over the limit is enough to tell the line is too long
+        let mut limited = (&mut reader).take(MAX_LINE as u64 + 1);
+        let read = limited.read_until(b'\n', &mut line);
+        let Ok(read) = tokio::time::timeout(STALE_AFTER, read).await else {
+            bail!("No data for {}s", STALE_AFTER.as_secs());
+        };
+        if read? == 0 {
+            return Ok(());
+        }
+        if line.len() > MAX_LINE {
+            bail!("Snapshot longer than {} bytes", MAX_LINE);
+        }
+        let snapshot: Snapshot =
+            serde_json::from_slice(&line).context("Bad snapshot from agent")?;
+        state.send_modify(|state| {
+            if !state.connected {
+                state.connected = true;
+                state.since = Some(Instant::now());
+            }
+            state.error = None;
+            state.snapshot = Some(snapshot);
+        });
+    }
+    Ok(())
+}
+
+/// A single agent feeding the regular TUI layout
+pub struct RemoteSource {
+    name: String,
+    state: watch::Receiver<HostState>,
+    /// Timestamp of the snapshot last handed out, heartbeats repeat it
+    seen: i64,
+}
+
+impl RemoteSource {
+    pub fn new(host: &Host) -> Self {
+        let mut state = host.state.clone();
+        let seen = state
+            .borrow_and_update()
+            .snapshot
+            .as_ref()
+            .map_or(0, |snapshot| snapshot.timestamp);
+        Self {
+            name: host.name.clone(),
+            state,
+            seen,
+        }
+    }
+}
+
+impl MetricsSource for RemoteSource {
+    fn update(&mut self) -> bool {
+        let timestamp = match &self.state.borrow_and_update().snapshot {
+            Some(snapshot) => snapshot.timestamp,
+            None => return false,
+        };
+        let new = timestamp != self.seen;
+        self.seen = timestamp;
+        new
+    }
+
+    // The agent decides when to sample
+    fn refresh_all(&mut self) {}
+
+    fn snapshot(&self) -> Snapshot {
+        self.state.borrow().snapshot.clone().unwrap_or_default()
+    }
+
+    fn until_next(&self) -> Duration {
+        Duration::from_millis(250)
+    }
+
+    fn status(&self) -> Option<String> {
+        let state = self.state.borrow();
+        Some(match &state.error {
+            Some(error) if !state.connected => format!("{} DOWN: {}", self.name, error),
+            _ => format!("{} UP", self.name),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fake;
+    use crate::remote::{serve, Listener};
+
+    #[test]
+    fn parse_host_specs() {
+        assert_eq!(
+            "build1".parse(),
+            Ok(HostSpec {
+                name: "build1".to_string(),
+                endpoint: Endpoint::Tcp("build1:7878".to_string()),
+            })
+        );
+        assert_eq!(
+            "ci=10.0.0.5:9000".parse(),
+            Ok(HostSpec {
+                name: "ci".to_string(),
+                endpoint: Endpoint::Tcp("10.0.0.5:9000".to_string()),
+            })
+        );
+        assert!("=10.0.0.5".parse::<HostSpec>().is_err());
+    }
+
+    /// Wait for the host state to match, giving up after a few seconds
+    async fn wait_for(host: &mut Host, check: impl Fn(&HostState) -> bool) -> HostState {
+        let state = tokio::time::timeout(Duration::from_secs(5), host.state.wait_for(check))
+            .await
+            .expect("host state never matched")
+            .unwrap();
+        state.clone()
+    }
+
+    /// Serve the fixture until the returned sender is dropped and the task aborted
+    async fn start_agent(
+        endpoint: &Endpoint,
+    ) -> (watch::Sender<Snapshot>, tokio::task::JoinHandle<Result<()>>) {
+        let listener = Listener::bind(endpoint).await.unwrap();
+        let (sender, receiver) = watch::channel(fake::snapshot(0));
+        (sender, tokio::spawn(serve(listener, receiver)))
+    }
+
+    #[tokio::test]
+    async fn drops_an_endless_line() {
+        let (state, _receiver) = watch::channel(HostState::default());
+        let stream = vec![b'x'; MAX_LINE + 100];
+        let error = read_snapshots(stream.as_slice(), &state).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("Snapshot longer than {} bytes", MAX_LINE)
+        );
+        assert!(state.borrow().snapshot.is_none());
+
+        // A line right at the limit is still read, and rejected only for not being JSON
+        let mut stream = vec![b' '; MAX_LINE - 1];
+        stream.push(b'\n');
+        let error = read_snapshots(stream.as_slice(), &state).await.unwrap_err();
+        assert_eq!(error.to_string(), "Bad snapshot from agent");
+    }
+
+    #[tokio::test]
+    async fn follows_an_agent_over_tcp() {
+        // Grab a free port, then hand it to the agent
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let endpoint = Endpoint::Tcp(format!("127.0.0.1:{}", port));
+        let (sender, agent) = start_agent(&endpoint).await;
+
+        let mut host = Host::connect(HostSpec {
+            name: "local".to_string(),
+            endpoint: endpoint.clone(),
+        });
+        let state = wait_for(&mut host, |state| state.connected).await;
+        assert_eq!(
+            state.snapshot.unwrap().timestamp,
+            fake::snapshot(0).timestamp
+        );
+
+        sender.send_replace(fake::snapshot(1));
+        let state = wait_for(&mut host, |state| {
+            state.snapshot.as_ref().unwrap().timestamp == fake::snapshot(1).timestamp
+        })
+        .await;
+        assert!(state.connected);
+
+        // The agent goes away: the host is down but keeps its last snapshot
+        agent.abort();
+        drop(sender);
+        let state = wait_for(&mut host, |state| !state.connected).await;
+        assert_eq!(state.error.as_deref(), Some("Agent closed the connection"));
+        assert!(state.snapshot.is_some());
+
+        // And comes back on the same port
+        let (_sender, _agent) = start_agent(&endpoint).await;
+        wait_for(&mut host, |state| state.connected).await;
+    }
+
+    #[tokio::test]
+    async fn follows_an_agent_over_a_unix_socket() {
+        let path = std::env::temp_dir().join(format!(
+            "rust-conky-agent-client-{}.sock",
+            std::process::id()
+        ));
+        let endpoint = Endpoint::Unix(path.clone());
+
+        // Nothing listening yet
+        let mut host = Host::connect(HostSpec {
+            name: "local".to_string(),
+            endpoint: endpoint.clone(),
+        });
+        let state = wait_for(&mut host, |state| state.error.is_some()).await;
+        assert!(!state.connected);
+
+        let (_sender, _agent) = start_agent(&endpoint).await;
+        let state = wait_for(&mut host, |state| state.connected).await;
+        assert!(state.error.is_none());
+
+        let mut source = RemoteSource::new(&host);
+        assert!(!source.update());
+        assert_eq!(source.snapshot().timestamp, fake::snapshot(0).timestamp);
+        assert_eq!(source.status().as_deref(), Some("local UP"));
+
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,281 @@
+// Agent mode - stream snapshots to dashboards over TCP or a Unix socket
+pub mod client;
+
+use anyhow::{Context, Result};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UnixListener, UnixStream};
+use tokio::sync::watch;
+
+use crate::config::{Config, IntervalArg};
+use crate::data::{RefreshScheduler, Snapshot, SystemInfo};
+
+/// Port used when an address leaves it out
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// Agents resend the latest snapshot at least this often, so a quiet agent is not taken for a dead one
+const HEARTBEAT: Duration = Duration::from_secs(5);
+
+/// `agent` subcommand options
+#[derive(Debug, Clone, clap::Args)]
+pub struct AgentOptions {
+    /// Where to listen, `host:port` or `unix:/path/to.sock`
+    #[arg(value_name = "ADDR", default_value = "127.0.0.1:7878")]
+    pub listen: Endpoint,
+
+    #[command(flatten)]
+    pub interval: IntervalArg,
+
+    /// Busiest processes sent per sample
+    #[arg(long, value_name = "N", default_value_t = 50)]
+    pub processes: usize,
+}
+
+/// `dashboard` subcommand options
+#[derive(Debug, Clone, clap::Args)]
+pub struct DashboardOptions {
+    /// Agents to watch, `ADDR` or `NAME=ADDR`, e.g. build1 ci=10.0.0.5:7878 local=unix:/run/conky.sock
+    #[arg(value_name = "HOST", required = true)]
+    pub hosts: Vec<client::HostSpec>,
+}
+
+/// An agent address: `host[:port]` or `unix:/path/to.sock`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Endpoint {
+    Tcp(String),
+    Unix(PathBuf),
+}
+
+impl FromStr for Endpoint {
+    type Err = String;
+
+    fn from_str(address: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = address.strip_prefix("unix:") {
+            if path.is_empty() {
+                return Err("missing socket path after `unix:`".to_string());
+            }
+            return Ok(Endpoint::Unix(PathBuf::from(path)));
+        }
+        if address.is_empty() {
+            return Err("empty address".to_string());
+        }
+        // `::1` could be an address or a host and port, only brackets tell them apart
+        if !address.starts_with('[') && address.matches(':').count() > 1 {
+            return Err(format!(
+                "IPv6 address `{}` needs brackets, e.g. `[{}]` or `[{}]:{}`",
+                address, address, address, DEFAULT_PORT
+            ));
+        }
+        // A bare host name gets the default port; bracketed IPv6 needs one after the bracket
+        let has_port = match address.rfind(']') {
+            Some(bracket) => address[bracket..].contains(':'),
+            None => address.contains(':'),
+        };
+        Ok(if has_port {
+            Endpoint::Tcp(address.to_string())
+        } else {
+            Endpoint::Tcp(format!("{}:{}", address, DEFAULT_PORT))
+        })
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Endpoint::Tcp(address) => write!(f, "{}", address),
+            Endpoint::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+impl Endpoint {
+    /// Open a stream to the agent listening here
+    pub async fn connect(&self) -> Result<Box<dyn AsyncRead + Unpin + Send>> {
+        Ok(match self {
+            Endpoint::Tcp(address) => Box::new(TcpStream::connect(address).await?),
+            Endpoint::Unix(path) => Box::new(UnixStream::connect(path).await?),
+        })
+    }
+}
+
+/// A bound agent socket
+pub enum Listener {
+    Tcp(TcpListener),
+    Unix(UnixListener),
+}
+
+impl Listener {
+    pub async fn bind(endpoint: &Endpoint) -> Result<Self> {
+        let listener = match endpoint {
+            Endpoint::Tcp(address) => TcpListener::bind(address).await.map(Listener::Tcp),
+            Endpoint::Unix(path) => {
+                // A socket left behind by an agent that didn't exit cleanly would block the bind,
+                // but one a running agent still answers on is left for the bind to refuse
+                if is_socket(path) && is_abandoned(path).await {
+                    std::fs::remove_file(path)?;
+                }
+                UnixListener::bind(path).map(Listener::Unix)
+            }
+        };
+        listener.with_context(|| format!("Cannot listen on {}", endpoint))
+    }
+}
+
+fn is_socket(path: &std::path::Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket())
+}
+
+/// Nobody accepts connections on the socket any more
+async fn is_abandoned(path: &std::path::Path) -> bool {
+    UnixStream::connect(path)
+        .await
+        .is_err_and(|error| error.kind() == std::io::ErrorKind::ConnectionRefused)
+}
+
+/// Sample the system until interrupted, streaming every sample to connected dashboards
+pub async fn agent(config: &Config, options: &AgentOptions) -> Result<()> {
+    let interval = options.interval.seconds(config);
+    let listener = Listener::bind(&options.listen).await?;
+    let mut scheduler = RefreshScheduler::all(Duration::from_secs(interval));
+    let system_info = SystemInfo::primed(config, &mut scheduler).await;
+
+    let (sender, receiver) = watch::channel(trimmed(system_info.snapshot(), options.processes));
+    let sampler = tokio::spawn(sample(system_info, scheduler, options.processes, sender));
+
+    eprintln!(
+        "📡 Streaming snapshots on {} every {}s - Ctrl+C to stop",
+        options.listen, interval
+    );
+    tokio::select! {
+        result = serve(listener, receiver) => result,
+        // Without a sampler dashboards would only ever get the last snapshot
+        stopped = sampler => stopped?,
+    }
+}
+
+/// Only the `processes` busiest processes, to keep lines short
+fn trimmed(snapshot: Snapshot, processes: usize) -> Snapshot {
+    Snapshot {
+        processes: snapshot.top_processes(processes),
+        ..snapshot
+    }
+}
+
+/// Refresh whatever is due and publish it, returning only when a refresh fails
+async fn sample(
+    mut system_info: SystemInfo,
+    mut scheduler: RefreshScheduler,
+    processes: usize,
+    sender: watch::Sender<Snapshot>,
+) -> Result<()> {
+    loop {
+        tokio::time::sleep(scheduler.until_next()).await;
+        // A process scan or systemctl blocks, keep it off the threads streaming to dashboards
+        let refresh = tokio::task::spawn_blocking(move || {
+            let snapshot = scheduler
+                .refresh_due(&mut system_info)
+                .then(|| trimmed(system_info.snapshot(), processes));
+            (system_info, scheduler, snapshot)
+        });
+        let (refreshed, rescheduled, snapshot) = refresh.await.context("Sampling failed")?;
+        (system_info, scheduler) = (refreshed, rescheduled);
+        if let Some(snapshot) = snapshot {
+            sender.send_replace(snapshot);
+        }
+    }
+}
+
+/// Accept dashboards forever, each one gets its own stream of the latest snapshots
+pub async fn serve(listener: Listener, snapshots: watch::Receiver<Snapshot>) -> Result<()> {
+    loop {
+        let snapshots = snapshots.clone();
+        // A dashboard hanging up is not worth reporting
+        match &listener {
+            Listener::Tcp(listener) => {
+                let (stream, _) = listener.accept().await?;
+                tokio::spawn(async move { stream_snapshots(stream, snapshots).await.ok() });
+            }
+            Listener::Unix(listener) => {
+                let (stream, _) = listener.accept().await?;
+                tokio::spawn(async move { stream_snapshots(stream, snapshots).await.ok() });
+            }
+        }
+    }
+}
+
+/// Write one JSON snapshot per line until the dashboard or the agent goes away
+async fn stream_snapshots(
+    mut stream: impl AsyncWrite + Unpin,
+    mut snapshots: watch::Receiver<Snapshot>,
+) -> Result<()> {
+    loop {
+        let mut line = serde_json::to_string(&*snapshots.borrow_and_update())?;
+        line.push('\n');
+        stream.write_all(line.as_bytes()).await?;
+
+        if let Ok(Err(_)) = tokio::time::timeout(HEARTBEAT, snapshots.changed()).await {
+            // The sampler stopped, so the agent is shutting down
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_endpoints() {
+        let parse = |address: &str| address.parse::<Endpoint>();
+        assert_eq!(
+            parse("build1"),
+            Ok(Endpoint::Tcp("build1:7878".to_string()))
+        );
+        assert_eq!(
+            parse("10.0.0.5:9000"),
+            Ok(Endpoint::Tcp("10.0.0.5:9000".to_string()))
+        );
+        assert_eq!(parse("[::1]"), Ok(Endpoint::Tcp("[::1]:7878".to_string())));
+        assert_eq!(
+            parse("[::1]:9000"),
+            Ok(Endpoint::Tcp("[::1]:9000".to_string()))
+        );
+        assert_eq!(
+            parse("unix:/run/conky.sock"),
+            Ok(Endpoint::Unix(PathBuf::from("/run/conky.sock")))
+        );
+        assert_eq!(
+            parse("::1"),
+            Err("IPv6 address `::1` needs brackets, e.g. `[::1]` or `[::1]:7878`".to_string())
+        );
+        assert!(parse("fe80::1:9000").is_err());
+        assert!(parse("unix:").is_err());
+        assert!(parse("").is_err());
+        assert_eq!(
+            parse("unix:/run/conky.sock").unwrap().to_string(),
+            "unix:/run/conky.sock"
+        );
+    }
+
+    #[tokio::test]
+    async fn replaces_only_abandoned_sockets() {
+        let path = std::env::temp_dir().join(format!(
+            "rust-conky-agent-replace-{}.sock",
+            std::process::id()
+        ));
+        let endpoint = Endpoint::Unix(path.clone());
+
+        let running = Listener::bind(&endpoint).await.unwrap();
+        assert!(Listener::bind(&endpoint).await.is_err());
+        // Dropping the listener leaves the socket file behind, like a crashed agent
+        drop(running);
+        assert!(is_socket(&path));
+        let replaced = Listener::bind(&endpoint).await;
+        let _ = std::fs::remove_file(&path);
+        assert!(replaced.is_ok());
+    }
+}
@@ -0,0 +1,207 @@
+// src/render/bar.rs - status bar protocols (i3bar/swaybar, waybar)
+use anyhow::Result;
+use serde::Serialize;
+use std::io::{self, Write};
+
+use crate::alerts::AlertMonitor;
+use crate::data::{History, Snapshot};
+use crate::widgets::WidgetGrid;
+
+const ALERT_COLOR: &str = "#FF5555";
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum BarProtocol {
+    /// i3bar JSON protocol, also understood by swaybar
+    I3bar,
+    /// JSON lines for a waybar custom module
+    Waybar,
+}
+
+/// https://i3wm.org/docs/i3bar-protocol.html
+#[derive(Serialize)]
+struct I3Block {
+    name: &'static str,
+    full_text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<&'static str>,
+    urgent: bool,
+}
+
+/// https://github.com/Alexays/Waybar/wiki/Module:-Custom
+#[derive(Serialize)]
+struct WaybarModule {
+    text: String,
+    tooltip: String,
+    class: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    percentage: Option<u8>,
+}
+
+/// Write whatever the protocol expects before the first update
+pub fn start(protocol: BarProtocol) -> Result<()> {
+    if let BarProtocol::I3bar = protocol {
+        // Header, then an endless array with one array of blocks per update
+        println!("{{\"version\":1}}");
+        println!("[");
+    }
+    io::stdout().flush()?;
+    Ok(())
+}
+
+/// Write one status update for every widget in the grid
+pub fn draw(
+    protocol: BarProtocol,
+    grid: &WidgetGrid,
+    snapshot: &Snapshot,
+    history: &History,
+    alerts: &AlertMonitor,
+) -> Result<()> {
+    println!("{}", encode(protocol, grid, snapshot, history, alerts)?);
+    io::stdout().flush()?;
+    Ok(())
+}
+
+/// One status update as a line of the protocol
+fn encode(
+    protocol: BarProtocol,
+    grid: &WidgetGrid,
+    snapshot: &Snapshot,
+    history: &History,
+    alerts: &AlertMonitor,
+) -> Result<String> {
+    let items: Vec<_> = grid
+        .rows()
+        .iter()
+        .flatten()
+        .filter_map(|cell| {
+            let item = cell.widget.bar_item(snapshot)?;
+            Some((cell, item, alerts.is_alerting(cell.widget_type)))
+        })
+        .collect();
+
+    Ok(match protocol {
+        BarProtocol::I3bar => {
+            let blocks: Vec<I3Block> = items
+                .into_iter()
+                .map(|(cell, item, alert)| I3Block {
+                    name: cell.widget_type.as_str(),
+                    full_text: item.text,
+                    color: alert.then_some(ALERT_COLOR),
+                    urgent: alert,
+                })
+                .collect();
+            format!("{},", serde_json::to_string(&blocks)?)
+        }
+        BarProtocol::Waybar => {
+            let mut class: Vec<String> = items
+                .iter()
+                .filter(|(_, _, alert)| *alert)
+                .map(|(cell, _, _)| format!("{}-alert", cell.widget_type.as_str()))
+                .collect();
+            if !class.is_empty() {
+                class.insert(0, "alert".to_string());
+            }
+
+            // The full text of the same widgets, for hovering
+            let tooltip: Vec<String> = items
+                .iter()
+                .flat_map(|(cell, _, _)| cell.widget.render_text(snapshot, history))
+                .collect();
+
+            let module = WaybarModule {
+                text: items
+                    .iter()
+                    .map(|(_, item, _)| item.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join("  "),
+                tooltip: tooltip.join("\n"),
+                class,
+                percentage: items
+                    .iter()
+                    .find_map(|(_, item, _)| item.percentage)
+                    .map(|percent| percent.clamp(0.0, 100.0).round() as u8),
+            };
+            serde_json::to_string(&module)?
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AlertMetric, AlertRule, WidgetConfig, WidgetType};
+    use crate::data::fake;
+
+    fn grid() -> WidgetGrid {
+        let widget = |widget_type, column| WidgetConfig {
+            widget_type,
+            position: [0, column],
+            update_interval: None,
+        };
+        WidgetGrid::from_config(&[widget(WidgetType::Cpu, 0), widget(WidgetType::Memory, 1)])
+            .unwrap()
+    }
+
+    /// Fired on the fixture, which runs at 25% CPU
+    fn cpu_alert() -> AlertMonitor {
+        let mut alerts = AlertMonitor::new(&[AlertRule {
+            metric: AlertMetric::Cpu,
+            above: 20.0,
+            mount: None,
+            for_secs: 0,
+            command: None,
+            stderr: false,
+        }])
+        .unwrap();
+        alerts.check(&fake::snapshot(0));
+        alerts
+    }
+
+    fn encode_json(protocol: BarProtocol, alerts: &AlertMonitor) -> serde_json::Value {
+        let line = encode(
+            protocol,
+            &grid(),
+            &fake::snapshot(0),
+            &History::default(),
+            alerts,
+        )
+        .unwrap();
+        // i3bar updates are elements of one endless array
+        let line = match protocol {
+            BarProtocol::I3bar => line.strip_suffix(',').unwrap(),
+            BarProtocol::Waybar => &line,
+        };
+        serde_json::from_str(line).unwrap()
+    }
+
+    #[test]
+    fn i3bar_blocks() {
+        let quiet = encode_json(BarProtocol::I3bar, &AlertMonitor::new(&[]).unwrap());
+        assert_eq!(quiet[0]["name"], "cpu");
+        assert_eq!(quiet[0]["full_text"], "CPU 25%");
+        assert_eq!(quiet[1]["name"], "memory");
+        assert_eq!(quiet[0]["urgent"], false);
+        assert!(quiet[0].get("color").is_none());
+
+        let alerting = encode_json(BarProtocol::I3bar, &cpu_alert());
+        assert_eq!(alerting[0]["urgent"], true);
+        assert_eq!(alerting[0]["color"], ALERT_COLOR);
+        assert_eq!(alerting[1]["urgent"], false);
+    }
+
+    #[test]
+    fn waybar_module() {
+        let quiet = encode_json(BarProtocol::Waybar, &AlertMonitor::new(&[]).unwrap());
+        assert_eq!(quiet["text"], "CPU 25%  RAM 6.0/16.0G");
+        assert_eq!(quiet["class"], serde_json::json!([]));
+        // The first widget with a percentage drives the module's
+        assert_eq!(quiet["percentage"], 25);
+        assert!(quiet["tooltip"]
+            .as_str()
+            .unwrap()
+            .starts_with("CPU: 25.0% (4 cores"));
+
+        let alerting = encode_json(BarProtocol::Waybar, &cpu_alert());
+        assert_eq!(alerting["class"], serde_json::json!(["alert", "cpu-alert"]));
+    }
+}
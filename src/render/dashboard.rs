@@ -0,0 +1,368 @@
+// src/render/dashboard.rs
+use anyhow::Result;
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
+};
+use std::time::{Duration, Instant};
+
+use super::tui::{next_key, restore_terminal, setup_terminal, TuiApp, TuiTerminal};
+use crate::alerts::AlertMonitor;
+use crate::config::Config;
+use crate::data::Snapshot;
+use crate::remote::client::{Host, HostState, RemoteSource};
+use crate::widgets::{format_age, percentage, WidgetGrid};
+
+// How often the host list redraws, agents push on their own schedule
+const REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+
+/// One summary row per agent, ENTER opens the regular layout for the selected one
+pub async fn launch_dashboard(config: &Config, hosts: Vec<Host>) -> Result<()> {
+    // Check the layout and alert rules before taking over the terminal
+    WidgetGrid::from_config(&config.widgets)?;
+    AlertMonitor::new(&config.alerts)?;
+
+    let mut terminal = setup_terminal()?;
+    let mut app = DashboardApp::new(config, hosts);
+    let result = run_dashboard_loop(&mut terminal, &mut app).await;
+    restore_terminal(&mut terminal)?;
+    result
+}
+
+async fn run_dashboard_loop(terminal: &mut TuiTerminal, app: &mut DashboardApp) -> Result<()> {
+    loop {
+        terminal.draw(|frame| app.draw(frame))?;
+
+        // The host connections keep running on the runtime while we wait for input
+        if let Some(code) = next_key(app.next_refresh_in()).await? {
+            if !app.handle_key(code)? {
+                return Ok(());
+            }
+        }
+
+        if let Some(detail) = &mut app.detail {
+            detail.tick();
+        }
+    }
+}
+
+struct DashboardApp {
+    config: Config,
+    hosts: Vec<Host>,
+    selected: usize,
+    /// The full layout for one host, while drilled down
+    detail: Option<TuiApp>,
+    message: Option<String>,
+}
+
+impl DashboardApp {
+    fn new(config: &Config, hosts: Vec<Host>) -> Self {
+        Self {
+            config: config.clone(),
+            hosts,
+            selected: 0,
+            detail: None,
+            message: None,
+        }
+    }
+
+    fn next_refresh_in(&self) -> Duration {
+        match &self.detail {
+            Some(detail) => detail.next_refresh_in().min(REDRAW_INTERVAL),
+            None => REDRAW_INTERVAL,
+        }
+    }
+
+    /// Returns false when the dashboard should quit
+    fn handle_key(&mut self, code: KeyCode) -> Result<bool> {
+        if let Some(detail) = &mut self.detail {
+            // Quitting the host view goes back to the list
+            if !detail.handle_key(code) {
+                self.detail = None;
+            }
+            return Ok(true);
+        }
+
+        self.message = None;
+        match code {
+            KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => return Ok(false),
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => {
+                self.selected = (self.selected + 1).min(self.hosts.len().saturating_sub(1))
+            }
+            KeyCode::Enter => self.open_selected()?,
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    fn open_selected(&mut self) -> Result<()> {
+        let Some(host) = self.hosts.get(self.selected) else {
+            return Ok(());
+        };
+        if host.state.borrow().snapshot.is_none() {
+            self.message = Some(format!("No data from {} yet", host.name));
+            return Ok(());
+        }
+
+        // Alerts still highlight widgets, but their commands are meant for the local machine
+        self.detail = Some(TuiApp::new(
+            &self.config,
+            WidgetGrid::from_config(&self.config.widgets)?,
+            AlertMonitor::new(&self.config.alerts)?.without_hooks(),
+            Box::new(RemoteSource::new(host)),
+        ));
+        Ok(())
+    }
+
+    fn draw(&self, frame: &mut ratatui::Frame) {
+        if let Some(detail) = &self.detail {
+            detail.draw(frame);
+            return;
+        }
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([Constraint::Min(3), Constraint::Length(1)])
+            .split(frame.area());
+
+        let now = Instant::now();
+        let states: Vec<HostState> = self
+            .hosts
+            .iter()
+            .map(|host| host.state.borrow().clone())
+            .collect();
+        let up = states.iter().filter(|state| state.connected).count();
+
+        let header = Row::new(["HOST", "STATE", "CPU", "MEM", "DISK", "LOAD", "UPTIME"])
+            .style(Style::default().fg(Color::Yellow));
+        let name_width = self
+            .hosts
+            .iter()
+            .map(|host| host.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(8) as u16;
+        let rows = self
+            .hosts
+            .iter()
+            .zip(&states)
+            .map(|(host, state)| summary_row(host, state, now));
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(name_width),
+                Constraint::Length(12),
+                Constraint::Length(7),
+                Constraint::Length(7),
+                Constraint::Length(7),
+                Constraint::Length(6),
+                Constraint::Length(11),
+            ],
+        )
+        .header(header)
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(
+            Block::default()
+                .title(format!(" HOSTS ({}/{} up) ", up, self.hosts.len()))
+                .borders(Borders::ALL),
+        );
+        let mut table_state = TableState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(table, chunks[0], &mut table_state);
+
+        // Why the selected host is down, otherwise the keys
+        let status = match (&self.message, states.get(self.selected)) {
+            (Some(message), _) => message.clone(),
+            (None, Some(state)) if !state.connected && state.error.is_some() => format!(
+                "{} ({}): {}",
+                self.hosts[self.selected].name,
+                self.hosts[self.selected].endpoint,
+                state.error.as_deref().unwrap_or("")
+            ),
+            _ => "Q:Quit | ↑↓:Select | ENTER:Details".to_string(),
+        };
+        let status_line = Line::from(vec![Span::styled(
+            status,
+            Style::default().fg(Color::White).bg(Color::DarkGray),
+        )]);
+        frame.render_widget(Paragraph::new(status_line), chunks[1]);
+    }
+}
+
+/// Host name, up/down state and the headline numbers; down hosts keep their last values, dimmed
+fn summary_row<'a>(host: &'a Host, state: &HostState, now: Instant) -> Row<'a> {
+    let since = state
+        .since
+        .map(|since| format_age(now.saturating_duration_since(since)))
+        .unwrap_or_default();
+    let (label, color) = match (state.connected, &state.error) {
+        (true, _) => ("UP", Color::Green),
+        (false, Some(_)) => ("DOWN", Color::Red),
+        (false, None) => ("CONNECTING", Color::Gray),
+    };
+    let state_cell = Cell::from(format!("{} {}", label, since).trim_end().to_string())
+        .style(Style::default().fg(color));
+
+    let mut cells = vec![Cell::from(host.name.as_str()), state_cell];
+    if let Some(snapshot) = &state.snapshot {
+        let memory = percentage(snapshot.memory.used, snapshot.memory.total);
+        cells.extend([
+            usage_cell(snapshot.cpu.usage as f64),
+            usage_cell(memory),
+            usage_cell(fullest_disk(snapshot)),
+            Cell::from(format!("{:.2}", snapshot.cpu.load_average.one)),
+            Cell::from(format_age(Duration::from_secs(snapshot.system.uptime))),
+        ]);
+    }
+
+    let row = Row::new(cells);
+    if state.connected {
+        row
+    } else {
+        row.style(Style::default().add_modifier(Modifier::DIM))
+    }
+}
+
+fn usage_cell<'a>(percent: f64) -> Cell<'a> {
+    let color = match percent {
+        percent if percent >= 90.0 => Color::Red,
+        percent if percent >= 75.0 => Color::Yellow,
+        _ => Color::Green,
+    };
+    Cell::from(format!("{:.1}%", percent)).style(Style::default().fg(color))
+}
+
+/// Usage of the fullest disk, the one about to cause trouble
+fn fullest_disk(snapshot: &Snapshot) -> f64 {
+    snapshot
+        .disks
+        .iter()
+        .map(|disk| percentage(disk.total.saturating_sub(disk.available), disk.total))
+        .fold(0.0, f64::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fake;
+    use crate::data::snapshot::DiskData;
+    use crate::remote::Endpoint;
+    use ratatui::{backend::TestBackend, Terminal};
+    use tokio::sync::watch;
+
+    fn host(name: &str, state: HostState) -> (Host, watch::Sender<HostState>) {
+        let (sender, receiver) = watch::channel(state);
+        let host = Host {
+            name: name.to_string(),
+            endpoint: Endpoint::Tcp(format!("{}:7878", name)),
+            state: receiver,
+        };
+        (host, sender)
+    }
+
+    fn screen(app: &DashboardApp) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                let line: String = (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect();
+                line.trim_end().to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn summary_and_drill_down() {
+        let (build1, _up) = host(
+            "build1",
+            HostState {
+                snapshot: Some(fake::snapshot(0)),
+                connected: true,
+                ..HostState::default()
+            },
+        );
+        let (build2, down) = host(
+            "build2",
+            HostState {
+                snapshot: Some(fake::snapshot(1)),
+                error: Some("Connection refused (os error 111)".to_string()),
+                ..HostState::default()
+            },
+        );
+        let (build3, _waiting) = host("build3", HostState::default());
+        let mut app = DashboardApp::new(&Config::default(), vec![build1, build2, build3]);
+
+        let summary = screen(&app);
+        assert!(summary.contains("HOSTS (1/3 up)"), "{}", summary);
+        let row = |name: &str| {
+            summary
+                .lines()
+                .find(|line| line.contains(name))
+                .unwrap()
+                .split_whitespace()
+                .skip(1)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        assert_eq!(row("build1"), "UP 25.0% 37.5% 60.0% 1.25 3d 04h │");
+        assert_eq!(row("build2"), "DOWN 30.0% 39.1% 60.0% 1.25 3d 04h │");
+        assert_eq!(row("build3"), "CONNECTING │");
+
+        // The selected host's error replaces the key help
+        assert!(app.handle_key(KeyCode::Down).unwrap());
+        assert!(screen(&app).contains("build2 (build2:7878): Connection refused"));
+        app.handle_key(KeyCode::Down).unwrap();
+        app.handle_key(KeyCode::Enter).unwrap();
+        assert!(app.detail.is_none());
+        assert!(screen(&app).contains("No data from build3 yet"));
+
+        // Drill down into build2, Q comes back to the list
+        app.handle_key(KeyCode::Up).unwrap();
+        app.handle_key(KeyCode::Enter).unwrap();
+        let detail = screen(&app);
+        assert!(detail.contains("PROCESSES (5)"), "{}", detail);
+        assert!(detail.contains("build2 DOWN: Connection refused"));
+
+        down.send_modify(|state| {
+            state.connected = true;
+            state.error = None;
+            state.snapshot = Some(fake::snapshot(2));
+        });
+        app.detail.as_mut().unwrap().tick();
+        assert!(screen(&app).contains("build2 UP | Q:Back"));
+
+        assert!(app.handle_key(KeyCode::Char('q')).unwrap());
+        assert!(app.detail.is_none());
+        assert!(!app.handle_key(KeyCode::Char('q')).unwrap());
+    }
+
+    #[test]
+    fn fullest_disk_from_an_odd_agent() {
+        // Some filesystems report more available than total, that is not a full disk
+        let mut snapshot = fake::snapshot(0);
+        snapshot.disks = vec![DiskData {
+            total: 100,
+            available: 150,
+            ..DiskData::default()
+        }];
+        assert_eq!(fullest_disk(&snapshot), 0.0);
+    }
+
+    #[test]
+    fn ages() {
+        assert_eq!(format_age(Duration::from_secs(45)), "45s");
+        assert_eq!(format_age(Duration::from_secs(725)), "12m 05s");
+        assert_eq!(
+            format_age(Duration::from_secs(3 * 86400 + 4 * 3600)),
+            "3d 04h"
+        );
+    }
+}
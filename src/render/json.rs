@@ -0,0 +1,217 @@
+// src/render/json.rs
+use anyhow::{bail, Result};
+use serde_json::{Map, Value};
+
+use crate::config::{JsonConfig, WidgetType};
+use crate::data::Snapshot;
+use crate::widgets::{self, ProcessesWidget, WidgetGrid};
+
+/// Check that every include/exclude path starts at a real top-level field
+pub fn validate(options: &JsonConfig) -> Result<()> {
+    let Value::Object(fields) = serde_json::to_value(Snapshot::default())? else {
+        return Ok(());
+    };
+    for path in options.include.iter().chain(&options.exclude) {
+        let field = path.split('.').next().unwrap_or("");
+        if !fields.contains_key(field) {
+            let known: Vec<&str> = fields.keys().map(String::as_str).collect();
+            bail!(
+                "Unknown JSON field `{}`, expected one of: {}",
+                path,
+                known.join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Every widget once, so include and exclude pick from every field
+fn json_widgets(options: &JsonConfig) -> WidgetGrid {
+    WidgetGrid::stacked(WidgetType::ALL.into_iter().map(|widget_type| {
+        let widget: Box<dyn widgets::Widget> = match widget_type {
+            WidgetType::Processes => Box::new(ProcessesWidget {
+                count: options.processes,
+            }),
+            _ => widgets::build(widget_type),
+        };
+        (widget_type, widget)
+    }))
+}
+
+/// One JSON document for a snapshot, trimmed down to the configured fields
+pub fn encode(snapshot: &Snapshot, options: &JsonConfig) -> Result<String> {
+    let data = widgets::collect(&json_widgets(options), snapshot);
+    // Going through text keeps f32 fields short; to_value would widen them to noisy f64s
+    let mut value: Value = serde_json::from_str(&serde_json::to_string(&data)?)?;
+
+    if !options.include.is_empty() {
+        let mut selected = Value::Object(Map::new());
+        for path in &options.include {
+            if let Some(part) = select(&value, &split(path)) {
+                merge(&mut selected, part);
+            }
+        }
+        value = selected;
+    }
+    for path in &options.exclude {
+        remove(&mut value, &split(path));
+    }
+
+    Ok(if options.pretty {
+        serde_json::to_string_pretty(&value)?
+    } else {
+        serde_json::to_string(&value)?
+    })
+}
+
+fn split(path: &str) -> Vec<&str> {
+    path.split('.').filter(|part| !part.is_empty()).collect()
+}
+
+/// The value at `path` wrapped in its parent objects; paths go through arrays element by element
+fn select(value: &Value, path: &[&str]) -> Option<Value> {
+    let Some((field, rest)) = path.split_first() else {
+        return Some(value.clone());
+    };
+    match value {
+        Value::Object(map) => {
+            let inner = select(map.get(*field)?, rest)?;
+            Some(Value::Object(Map::from_iter([(field.to_string(), inner)])))
+        }
+        Value::Array(items) => Some(Value::Array(
+            items
+                .iter()
+                .map(|item| select(item, path).unwrap_or(Value::Object(Map::new())))
+                .collect(),
+        )),
+        _ => None,
+    }
+}
+
+/// Deep merge of two selections taken from the same document
+fn merge(target: &mut Value, source: Value) {
+    match (target, source) {
+        (Value::Object(target), Value::Object(source)) => {
+            for (key, value) in source {
+                match target.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(target), Value::Array(source)) => {
+            for (existing, value) in target.iter_mut().zip(source) {
+                merge(existing, value);
+            }
+        }
+        (target, source) => *target = source,
+    }
+}
+
+fn remove(value: &mut Value, path: &[&str]) {
+    let Some((field, rest)) = path.split_first() else {
+        return;
+    };
+    match value {
+        Value::Object(map) if rest.is_empty() => {
+            // Keep the remaining fields in schema order
+            map.shift_remove(*field);
+        }
+        Value::Object(map) => {
+            if let Some(inner) = map.get_mut(*field) {
+                remove(inner, rest);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                remove(item, path);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fake;
+    use crate::render::assert_snapshot;
+
+    #[test]
+    fn full_schema() {
+        let options = JsonConfig {
+            pretty: true,
+            ..JsonConfig::default()
+        };
+        let json = encode(&fake::snapshot(0), &options).unwrap();
+        assert_snapshot("json_full", &json);
+
+        // Whatever gets written can be read back, which is what replays rely on
+        let parsed: Snapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.timestamp, fake::snapshot(0).timestamp);
+    }
+
+    #[test]
+    fn every_field_comes_from_a_widget() {
+        // The widgets together cover the whole snapshot, nothing is lost on the way
+        let snapshot = fake::snapshot(0);
+        let expected = Snapshot {
+            processes: snapshot.top_processes(5),
+            ..snapshot.clone()
+        };
+        assert_eq!(
+            encode(&snapshot, &JsonConfig::default()).unwrap(),
+            serde_json::to_string(&expected).unwrap()
+        );
+
+        // A grid only gets what its widgets show
+        let grid = WidgetGrid::stacked([(WidgetType::Swap, widgets::build(WidgetType::Swap))]);
+        let data = widgets::collect(&grid, &snapshot);
+        assert_eq!(data.memory.total_swap, snapshot.memory.total_swap);
+        assert_eq!(data.memory.total, 0);
+        assert!(data.processes.is_empty() && data.disks.is_empty());
+    }
+
+    #[test]
+    fn include_and_exclude() {
+        let options = JsonConfig {
+            processes: 2,
+            include: vec![
+                "cpu.usage".to_string(),
+                "disks.mount_point".to_string(),
+                "processes".to_string(),
+            ],
+            exclude: vec![
+                "processes.parent".to_string(),
+                "processes.user".to_string(),
+                "processes.cgroup".to_string(),
+            ],
+            ..JsonConfig::default()
+        };
+        assert_eq!(
+            encode(&fake::snapshot(0), &options).unwrap(),
+            concat!(
+                r#"{"cpu":{"usage":25.0},"#,
+                r#""disks":[{"mount_point":"/"},{"mount_point":"/media/usb"}],"#,
+                r#""processes":[{"name":"cargo","pid":2000,"cpu_usage":45.0,"memory":1258291200,"container":"4f6e5d0c3b2a"},"#,
+                r#"{"name":"postgres","pid":1200,"cpu_usage":12.5,"memory":943718400,"container":null}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn unknown_fields() {
+        let options = JsonConfig {
+            exclude: vec!["cpu".to_string(), "gpu.usage".to_string()],
+            ..JsonConfig::default()
+        };
+        let error = validate(&options).unwrap_err().to_string();
+        assert!(
+            error.starts_with("Unknown JSON field `gpu.usage`"),
+            "{}",
+            error
+        );
+    }
+}
@@ -1,36 +1,48 @@
 // src/render/mod.rs
-pub mod simple; // Your current render logic
-pub mod tui; // New ratatui render logic
+pub mod bar; // i3bar/swaybar and waybar output
+pub mod dashboard; // Multi-host view of remote agents
+pub mod json; // --json output
+pub mod prometheus; // Prometheus exporter
+pub mod simple; // Plain text render logic
+pub mod template; // conky style TEXT templates
+pub mod tui; // Ratatui render logic
 
-// Remove or comment out the unused parts:
-// pub use simple::*;  // Remove this line if you're not using it elsewhere
+use crate::alerts::AlertMonitor;
+use crate::data::{History, Snapshot};
+use crate::widgets::WidgetGrid;
 
-// If you want to keep the old API, use conditional compilation:
-#[cfg(not(feature = "tui"))]
-pub use simple::*;
-
-// Remove or comment out the unused enum and function:
-/*
-pub enum RenderMode {
-    Simple,
-    Tui,
+// Plain text entry points, forwarded to simple
+pub fn clear_screen() -> anyhow::Result<()> {
+    simple::clear_screen()
 }
 
-pub fn render(system_info: &crate::data::SystemInfo, mode: RenderMode) -> anyhow::Result<()> {
-    match mode {
-        RenderMode::Simple => simple::draw(system_info),
-        RenderMode::Tui => {
-            anyhow::bail!("TUI mode should be launched via launch_tui() function");
-        }
-    }
+pub fn draw(
+    grid: &WidgetGrid,
+    snapshot: &Snapshot,
+    history: &History,
+    alerts: &AlertMonitor,
+) -> anyhow::Result<()> {
+    simple::draw(grid, snapshot, history, alerts)
 }
-*/
 
-// Keep these functions - they're used by your main.rs
-pub fn clear_screen() -> anyhow::Result<()> {
-    simple::clear_screen()
-}
+/// Compare against `src/render/snapshots/<name>.txt`; UPDATE_SNAPSHOTS=1 rewrites the file
+#[cfg(test)]
+pub(crate) fn assert_snapshot(name: &str, actual: &str) {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/render/snapshots")
+        .join(format!("{}.txt", name));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, format!("{}\n", actual)).unwrap();
+        return;
+    }
 
-pub fn draw(system_info: &crate::data::SystemInfo) -> anyhow::Result<()> {
-    simple::draw(system_info)
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("missing {}, run with UPDATE_SNAPSHOTS=1", path.display()));
+    assert_eq!(
+        actual,
+        expected.strip_suffix('\n').unwrap_or(&expected),
+        "{} changed, run with UPDATE_SNAPSHOTS=1 to accept",
+        name
+    );
 }
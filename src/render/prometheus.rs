@@ -0,0 +1,748 @@
+// src/render/prometheus.rs
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+use crate::config::Config;
+use crate::data::snapshot::{CgroupGroup, DiskData, NetworkData, ProcessData};
+use crate::data::{RefreshScheduler, Snapshot, SystemInfo};
+
+// Process names exported per scrape, by CPU usage
+const TOP_PROCESSES: usize = 10;
+// Requests with a bigger head than this are dropped
+const MAX_REQUEST: usize = 8192;
+// Clients that take longer than this to send their request are dropped
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Refresh in the background and answer scrapes on `addr` with the latest snapshot
+pub async fn serve(addr: &str, config: &Config) -> Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Cannot listen on {}", addr))?;
+    println!("📡 Serving Prometheus metrics on http://{}/metrics", addr);
+
+    let mut scheduler = RefreshScheduler::all(Duration::from_secs(config.update_interval.max(1)));
+    // Primed so the first scrape already has CPU usage and rates
+    let mut system_info = SystemInfo::primed(config, &mut scheduler).await;
+    let (sender, latest) = watch::channel(system_info.snapshot());
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(scheduler.until_next()).await;
+            // sysinfo reads /proc synchronously, keep it off the threads answering scrapes
+            let refresh = tokio::task::spawn_blocking(move || {
+                let snapshot = scheduler
+                    .refresh_due(&mut system_info)
+                    .then(|| system_info.snapshot());
+                (system_info, scheduler, snapshot)
+            });
+            let Ok((refreshed, rescheduled, snapshot)) = refresh.await else {
+                return;
+            };
+            (system_info, scheduler) = (refreshed, rescheduled);
+            if let Some(snapshot) = snapshot {
+                sender.send_replace(snapshot);
+            }
+        }
+    });
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let latest = latest.clone();
+        tokio::spawn(async move {
+            // A client hanging up mid-request is not worth reporting
+            let _ = handle_connection(stream, &latest).await;
+        });
+    }
+}
+
+/// Read up to the end of the request head, None when the client hangs up or sends too much
+async fn read_request(stream: &mut (impl AsyncRead + Unpin)) -> Result<Option<Vec<u8>>> {
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 || request.len() + read > MAX_REQUEST {
+            return Ok(None);
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+    Ok(Some(request))
+}
+
+async fn handle_connection(
+    mut stream: impl AsyncRead + AsyncWrite + Unpin,
+    latest: &watch::Receiver<Snapshot>,
+) -> Result<()> {
+    let Ok(request) = tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream)).await else {
+        return Ok(());
+    };
+    let Some(request) = request? else {
+        return Ok(());
+    };
+
+    let request = String::from_utf8_lossy(&request);
+    let mut parts = request.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let (status, content_type, body) = match (method, path) {
+        ("GET" | "HEAD", "/metrics") => (
+            "200 OK",
+            "text/plain; version=0.0.4; charset=utf-8",
+            encode(&latest.borrow()),
+        ),
+        ("GET" | "HEAD", "/") => (
+            "200 OK",
+            "text/html; charset=utf-8",
+            "<html><body><a href=\"/metrics\">Metrics</a></body></html>\n".to_string(),
+        ),
+        ("GET" | "HEAD", _) => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method not allowed\n".to_string(),
+        ),
+    };
+
+    let mut response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    if method != "HEAD" {
+        response.push_str(&body);
+    }
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Prometheus text exposition format writer
+#[derive(Default)]
+struct Exposition {
+    out: String,
+}
+
+impl Exposition {
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.out, "# HELP {} {}", name, escape_help(help));
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind);
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(label, value)| format!("{}=\"{}\"", label, escape(value)))
+                .collect();
+            let _ = write!(self.out, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.out, " {}", value);
+    }
+
+    /// A family with a single unlabelled sample
+    fn single(&mut self, name: &str, kind: &str, help: &str, value: f64) {
+        self.family(name, kind, help);
+        self.sample(name, &[], value);
+    }
+}
+
+/// Name, type, help text and how to read the value off each item
+type Family<T> = (&'static str, &'static str, &'static str, fn(&T) -> f64);
+
+/// Label values escape backslashes, quotes and newlines
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// HELP text escapes only backslashes and newlines
+fn escape_help(help: &str) -> String {
+    help.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+/// Processes sharing a name and user; labelling by PID would start new series all the time
+struct ProcessGroup<'a> {
+    name: &'a str,
+    user: &'a str,
+    cpu_usage: f64,
+    memory: u64,
+}
+
+/// The `TOP_PROCESSES` busiest groups by CPU usage
+fn process_groups(processes: &[ProcessData]) -> Vec<ProcessGroup<'_>> {
+    let mut groups: Vec<ProcessGroup> = Vec::new();
+    for process in processes {
+        let index = match groups
+            .iter()
+            .position(|group| group.name == process.name && group.user == process.user)
+        {
+            Some(index) => index,
+            None => {
+                groups.push(ProcessGroup {
+                    name: &process.name,
+                    user: &process.user,
+                    cpu_usage: 0.0,
+                    memory: 0,
+                });
+                groups.len() - 1
+            }
+        };
+        groups[index].cpu_usage += process.cpu_usage as f64;
+        groups[index].memory += process.memory;
+    }
+    groups.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage));
+    groups.truncate(TOP_PROCESSES);
+    groups
+}
+
+/// Render a snapshot as Prometheus metrics
+pub fn encode(snapshot: &Snapshot) -> String {
+    let mut metrics = Exposition::default();
+    let cpu = &snapshot.cpu;
+
+    metrics.single(
+        "conky_cpu_usage_percent",
+        "gauge",
+        "Global CPU usage.",
+        cpu.usage as f64,
+    );
+    metrics.single(
+        "conky_cpu_logical_cores",
+        "gauge",
+        "Number of logical cores.",
+        cpu.count as f64,
+    );
+    if let Some(physical) = cpu.physical_count {
+        metrics.single(
+            "conky_cpu_physical_cores",
+            "gauge",
+            "Number of physical cores.",
+            physical as f64,
+        );
+    }
+
+    metrics.family("conky_cpu_core_usage_percent", "gauge", "Usage per core.");
+    for core in &cpu.cores {
+        metrics.sample(
+            "conky_cpu_core_usage_percent",
+            &[("core", &core.name)],
+            core.usage as f64,
+        );
+    }
+    metrics.family(
+        "conky_cpu_core_frequency_mhz",
+        "gauge",
+        "Current frequency per core.",
+    );
+    for core in &cpu.cores {
+        metrics.sample(
+            "conky_cpu_core_frequency_mhz",
+            &[("core", &core.name)],
+            core.frequency as f64,
+        );
+    }
+
+    let load = &cpu.load_average;
+    metrics.family("conky_load_average", "gauge", "System load average.");
+    for (window, value) in [("1m", load.one), ("5m", load.five), ("15m", load.fifteen)] {
+        metrics.sample("conky_load_average", &[("window", window)], value);
+    }
+
+    let memory = &snapshot.memory;
+    metrics.single(
+        "conky_memory_used_bytes",
+        "gauge",
+        "RAM in use.",
+        memory.used as f64,
+    );
+    metrics.single(
+        "conky_memory_total_bytes",
+        "gauge",
+        "Total RAM.",
+        memory.total as f64,
+    );
+    if let Some(details) = &memory.details {
+        for (name, help, value) in [
+            (
+                "conky_memory_available_bytes",
+                "RAM available without swapping.",
+                details.available,
+            ),
+            ("conky_memory_free_bytes", "Unused RAM.", details.free),
+            (
+                "conky_memory_cached_bytes",
+                "Page cache and reclaimable slab.",
+                details.cached,
+            ),
+            (
+                "conky_memory_buffers_bytes",
+                "Block device buffers.",
+                details.buffers,
+            ),
+            (
+                "conky_memory_shared_bytes",
+                "tmpfs and shared memory.",
+                details.shared,
+            ),
+            (
+                "conky_memory_dirty_bytes",
+                "Waiting to be written back to disk.",
+                details.dirty,
+            ),
+            (
+                "conky_memory_writeback_bytes",
+                "Being written back to disk.",
+                details.writeback,
+            ),
+        ] {
+            metrics.single(name, "gauge", help, value as f64);
+        }
+    }
+    if let Some(pressure) = &memory.pressure {
+        metrics.family(
+            "conky_memory_pressure_percent",
+            "gauge",
+            "Share of time tasks stalled on memory, `some` for any task and `full` for all of them.",
+        );
+        for (kind, window, value) in [
+            ("some", "10s", pressure.some_avg10),
+            ("some", "60s", pressure.some_avg60),
+            ("some", "300s", pressure.some_avg300),
+            ("full", "10s", pressure.full_avg10),
+            ("full", "60s", pressure.full_avg60),
+            ("full", "300s", pressure.full_avg300),
+        ] {
+            metrics.sample(
+                "conky_memory_pressure_percent",
+                &[("kind", kind), ("window", window)],
+                value,
+            );
+        }
+    }
+    metrics.single(
+        "conky_swap_used_bytes",
+        "gauge",
+        "Swap in use.",
+        memory.used_swap as f64,
+    );
+    metrics.single(
+        "conky_swap_total_bytes",
+        "gauge",
+        "Total swap.",
+        memory.total_swap as f64,
+    );
+
+    let disk_families: [Family<DiskData>; 9] = [
+        (
+            "conky_disk_total_bytes",
+            "gauge",
+            "Filesystem size.",
+            |disk| disk.total as f64,
+        ),
+        (
+            "conky_disk_available_bytes",
+            "gauge",
+            "Space left on the filesystem.",
+            |disk| disk.available as f64,
+        ),
+        (
+            "conky_disk_inodes",
+            "gauge",
+            "Inodes on the filesystem.",
+            |disk| disk.inodes_total as f64,
+        ),
+        (
+            "conky_disk_inodes_free",
+            "gauge",
+            "Inodes left on the filesystem.",
+            |disk| disk.inodes_free as f64,
+        ),
+        (
+            "conky_disk_read_bytes_per_second",
+            "gauge",
+            "Bytes read per second over the last refresh.",
+            |disk| disk.read_per_sec,
+        ),
+        (
+            "conky_disk_written_bytes_per_second",
+            "gauge",
+            "Bytes written per second over the last refresh.",
+            |disk| disk.written_per_sec,
+        ),
+        (
+            "conky_disk_read_iops",
+            "gauge",
+            "Read requests completed per second over the last refresh.",
+            |disk| disk.read_iops,
+        ),
+        (
+            "conky_disk_write_iops",
+            "gauge",
+            "Write requests completed per second over the last refresh.",
+            |disk| disk.write_iops,
+        ),
+        (
+            "conky_disk_busy_percent",
+            "gauge",
+            "Time the device spent serving requests over the last refresh.",
+            |disk| disk.busy_percent,
+        ),
+    ];
+    for (name, kind, help, value) in disk_families {
+        metrics.family(name, kind, help);
+        for disk in &snapshot.disks {
+            metrics.sample(
+                name,
+                &[("device", &disk.name), ("mountpoint", &disk.mount_point)],
+                value(disk),
+            );
+        }
+    }
+
+    let network_families: [Family<NetworkData>; 10] = [
+        (
+            "conky_network_receive_bytes_per_second",
+            "gauge",
+            "Bytes received per second over the last refresh.",
+            |network| network.received_per_sec,
+        ),
+        (
+            "conky_network_transmit_bytes_per_second",
+            "gauge",
+            "Bytes sent per second over the last refresh.",
+            |network| network.transmitted_per_sec,
+        ),
+        (
+            "conky_network_receive_bytes_total",
+            "counter",
+            "Bytes received since boot.",
+            |network| network.total_received as f64,
+        ),
+        (
+            "conky_network_transmit_bytes_total",
+            "counter",
+            "Bytes sent since boot.",
+            |network| network.total_transmitted as f64,
+        ),
+        (
+            "conky_network_receive_packets_total",
+            "counter",
+            "Packets received since boot.",
+            |network| network.packets_received as f64,
+        ),
+        (
+            "conky_network_transmit_packets_total",
+            "counter",
+            "Packets sent since boot.",
+            |network| network.packets_transmitted as f64,
+        ),
+        (
+            "conky_network_receive_errors_total",
+            "counter",
+            "Receive errors since boot.",
+            |network| network.errors_received as f64,
+        ),
+        (
+            "conky_network_transmit_errors_total",
+            "counter",
+            "Transmit errors since boot.",
+            |network| network.errors_transmitted as f64,
+        ),
+        (
+            "conky_network_receive_dropped_total",
+            "counter",
+            "Received packets dropped since boot.",
+            |network| network.dropped_received as f64,
+        ),
+        (
+            "conky_network_transmit_dropped_total",
+            "counter",
+            "Outgoing packets dropped since boot.",
+            |network| network.dropped_transmitted as f64,
+        ),
+    ];
+    for (name, kind, help, value) in network_families {
+        metrics.family(name, kind, help);
+        for network in &snapshot.network {
+            metrics.sample(name, &[("interface", &network.interface)], value(network));
+        }
+    }
+
+    metrics.single(
+        "conky_uptime_seconds",
+        "gauge",
+        "Time since boot.",
+        snapshot.system.uptime as f64,
+    );
+
+    let power = &snapshot.power;
+    if !power.batteries.is_empty() {
+        metrics.family(
+            "conky_battery_capacity_percent",
+            "gauge",
+            "Charge left per battery.",
+        );
+        for battery in &power.batteries {
+            if let Some(capacity) = battery.capacity {
+                metrics.sample(
+                    "conky_battery_capacity_percent",
+                    &[("battery", &battery.name)],
+                    capacity,
+                );
+            }
+        }
+        metrics.family(
+            "conky_battery_status",
+            "gauge",
+            "Charging status of each battery in the `status` label, always 1.",
+        );
+        for battery in &power.batteries {
+            metrics.sample(
+                "conky_battery_status",
+                &[("battery", &battery.name), ("status", &battery.status)],
+                1.0,
+            );
+        }
+        metrics.family(
+            "conky_battery_power_watts",
+            "gauge",
+            "Power drawn from or into each battery.",
+        );
+        for battery in &power.batteries {
+            if let Some(power) = battery.power_now {
+                metrics.sample(
+                    "conky_battery_power_watts",
+                    &[("battery", &battery.name)],
+                    power,
+                );
+            }
+        }
+    }
+    if let Some(online) = power.ac_online {
+        metrics.single(
+            "conky_ac_online",
+            "gauge",
+            "Whether a mains adapter is plugged in.",
+            if online { 1.0 } else { 0.0 },
+        );
+    }
+
+    let sensors = &snapshot.sensors;
+    metrics.family(
+        "conky_temperature_celsius",
+        "gauge",
+        "Hardware sensor temperatures.",
+    );
+    for temperature in &sensors.temperatures {
+        metrics.sample(
+            "conky_temperature_celsius",
+            &[
+                ("chip", &temperature.chip),
+                ("device", &temperature.device),
+                ("sensor", &temperature.label),
+            ],
+            temperature.celsius,
+        );
+    }
+    metrics.family(
+        "conky_temperature_critical_celsius",
+        "gauge",
+        "Critical threshold of sensors that report one.",
+    );
+    for temperature in &sensors.temperatures {
+        if let Some(critical) = temperature.critical {
+            metrics.sample(
+                "conky_temperature_critical_celsius",
+                &[
+                    ("chip", &temperature.chip),
+                    ("device", &temperature.device),
+                    ("sensor", &temperature.label),
+                ],
+                critical,
+            );
+        }
+    }
+    metrics.family("conky_fan_rpm", "gauge", "Fan speeds.");
+    for fan in &sensors.fans {
+        metrics.sample(
+            "conky_fan_rpm",
+            &[
+                ("chip", &fan.chip),
+                ("device", &fan.device),
+                ("fan", &fan.label),
+            ],
+            fan.rpm as f64,
+        );
+    }
+
+    // Grouped the way the TUI shows them, per container or unit
+    let groups = snapshot.cgroup_groups();
+    let cgroup_families: [Family<CgroupGroup>; 4] = [
+        (
+            "conky_cgroup_cpu_usage_percent",
+            "gauge",
+            "CPU usage per container or systemd unit, 100 is one core.",
+            |group| group.cpu_usage,
+        ),
+        (
+            "conky_cgroup_memory_bytes",
+            "gauge",
+            "Memory charged to each container or systemd unit.",
+            |group| group.memory as f64,
+        ),
+        (
+            "conky_cgroup_read_bytes_per_second",
+            "gauge",
+            "Block device bytes read per second over the last refresh.",
+            |group| group.read_per_sec,
+        ),
+        (
+            "conky_cgroup_written_bytes_per_second",
+            "gauge",
+            "Block device bytes written per second over the last refresh.",
+            |group| group.written_per_sec,
+        ),
+    ];
+    for (name, kind, help, value) in cgroup_families {
+        metrics.family(name, kind, help);
+        for group in &groups {
+            metrics.sample(name, &[("cgroup", &group.name)], value(group));
+        }
+    }
+    metrics.family(
+        "conky_cgroup_memory_limit_bytes",
+        "gauge",
+        "Memory limit of containers and units that have one.",
+    );
+    for group in &groups {
+        if let Some(limit) = group.memory_limit {
+            metrics.sample(
+                "conky_cgroup_memory_limit_bytes",
+                &[("cgroup", &group.name)],
+                limit as f64,
+            );
+        }
+    }
+
+    let process_families: [Family<ProcessGroup>; 2] = [
+        (
+            "conky_process_cpu_usage_percent",
+            "gauge",
+            "CPU usage of the busiest process names, summed per user.",
+            |group| group.cpu_usage,
+        ),
+        (
+            "conky_process_memory_bytes",
+            "gauge",
+            "Resident memory of the busiest process names, summed per user.",
+            |group| group.memory as f64,
+        ),
+    ];
+    let groups = process_groups(&snapshot.processes);
+    for (name, kind, help, value) in process_families {
+        metrics.family(name, kind, help);
+        for group in &groups {
+            metrics.sample(
+                name,
+                &[("name", group.name), ("user", group.user)],
+                value(group),
+            );
+        }
+    }
+
+    metrics.out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fake;
+    use crate::data::snapshot::TemperatureData;
+    use crate::render::assert_snapshot;
+
+    #[test]
+    fn full_exposition() {
+        assert_snapshot("prometheus_full", &encode(&fake::snapshot(0)));
+    }
+
+    #[test]
+    fn escapes_labels_and_help() {
+        let mut metrics = Exposition::default();
+        metrics.family(
+            "conky_test",
+            "gauge",
+            "Back\\slash \"quoted\"\nsecond line.",
+        );
+        metrics.sample("conky_test", &[("path", "C:\\dir \"x\"\ny")], 1.5);
+        assert_eq!(
+            metrics.out,
+            "# HELP conky_test Back\\\\slash \"quoted\"\\nsecond line.\n\
+             # TYPE conky_test gauge\n\
+             conky_test{path=\"C:\\\\dir \\\"x\\\"\\ny\"} 1.5\n"
+        );
+    }
+
+    /// Send a raw request and return the whole response
+    async fn request(request: &str) -> String {
+        let (_sender, latest) = watch::channel(fake::snapshot(0));
+        let (mut client, server) = tokio::io::duplex(64 * 1024);
+        client.write_all(request.as_bytes()).await.unwrap();
+        handle_connection(server, &latest).await.unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn answers_scrapes() {
+        let response = request("GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.contains("\r\n\r\n# HELP conky_cpu_usage_percent"));
+        assert!(response.contains("\nconky_cpu_usage_percent 25\n"));
+
+        let response = request("HEAD /metrics HTTP/1.1\r\n\r\n").await;
+        assert!(response.ends_with("Connection: close\r\n\r\n"));
+        let response = request("GET /nope HTTP/1.1\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        let response = request("POST /metrics HTTP/1.1\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+
+        // A head that never ends is dropped without an answer
+        assert_eq!(request(&"x".repeat(MAX_REQUEST + 1)).await, "");
+    }
+
+    #[test]
+    fn identical_chips_stay_apart() {
+        // Two drives of the same model both show up as nvme / Composite
+        let drive = |device: &str, celsius: f64| TemperatureData {
+            chip: "nvme".to_string(),
+            device: device.to_string(),
+            label: "Composite".to_string(),
+            celsius,
+            ..TemperatureData::default()
+        };
+        let mut snapshot = fake::snapshot(0);
+        snapshot.sensors.temperatures = vec![drive("hwmon1", 38.0), drive("hwmon2", 41.0)];
+
+        let metrics = encode(&snapshot);
+        let series: Vec<&str> = metrics
+            .lines()
+            .filter(|line| line.starts_with("conky_temperature_celsius{"))
+            .collect();
+        assert_eq!(
+            series,
+            [
+                "conky_temperature_celsius{chip=\"nvme\",device=\"hwmon1\",sensor=\"Composite\"} 38",
+                "conky_temperature_celsius{chip=\"nvme\",device=\"hwmon2\",sensor=\"Composite\"} 41",
+            ]
+        );
+    }
+}
@@ -1,119 +1,157 @@
 // src/render/simple.rs
 use crossterm::{
     cursor,
+    style::Stylize,
     terminal::{Clear, ClearType},
     ExecutableCommand,
 };
 use std::io::{self, Write};
 
-pub fn draw(system_info: &crate::data::SystemInfo) -> anyhow::Result<()> {
+use super::template::Template;
+use crate::alerts::AlertMonitor;
+use crate::data::{History, Snapshot};
+use crate::widgets::WidgetGrid;
+
+// Most recent alert log entries printed under the widgets
+const ALERT_LINES: usize = 5;
+
+pub fn draw(
+    grid: &WidgetGrid,
+    snapshot: &Snapshot,
+    history: &History,
+    alerts: &AlertMonitor,
+) -> anyhow::Result<()> {
     let mut stdout = io::stdout();
 
     // Move cursor to top-left and clear from cursor to end of screen
     stdout.execute(cursor::MoveTo(0, 0))?;
     stdout.execute(Clear(ClearType::FromCursorDown))?;
 
-    // Header
-    println!("=== Rust Conky System Monitor ===");
-    println!();
-
-    // CPU Information
-    let cpu_usage = system_info.cpu_usage();
-    let cpu_count = system_info.cpu_count();
-    let load_avg = system_info.load_average();
-
-    println!("CPU: {:.1}% ({} cores)", cpu_usage, cpu_count);
-    println!(
-        "Load Average: {:.2}, {:.2}, {:.2}",
-        load_avg.0, load_avg.1, load_avg.2
-    );
-    println!();
-
-    // Memory Information
-    let (used_mem, total_mem) = system_info.memory_usage();
-    let (used_swap, total_swap) = system_info.swap_usage();
-    let used_mem_gb = used_mem as f64 / 1024.0 / 1024.0 / 1024.0;
-    let total_mem_gb = total_mem as f64 / 1024.0 / 1024.0 / 1024.0;
-    let mem_percentage = if total_mem > 0 {
-        (used_mem as f64 / total_mem as f64) * 100.0
-    } else {
-        0.0
-    };
-
-    println!(
-        "Memory: {:.2}GB / {:.2}GB ({:.1}%)",
-        used_mem_gb, total_mem_gb, mem_percentage
-    );
-
-    if total_swap > 0 {
-        let used_swap_gb = used_swap as f64 / 1024.0 / 1024.0 / 1024.0;
-        let total_swap_gb = total_swap as f64 / 1024.0 / 1024.0 / 1024.0;
-        let swap_percentage = (used_swap as f64 / total_swap as f64) * 100.0;
-
-        println!(
-            "Swap:   {:.2}GB / {:.2}GB ({:.1}%)",
-            used_swap_gb, total_swap_gb, swap_percentage
-        );
+    for line in render(grid, snapshot, history, alerts) {
+        println!("{}", line);
     }
-    println!();
-
-    // Disk Information
-    let disk_stats = system_info.disk_stats();
-    if !disk_stats.is_empty() {
-        println!("Disks:");
-
-        for (name, total, available, mount_point) in disk_stats {
-            let used = total.saturating_sub(available);
-            let used_gb = used as f64 / 1024.0 / 1024.0 / 1024.0;
-            let total_gb = total as f64 / 1024.0 / 1024.0 / 1024.0;
-            let percentage = if total > 0 {
-                (used as f64 / total as f64) * 100.0
-            } else {
-                0.0
-            };
 
-            println!(
-                "  {} ({}) {:.1}GB / {:.1}GB ({:.1}%)",
-                name, mount_point, used_gb, total_gb, percentage
-            );
-        }
-        println!();
-    }
+    stdout.flush()?;
+    Ok(())
+}
 
-    // Network Information
-    let network_stats = system_info.network_stats();
-    if !network_stats.is_empty() {
-        println!("Network Interfaces:");
+/// The lines `draw` prints, alerting widgets already colored
+pub fn render(
+    grid: &WidgetGrid,
+    snapshot: &Snapshot,
+    history: &History,
+    alerts: &AlertMonitor,
+) -> Vec<String> {
+    // Header
+    let mut lines = vec![
+        "=== Rust Conky System Monitor ===".to_string(),
+        String::new(),
+    ];
+
+    let widths = column_widths(grid, snapshot, history);
+
+    for row in grid.rows() {
+        let cells: Vec<TextCell> = row
+            .iter()
+            .map(|cell| TextCell {
+                column: cell.column as usize,
+                lines: cell.widget.render_text(snapshot, history),
+                alert: alerts.is_alerting(cell.widget_type),
+            })
+            .filter(|cell| !cell.lines.is_empty())
+            .collect();
+        if cells.is_empty() {
+            continue;
+        }
 
-        for (interface, received, transmitted) in network_stats {
-            let received_mb = received as f64 / 1024.0 / 1024.0;
-            let transmitted_mb = transmitted as f64 / 1024.0 / 1024.0;
+        lines.extend(layout_row(&cells, &widths));
+        lines.push(String::new());
+    }
 
-            println!(
-                "  {}: ↓ {:.2}MB ↑ {:.2}MB",
-                interface, received_mb, transmitted_mb
-            );
+    let log = alerts.log();
+    if !log.is_empty() {
+        lines.push("Alerts:".to_string());
+        for event in log.iter().skip(log.len().saturating_sub(ALERT_LINES)) {
+            let message = format!("  {}", event.message());
+            if event.fired {
+                lines.push(message.red().to_string());
+            } else {
+                lines.push(message);
+            }
         }
-        println!();
     }
 
-    // Top Processes
-    let top_processes = system_info.top_processes(5);
-    if !top_processes.is_empty() {
-        println!("Top Processes (by CPU):");
+    lines
+}
 
-        for (name, pid, cpu, memory) in top_processes {
-            let memory_mb = memory as f64 / 1024.0 / 1024.0;
-            println!("  {:6} {:.1}% {:.1}MB {}", pid, cpu, memory_mb, name);
-        }
-        println!();
+/// Widest line of each grid column, so columns line up across rows
+fn column_widths(grid: &WidgetGrid, snapshot: &Snapshot, history: &History) -> Vec<usize> {
+    let mut widths = vec![0; grid.columns() as usize];
+    for cell in grid.rows().iter().flatten() {
+        let width = cell
+            .widget
+            .render_text(snapshot, history)
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let column = cell.column as usize;
+        widths[column] = widths[column].max(width);
     }
+    widths
+}
 
-    // System Uptime
-    let uptime = system_info.uptime();
-    let hours = uptime / 3600;
-    let minutes = (uptime % 3600) / 60;
-    println!("Uptime: {} hours, {} minutes", hours, minutes);
+/// A widget's text and where it goes
+struct TextCell {
+    column: usize,
+    lines: Vec<String>,
+    /// Printed in red while an alert concerns the widget
+    alert: bool,
+}
+
+/// Place the text of one grid row side by side
+fn layout_row(cells: &[TextCell], widths: &[usize]) -> Vec<String> {
+    let height = cells.iter().map(|cell| cell.lines.len()).max().unwrap_or(0);
+    let last_column = cells.iter().map(|cell| cell.column).max().unwrap_or(0);
+
+    (0..height)
+        .map(|index| {
+            let mut line = String::new();
+            // Padding is only written once something follows it
+            let mut padding = 0;
+            for (column, width) in widths.iter().enumerate().take(last_column + 1) {
+                if *width == 0 {
+                    continue;
+                }
+                let cell = cells.iter().find(|cell| cell.column == column);
+                let text = cell
+                    .and_then(|cell| cell.lines.get(index))
+                    .map(String::as_str)
+                    .unwrap_or("");
+                if !text.is_empty() {
+                    line.push_str(&" ".repeat(padding));
+                    padding = 0;
+                    match cell {
+                        Some(cell) if cell.alert => line.push_str(&text.red().to_string()),
+                        _ => line.push_str(text),
+                    }
+                }
+                padding += width.saturating_sub(text.chars().count()) + 2;
+            }
+            line
+        })
+        .collect()
+}
+
+/// Print a user template in place of the widget grid
+pub fn draw_template(template: &Template, snapshot: &Snapshot) -> anyhow::Result<()> {
+    let mut stdout = io::stdout();
+
+    stdout.execute(cursor::MoveTo(0, 0))?;
+    stdout.execute(Clear(ClearType::FromCursorDown))?;
+
+    // Raw mode is off, so a bare \n already returns to the first column
+    print!("{}", template.render(snapshot));
 
     stdout.flush()?;
     Ok(())
@@ -126,3 +164,85 @@ pub fn clear_screen() -> anyhow::Result<()> {
     stdout.flush()?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AlertMetric, AlertRule, WidgetConfig, WidgetType};
+    use crate::data::fake;
+    use crate::render::assert_snapshot;
+
+    fn history() -> History {
+        let mut history = History::default();
+        for step in 0..3 {
+            history.record(&fake::snapshot(step));
+        }
+        history
+    }
+
+    #[test]
+    fn default_grid() {
+        let alerts = AlertMonitor::new(&[]).unwrap();
+        let lines = render(
+            &WidgetGrid::default(),
+            &fake::snapshot(2),
+            &history(),
+            &alerts,
+        );
+        assert_snapshot("simple_default", &lines.join("\n"));
+    }
+
+    #[test]
+    fn columns_and_alerts() {
+        let widget = |widget_type, row, column| WidgetConfig {
+            widget_type,
+            position: [row, column],
+            update_interval: None,
+        };
+        let grid = WidgetGrid::from_config(&[
+            widget(WidgetType::Cpu, 0, 0),
+            widget(WidgetType::Memory, 0, 1),
+            widget(WidgetType::Battery, 1, 0),
+            widget(WidgetType::Sensors, 1, 1),
+        ])
+        .unwrap();
+        let mut alerts = AlertMonitor::new(&[AlertRule {
+            metric: AlertMetric::Memory,
+            above: 30.0,
+            mount: None,
+            for_secs: 0,
+            command: None,
+            stderr: false,
+        }])
+        .unwrap();
+        let snapshot = fake::snapshot(2);
+        alerts.check(&snapshot);
+
+        let lines = render(&grid, &snapshot, &history(), &alerts);
+        let memory = lines.iter().find(|line| line.contains("Memory:")).unwrap();
+        assert!(memory.contains("\u{1b}[38;5;9mMemory:"), "{:?}", memory);
+
+        // The alert log has local times in it, only the widgets are stable
+        let widgets: Vec<String> = lines
+            .iter()
+            .take_while(|line| !line.starts_with("Alerts:"))
+            .map(|line| strip_colors(line))
+            .collect();
+        assert_snapshot("simple_columns", &widgets.join("\n"));
+        assert!(lines.iter().any(|line| line.contains("FIRED memory > 30%")));
+    }
+
+    fn strip_colors(line: &str) -> String {
+        let mut plain = String::new();
+        let mut chars = line.chars();
+        while let Some(c) = chars.next() {
+            if c == '\u{1b}' {
+                // Skip to the end of the escape sequence
+                chars.by_ref().find(|c| c.is_ascii_alphabetic());
+            } else {
+                plain.push(c);
+            }
+        }
+        plain
+    }
+}
@@ -0,0 +1,510 @@
+// src/render/template.rs - conky style TEXT templates
+use anyhow::{bail, Context, Result};
+
+use crate::data::{DataSource, RefreshScheduler, Snapshot};
+use crate::widgets::{format_rate, percentage};
+
+/// A parsed template such as `CPU ${cpu}% RAM ${mem}/${memmax}`
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+enum Segment {
+    Text(String),
+    Variable(Variable),
+}
+
+enum Variable {
+    /// Usage in percent; core numbers start at 1 like conky, 0 is the total
+    Cpu(usize),
+    /// MHz of a core, the first one by default
+    Freq(usize),
+    Mem,
+    MemMax,
+    MemFree,
+    MemPerc,
+    Swap,
+    SwapMax,
+    SwapFree,
+    SwapPerc,
+    Fs(FsValue, String),
+    Net(NetValue, String),
+    /// `None` prints all three averages
+    LoadAvg(Option<usize>),
+    Uptime,
+    UptimeShort,
+    /// Nth busiest process by CPU, starting at 1
+    Top(TopValue, usize),
+    Processes,
+    DiskIo(DiskIoValue),
+    /// strftime format
+    Time(String),
+}
+
+#[derive(Clone, Copy)]
+enum FsValue {
+    Used,
+    Size,
+    Free,
+    UsedPerc,
+    FreePerc,
+}
+
+#[derive(Clone, Copy)]
+enum NetValue {
+    DownSpeed,
+    UpSpeed,
+    TotalDown,
+    TotalUp,
+}
+
+#[derive(Clone, Copy)]
+enum TopValue {
+    Name,
+    Pid,
+    Cpu,
+    /// Share of total RAM in percent
+    Mem,
+    MemRes,
+    User,
+}
+
+#[derive(Clone, Copy)]
+enum DiskIoValue {
+    Total,
+    Read,
+    Write,
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c != '$' {
+                literal.push(c);
+                continue;
+            }
+
+            let expression = match chars.peek() {
+                Some('$') => {
+                    chars.next();
+                    literal.push('$');
+                    continue;
+                }
+                Some('{') => {
+                    chars.next();
+                    let mut expression = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => expression.push(c),
+                            None => bail!("Unclosed `${{{}` in template", expression),
+                        }
+                    }
+                    expression
+                }
+                _ => {
+                    let mut name = String::new();
+                    while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                        name.push(c);
+                    }
+                    if name.is_empty() {
+                        literal.push('$');
+                        continue;
+                    }
+                    name
+                }
+            };
+
+            if !literal.is_empty() {
+                segments.push(Segment::Text(std::mem::take(&mut literal)));
+            }
+            let variable = Variable::parse(&expression)
+                .with_context(|| format!("Invalid template variable `${{{}}}`", expression))?;
+            segments.push(Segment::Variable(variable));
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Text(literal));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Fill in every variable from a snapshot
+    pub fn render(&self, snapshot: &Snapshot) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.clone(),
+                Segment::Variable(variable) => variable.evaluate(snapshot),
+            })
+            .collect()
+    }
+
+    /// Refresh only what the template's variables read
+    pub fn scheduler(&self, update_interval: u64) -> RefreshScheduler {
+        let interval = std::time::Duration::from_secs(update_interval.max(1));
+        let mut scheduler = RefreshScheduler::new();
+        for segment in &self.segments {
+            if let Segment::Variable(variable) = segment {
+                for source in variable.sources() {
+                    scheduler.every(*source, interval);
+                }
+            }
+        }
+        scheduler
+    }
+}
+
+impl Variable {
+    fn parse(expression: &str) -> Result<Self> {
+        let expression = expression.trim_start();
+        if expression.is_empty() {
+            bail!("empty variable");
+        }
+        // Everything after the name, as written; most variables split it into words
+        let (name, raw_args) = expression
+            .split_once(char::is_whitespace)
+            .map_or((expression, ""), |(name, rest)| (name, rest.trim_start()));
+        let args: Vec<&str> = raw_args.split_whitespace().collect();
+        let arg = |index: usize, what: &str| -> Result<String> {
+            match args.get(index) {
+                Some(arg) => Ok(arg.to_string()),
+                None => bail!("`{}` needs {}", name, what),
+            }
+        };
+        let number = |index: usize, default: usize| -> Result<usize> {
+            match args.get(index) {
+                Some(arg) => arg
+                    .parse()
+                    .with_context(|| format!("`{}` is not a number", arg)),
+                None => Ok(default),
+            }
+        };
+
+        Ok(match name {
+            "cpu" => Variable::Cpu(number(0, 0)?),
+            "freq" => Variable::Freq(number(0, 1)?),
+            "mem" => Variable::Mem,
+            "memmax" => Variable::MemMax,
+            "memfree" => Variable::MemFree,
+            "memperc" => Variable::MemPerc,
+            "swap" => Variable::Swap,
+            "swapmax" => Variable::SwapMax,
+            "swapfree" => Variable::SwapFree,
+            "swapperc" => Variable::SwapPerc,
+            "fs_used" => Variable::Fs(FsValue::Used, arg(0, "a mount point")?),
+            "fs_size" => Variable::Fs(FsValue::Size, arg(0, "a mount point")?),
+            "fs_free" => Variable::Fs(FsValue::Free, arg(0, "a mount point")?),
+            "fs_used_perc" => Variable::Fs(FsValue::UsedPerc, arg(0, "a mount point")?),
+            "fs_free_perc" => Variable::Fs(FsValue::FreePerc, arg(0, "a mount point")?),
+            "downspeed" => Variable::Net(NetValue::DownSpeed, arg(0, "an interface")?),
+            "upspeed" => Variable::Net(NetValue::UpSpeed, arg(0, "an interface")?),
+            "totaldown" => Variable::Net(NetValue::TotalDown, arg(0, "an interface")?),
+            "totalup" => Variable::Net(NetValue::TotalUp, arg(0, "an interface")?),
+            "loadavg" => match args.first() {
+                None => Variable::LoadAvg(None),
+                Some(_) => match number(0, 1)? {
+                    index @ 1..=3 => Variable::LoadAvg(Some(index)),
+                    _ => bail!("`loadavg` takes 1, 2 or 3"),
+                },
+            },
+            "uptime" => Variable::Uptime,
+            "uptime_short" => Variable::UptimeShort,
+            "top" => {
+                let value = match arg(0, "a field and a rank, e.g. `top name 1`")?.as_str() {
+                    "name" => TopValue::Name,
+                    "pid" => TopValue::Pid,
+                    "cpu" => TopValue::Cpu,
+                    "mem" => TopValue::Mem,
+                    "mem_res" => TopValue::MemRes,
+                    "user" => TopValue::User,
+                    other => bail!(
+                        "unknown `top` field `{}`, expected name, pid, cpu, mem, mem_res or user",
+                        other
+                    ),
+                };
+                match number(1, 1)? {
+                    0 => bail!("`top` ranks start at 1"),
+                    rank => Variable::Top(value, rank),
+                }
+            }
+            "processes" => Variable::Processes,
+            "diskio" => Variable::DiskIo(DiskIoValue::Total),
+            "diskio_read" => Variable::DiskIo(DiskIoValue::Read),
+            "diskio_write" => Variable::DiskIo(DiskIoValue::Write),
+            "time" => {
+                // Spacing inside the format is kept, `%H:%M  %S` is not `%H:%M %S`
+                let format = if args.is_empty() {
+                    "%H:%M:%S".to_string()
+                } else {
+                    raw_args.to_string()
+                };
+                // chrono panics while printing a bad format, so reject it up front
+                if chrono::format::StrftimeItems::new(&format)
+                    .any(|item| matches!(item, chrono::format::Item::Error))
+                {
+                    bail!("`{}` is not a valid time format", format);
+                }
+                Variable::Time(format)
+            }
+            _ => bail!("unknown variable `{}`", name),
+        })
+    }
+
+    fn sources(&self) -> &'static [DataSource] {
+        match self {
+            Variable::Cpu(_) | Variable::Freq(_) => &[DataSource::Cpu],
+            Variable::Mem
+            | Variable::MemMax
+            | Variable::MemFree
+            | Variable::MemPerc
+            | Variable::Swap
+            | Variable::SwapMax
+            | Variable::SwapFree
+            | Variable::SwapPerc => &[DataSource::Memory],
+            Variable::Fs(..) | Variable::DiskIo(_) => &[DataSource::Disks],
+            Variable::Net(..) => &[DataSource::Networks],
+            // Process memory share needs the RAM total too
+            Variable::Top(..) => &[DataSource::Processes, DataSource::Memory],
+            Variable::Processes => &[DataSource::Processes],
+            Variable::LoadAvg(_) | Variable::Uptime | Variable::UptimeShort | Variable::Time(_) => {
+                &[DataSource::Clock]
+            }
+        }
+    }
+
+    fn evaluate(&self, snapshot: &Snapshot) -> String {
+        let memory = &snapshot.memory;
+        match self {
+            Variable::Cpu(0) => format!("{:.0}", snapshot.cpu.usage),
+            Variable::Cpu(core) => snapshot
+                .cpu
+                .cores
+                .get(core - 1)
+                .map(|core| format!("{:.0}", core.usage))
+                .unwrap_or_else(missing),
+            Variable::Freq(core) => snapshot
+                .cpu
+                .cores
+                .get(core.saturating_sub(1))
+                .map(|core| core.frequency.to_string())
+                .unwrap_or_else(missing),
+            Variable::Mem => format_bytes(memory.used),
+            Variable::MemMax => format_bytes(memory.total),
+            Variable::MemFree => format_bytes(memory.total.saturating_sub(memory.used)),
+            Variable::MemPerc => format!("{:.0}", percentage(memory.used, memory.total)),
+            Variable::Swap => format_bytes(memory.used_swap),
+            Variable::SwapMax => format_bytes(memory.total_swap),
+            Variable::SwapFree => format_bytes(memory.total_swap.saturating_sub(memory.used_swap)),
+            Variable::SwapPerc => format!("{:.0}", percentage(memory.used_swap, memory.total_swap)),
+            Variable::Fs(value, mount) => snapshot
+                .disks
+                .iter()
+                .find(|disk| disk.mount_point == *mount)
+                .map(|disk| {
+                    let used = disk.total.saturating_sub(disk.available);
+                    match value {
+                        FsValue::Used => format_bytes(used),
+                        FsValue::Size => format_bytes(disk.total),
+                        FsValue::Free => format_bytes(disk.available),
+                        FsValue::UsedPerc => format!("{:.0}", percentage(used, disk.total)),
+                        FsValue::FreePerc => {
+                            format!("{:.0}", percentage(disk.available, disk.total))
+                        }
+                    }
+                })
+                .unwrap_or_else(missing),
+            Variable::Net(value, interface) => snapshot
+                .network
+                .iter()
+                .find(|network| network.interface == *interface)
+                .map(|network| match value {
+                    NetValue::DownSpeed => format_rate(network.received_per_sec),
+                    NetValue::UpSpeed => format_rate(network.transmitted_per_sec),
+                    NetValue::TotalDown => format_bytes(network.total_received),
+                    NetValue::TotalUp => format_bytes(network.total_transmitted),
+                })
+                .unwrap_or_else(missing),
+            Variable::LoadAvg(index) => {
+                let load = &snapshot.cpu.load_average;
+                match index {
+                    Some(1) => format!("{:.2}", load.one),
+                    Some(2) => format!("{:.2}", load.five),
+                    Some(_) => format!("{:.2}", load.fifteen),
+                    None => format!("{:.2} {:.2} {:.2}", load.one, load.five, load.fifteen),
+                }
+            }
+            Variable::Uptime => {
+                let uptime = snapshot.system.uptime;
+                format!(
+                    "{}d {}h {}m {}s",
+                    uptime / 86400,
+                    (uptime % 86400) / 3600,
+                    (uptime % 3600) / 60,
+                    uptime % 60
+                )
+            }
+            Variable::UptimeShort => {
+                let uptime = snapshot.system.uptime;
+                format!(
+                    "{}d {}h {}m",
+                    uptime / 86400,
+                    (uptime % 86400) / 3600,
+                    (uptime % 3600) / 60
+                )
+            }
+            Variable::Top(value, rank) => snapshot
+                .top_processes(*rank)
+                .get(rank - 1)
+                .map(|process| match value {
+                    TopValue::Name => process.name.clone(),
+                    TopValue::Pid => process.pid.to_string(),
+                    TopValue::Cpu => format!("{:.1}", process.cpu_usage),
+                    TopValue::Mem => format!("{:.1}", percentage(process.memory, memory.total)),
+                    TopValue::MemRes => format_bytes(process.memory),
+                    TopValue::User => process.user.clone(),
+                })
+                .unwrap_or_default(),
+            Variable::Processes => snapshot.processes.len().to_string(),
+            Variable::DiskIo(value) => {
+                let (read, write) = snapshot.disk_io_rates();
+                format_rate(match value {
+                    DiskIoValue::Total => read + write,
+                    DiskIoValue::Read => read,
+                    DiskIoValue::Write => write,
+                })
+            }
+            Variable::Time(format) => chrono::Local::now().format(format).to_string(),
+        }
+    }
+}
+
+/// Shown for a core, mount point or interface that doesn't exist
+fn missing() -> String {
+    "n/a".to_string()
+}
+
+/// Conky style sizes, e.g. `512MiB` or `1.23GiB`
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 || value >= 100.0 {
+        format!("{:.0}{}", value, UNITS[unit])
+    } else {
+        format!("{:.2}{}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fake;
+
+    fn render(text: &str) -> String {
+        Template::parse(text).unwrap().render(&fake::snapshot(0))
+    }
+
+    fn parse_error(text: &str) -> String {
+        match Template::parse(text) {
+            Ok(_) => panic!("`{}` parsed", text),
+            Err(error) => format!("{:#}", error),
+        }
+    }
+
+    #[test]
+    fn renders_variables() {
+        assert_eq!(render("CPU ${cpu}% core ${cpu 2}%"), "CPU 25% core 20%");
+        assert_eq!(
+            render("RAM $mem/$memmax $memperc%"),
+            "RAM 6.00GiB/16.00GiB 38%"
+        );
+        assert_eq!(render("${fs_used_perc /}% ${fs_free /nowhere}"), "60% n/a");
+        assert_eq!(render("${loadavg} ${loadavg 2}"), "1.25 0.75 0.50 0.75");
+        assert_eq!(
+            render("${top name 1} ${top pid 2} ${top name 9}"),
+            "cargo 1200 "
+        );
+        assert_eq!(render("${  cpu   3  }"), "30");
+    }
+
+    #[test]
+    fn per_core_variables() {
+        assert_eq!(
+            render("${cpu 1} ${cpu 4} ${freq 1} ${freq 4}"),
+            "10 40 2400 2700"
+        );
+        // Past the last core
+        assert_eq!(render("${cpu 5} ${freq 5}"), "n/a n/a");
+    }
+
+    #[test]
+    fn dollars() {
+        assert_eq!(render("$$5 and $$cpu"), "$5 and $cpu");
+        // A lone `$` that starts no variable stays as it is
+        assert_eq!(render("costs $ 5$"), "costs $ 5$");
+        assert_eq!(render("$cpu%"), "25%");
+    }
+
+    #[test]
+    fn time_keeps_its_format() {
+        let template = Template::parse("${time %H:%M   [%%] }").unwrap();
+        let Segment::Variable(Variable::Time(format)) = &template.segments[0] else {
+            panic!("not a time variable");
+        };
+        assert_eq!(format, "%H:%M   [%%] ");
+        assert_eq!(render("${time [%%]  x}"), "[%]  x");
+    }
+
+    #[test]
+    fn rejects_bad_templates() {
+        assert_eq!(parse_error("CPU ${cpu"), "Unclosed `${cpu` in template");
+        assert_eq!(
+            parse_error("${nope}"),
+            "Invalid template variable `${nope}`: unknown variable `nope`"
+        );
+        assert_eq!(
+            parse_error("$nope"),
+            "Invalid template variable `${nope}`: unknown variable `nope`"
+        );
+        assert_eq!(
+            parse_error("${ }"),
+            "Invalid template variable `${ }`: empty variable"
+        );
+        assert_eq!(
+            parse_error("${cpu two}"),
+            "Invalid template variable `${cpu two}`: `two` is not a number: invalid digit found in string"
+        );
+        assert_eq!(
+            parse_error("${fs_used}"),
+            "Invalid template variable `${fs_used}`: `fs_used` needs a mount point"
+        );
+        assert_eq!(
+            parse_error("${loadavg 4}"),
+            "Invalid template variable `${loadavg 4}`: `loadavg` takes 1, 2 or 3"
+        );
+        assert_eq!(
+            parse_error("${top name 0}"),
+            "Invalid template variable `${top name 0}`: `top` ranks start at 1"
+        );
+        assert!(parse_error("${top size 1}").contains("unknown `top` field `size`"));
+        assert!(parse_error("${time %Q}").contains("`%Q` is not a valid time format"));
+    }
+
+    #[test]
+    fn schedules_only_what_is_used() {
+        let scheduler = Template::parse("${mem} ${time}").unwrap().scheduler(3);
+        let every_3s = Some(std::time::Duration::from_secs(3));
+        assert_eq!(scheduler.interval(DataSource::Memory), every_3s);
+        assert_eq!(scheduler.interval(DataSource::Clock), every_3s);
+        assert_eq!(scheduler.interval(DataSource::Processes), None);
+    }
+}
@@ -10,380 +10,702 @@ use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Cell, Gauge, Paragraph, Row, Table},
+    widgets::{Block, Borders, Paragraph},
     Terminal,
 };
 use std::{io, time::Duration};
 
-use crate::data::SystemInfo;
+use crate::alerts::AlertMonitor;
+use crate::config::{Config, WidgetType};
+use crate::data::source::Playback;
+use crate::data::{History, MetricsSource, Snapshot};
+use crate::widgets::{ProcessView, SortColumn, TuiContext, WidgetGrid};
 
 /// Launch the TUI interface
-pub async fn launch_tui(update_interval: u64) -> Result<()> {
-    // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    stdout.execute(EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+pub async fn launch_tui(
+    config: &Config,
+    grid: WidgetGrid,
+    source: Box<dyn MetricsSource>,
+) -> Result<()> {
+    // Check the alert rules before taking over the terminal
+    let alerts = AlertMonitor::new(&config.alerts)?;
+
+    let mut terminal = setup_terminal()?;
 
     // Create app state
-    let mut app = TuiApp::new(update_interval);
+    let mut app = TuiApp::new(config, grid, alerts, source);
 
     // Main TUI loop
     let result = run_tui_loop(&mut terminal, &mut app).await;
 
-    // Restore terminal
+    restore_terminal(&mut terminal)?;
+    result?;
+    Ok(())
+}
+
+pub(super) type TuiTerminal = Terminal<CrosstermBackend<io::Stdout>>;
+
+pub(super) fn setup_terminal() -> Result<TuiTerminal> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    stdout.execute(EnterAlternateScreen)?;
+    Ok(Terminal::new(CrosstermBackend::new(stdout))?)
+}
+
+pub(super) fn restore_terminal(terminal: &mut TuiTerminal) -> Result<()> {
     disable_raw_mode()?;
     terminal.backend_mut().execute(LeaveAlternateScreen)?;
     terminal.show_cursor()?;
-
-    result?;
     Ok(())
 }
 
-async fn run_tui_loop(
-    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
-    app: &mut TuiApp,
-) -> Result<()> {
-    loop {
-        terminal.draw(|frame| app.draw(frame))?;
-
-        // Handle input
-        if event::poll(Duration::from_millis(100))? {
+/// Wait up to `timeout` for a key press; crossterm blocks, so on a blocking thread
+pub(super) async fn next_key(timeout: Duration) -> Result<Option<KeyCode>> {
+    tokio::task::spawn_blocking(move || {
+        if event::poll(timeout)? {
             if let Event::Key(key) = event::read()? {
                 if key.kind == KeyEventKind::Press {
-                    match key.code {
-                        KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => break,
-                        KeyCode::Char(' ') => app.toggle_pause(),
-                        KeyCode::Char('r') => app.force_refresh(),
-                        KeyCode::Tab => app.next_section(),
-                        KeyCode::Up => app.scroll_up(),
-                        KeyCode::Down => app.scroll_down(),
-                        _ => {}
-                    }
+                    return Ok(Some(key.code));
                 }
             }
         }
+        Ok(None)
+    })
+    .await?
+}
 
-        // Update data if not paused
-        if !app.paused {
-            app.update();
-            tokio::time::sleep(Duration::from_secs(app.update_interval)).await;
-        } else {
-            tokio::time::sleep(Duration::from_millis(100)).await;
+async fn run_tui_loop(terminal: &mut TuiTerminal, app: &mut TuiApp) -> Result<()> {
+    loop {
+        terminal.draw(|frame| app.draw(frame))?;
+
+        // Handle input, waking up in time for the next scheduled refresh
+        if let Some(code) = next_key(app.next_refresh_in()).await? {
+            if !app.handle_key(code) {
+                break;
+            }
         }
+
+        app.tick();
     }
 
     Ok(())
 }
 
+// Most recent alert log entries shown above the status bar
+const ALERT_LINES: usize = 3;
+
+// How far < and > jump in a replay
+const SEEK_SECONDS: i64 = 60;
+
+/// What keystrokes currently go to
+enum InputMode {
+    Normal,
+    /// Typing a process name filter after `/`
+    Filter,
+    /// Waiting for y/n before signalling a process
+    ConfirmKill {
+        pid: u32,
+        name: String,
+        force: bool,
+    },
+}
+
 /// TUI Application State
-struct TuiApp {
-    system_info: SystemInfo,
+pub(super) struct TuiApp {
+    source: Box<dyn MetricsSource>,
+    // Replays get their own key help in the status bar
+    replay: bool,
+    // What every widget draws from, taken after each refresh
+    snapshot: Snapshot,
+    history: History,
+    alerts: AlertMonitor,
+    grid: WidgetGrid,
     paused: bool,
     selected_section: usize,
-    update_interval: u64,
-    process_scroll: usize,
-    max_processes: usize,
+    process_view: ProcessView,
+    disk_offset: usize,
+    input_mode: InputMode,
+    // Outcome of the last action, shown in the status bar until the next key
+    message: Option<String>,
 }
 
 impl TuiApp {
-    fn new(update_interval: u64) -> Self {
+    pub(super) fn new(
+        config: &Config,
+        grid: WidgetGrid,
+        alerts: AlertMonitor,
+        mut source: Box<dyn MetricsSource>,
+    ) -> Self {
+        let snapshot = source.snapshot();
+        let replay = source.playback().is_some();
+        // The terminal is ours, so alerts only go to the alert log
+        let alerts = alerts.on_screen();
+        // Alerts in a replay are about the past, running their commands now would mislead
+        let alerts = if replay {
+            alerts.without_hooks()
+        } else {
+            alerts
+        };
         Self {
-            system_info: SystemInfo::new(),
+            replay,
+            source,
+            snapshot,
+            history: History::new(config.history_window()),
+            alerts,
+            grid,
             paused: false,
             selected_section: 0,
-            update_interval,
-            process_scroll: 0,
-            max_processes: 10,
+            process_view: ProcessView::default(),
+            disk_offset: 0,
+            input_mode: InputMode::Normal,
+            message: None,
+        }
+    }
+
+    /// Update data if not paused
+    pub(super) fn tick(&mut self) {
+        if !self.paused {
+            self.update();
         }
     }
 
     fn update(&mut self) {
-        self.system_info.refresh();
+        if self.source.update() {
+            self.take_snapshot();
+        }
     }
 
     fn force_refresh(&mut self) {
-        self.system_info.refresh();
+        self.source.refresh_all();
+        self.take_snapshot();
+    }
+
+    fn take_snapshot(&mut self) {
+        self.snapshot = self.source.snapshot();
+        let window = self.history.window();
+        match self.source.playback() {
+            // Replays skip and jump around, so rebuild the graphs from the recording
+            Some(playback) => {
+                self.history = History::new(window);
+                for sample in playback.recent(window) {
+                    self.history.record(sample);
+                }
+            }
+            None => self.history.record(&self.snapshot),
+        }
+        self.process_view.new_snapshot();
+        self.process_view.follow(&self.snapshot);
+        self.alerts.check(&self.snapshot);
+    }
+
+    /// Run a playback control and show where it ended up
+    fn playback(&mut self, action: impl FnOnce(&mut dyn Playback)) {
+        if let Some(playback) = self.source.playback() {
+            action(playback);
+            self.take_snapshot();
+        }
+    }
+
+    pub(super) fn next_refresh_in(&self) -> Duration {
+        if self.paused {
+            Duration::from_millis(100)
+        } else {
+            self.source.until_next()
+        }
     }
 
     fn toggle_pause(&mut self) {
         self.paused = !self.paused;
+        let paused = self.paused;
+        if let Some(playback) = self.source.playback() {
+            playback.set_paused(paused);
+        }
     }
 
     fn next_section(&mut self) {
-        self.selected_section = (self.selected_section + 1) % 6; // 6 sections
+        if !self.grid.is_empty() {
+            self.selected_section = (self.selected_section + 1) % self.grid.len();
+        }
     }
 
-    fn scroll_up(&mut self) {
-        if self.process_scroll > 0 {
-            self.process_scroll -= 1;
+    /// Returns false when the app should quit
+    pub(super) fn handle_key(&mut self, code: KeyCode) -> bool {
+        self.message = None;
+
+        match &self.input_mode {
+            InputMode::Filter => {
+                match code {
+                    KeyCode::Enter => self.input_mode = InputMode::Normal,
+                    KeyCode::Esc => {
+                        self.process_view.filter.clear();
+                        self.input_mode = InputMode::Normal;
+                    }
+                    KeyCode::Backspace => {
+                        self.process_view.filter.pop();
+                    }
+                    KeyCode::Char(c) => self.process_view.filter.push(c),
+                    _ => {}
+                }
+                self.process_view.cursor = 0;
+                self.process_view.selected_pid = None;
+            }
+            InputMode::ConfirmKill { pid, force, .. } => {
+                if matches!(code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                    let signal = if *force { "SIGKILL" } else { "SIGTERM" };
+                    self.message = Some(match self.source.signal_process(*pid, *force) {
+                        Ok(()) => format!("Sent {} to {}", signal, pid),
+                        Err(error) => error.to_string(),
+                    });
+                }
+                self.input_mode = InputMode::Normal;
+            }
+            InputMode::Normal => match code {
+                KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => return false,
+                KeyCode::Char(' ') => self.toggle_pause(),
+                KeyCode::Char('r') => self.force_refresh(),
+                KeyCode::Tab => self.next_section(),
+                KeyCode::Up => self.move_cursor(-1),
+                KeyCode::Down => self.move_cursor(1),
+                KeyCode::PageUp => self.move_cursor(-10),
+                KeyCode::PageDown => self.move_cursor(10),
+                KeyCode::Char('/') => self.input_mode = InputMode::Filter,
+                KeyCode::Char('c') => self.process_view.sort_by(SortColumn::Cpu),
+                KeyCode::Char('m') => self.process_view.sort_by(SortColumn::Memory),
+                KeyCode::Char('p') => self.process_view.sort_by(SortColumn::Pid),
+                KeyCode::Char('n') => self.process_view.sort_by(SortColumn::Name),
+                KeyCode::Char('u') => self.process_view.sort_by(SortColumn::User),
+                KeyCode::Char('t') => self.process_view.toggle_tree(),
+                KeyCode::Left => self.process_view.set_collapsed(&self.snapshot, true),
+                KeyCode::Right => self.process_view.set_collapsed(&self.snapshot, false),
+                KeyCode::Char('.') => self.playback(|playback| playback.step(1)),
+                KeyCode::Char(',') => self.playback(|playback| playback.step(-1)),
+                KeyCode::Char('>') => self.playback(|playback| playback.seek(SEEK_SECONDS)),
+                KeyCode::Char('<') => self.playback(|playback| playback.seek(-SEEK_SECONDS)),
+                KeyCode::Char('+') | KeyCode::Char('=') => {
+                    self.playback(|playback| playback.change_speed(true))
+                }
+                KeyCode::Char('-') => self.playback(|playback| playback.change_speed(false)),
+                KeyCode::Char('k') => self.confirm_kill(false),
+                KeyCode::Char('K') => self.confirm_kill(true),
+                _ => {}
+            },
         }
+
+        // Sorting or switching to the tree moves rows around
+        self.process_view.follow(&self.snapshot);
+        true
     }
 
-    fn scroll_down(&mut self) {
-        self.process_scroll += 1;
+    fn focused_widget(&self) -> Option<WidgetType> {
+        self.grid
+            .rows()
+            .iter()
+            .flatten()
+            .nth(self.selected_section)
+            .map(|cell| cell.widget_type)
     }
 
-    fn draw(&self, frame: &mut ratatui::Frame) {
-        // Main layout - matches your old screenshot
+    /// Scroll the disk list when it has focus, move the process selection otherwise
+    fn move_cursor(&mut self, delta: isize) {
+        if self.focused_widget() == Some(WidgetType::Disk) {
+            let last = self.snapshot.disks.len().saturating_sub(1);
+            self.disk_offset = self.disk_offset.saturating_add_signed(delta).min(last);
+            return;
+        }
+
+        self.process_view.move_cursor(&self.snapshot, delta);
+    }
+
+    fn confirm_kill(&mut self, force: bool) {
+        if let Some(row) = self.process_view.selected(&self.snapshot) {
+            self.input_mode = InputMode::ConfirmKill {
+                pid: row.process.pid,
+                name: row.process.name,
+                force,
+            };
+        }
+    }
+
+    pub(super) fn draw(&self, frame: &mut ratatui::Frame) {
+        // One layout row per grid row, the alert log once something fired, and the status bar
+        let mut constraints: Vec<Constraint> = self
+            .grid
+            .rows()
+            .iter()
+            .map(|row| WidgetGrid::row_constraint(row, &self.snapshot))
+            .collect();
+        let alert_lines = self.alerts.log().len().min(ALERT_LINES) as u16;
+        if alert_lines > 0 {
+            constraints.push(Constraint::Length(alert_lines + 2));
+        }
+        constraints.push(Constraint::Length(1));
+
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .margin(1)
-            .constraints([
-                Constraint::Length(3), // CPU
-                Constraint::Length(3), // RAM
-                Constraint::Length(3), // SWAP
-                Constraint::Length(6), // DISKS
-                Constraint::Length(4), // NETWORK
-                Constraint::Min(10),   // TOP PROCESSES
-                Constraint::Length(1), // Status bar
-            ])
+            .constraints(constraints)
             .split(frame.area());
 
-        self.draw_cpu(frame, chunks[0]);
-        self.draw_memory(frame, chunks[1], "RAM");
-        self.draw_memory(frame, chunks[2], "SWAP");
-        self.draw_disks(frame, chunks[3]);
-        self.draw_network(frame, chunks[4]);
-        self.draw_processes(frame, chunks[5]);
-        self.draw_status_bar(frame, chunks[6]);
-    }
-
-    fn draw_cpu(&self, frame: &mut ratatui::Frame, area: Rect) {
-        let cpu_usage = self.system_info.cpu_usage();
-        let load_avg = self.system_info.load_average();
-
-        let block = Block::default()
-            .title(" CPU ")
-            .borders(Borders::ALL)
-            .border_style(if self.selected_section == 0 {
-                Style::default().fg(Color::Yellow)
-            } else {
-                Style::default()
-            });
-
-        // Create gauge with custom bar
-        let gauge = Gauge::default()
-            .block(block)
-            .gauge_style(Style::default().fg(Color::Green))
-            .percent(cpu_usage as u16)
-            .label(format!(" {:.1}%", cpu_usage));
-
-        frame.render_widget(gauge, area);
-
-        // Add load average as text below the gauge
-        let load_text = format!(
-            "Cores: {} Load: {:.2} {:.2} {:.2}",
-            self.system_info.cpu_count(),
-            load_avg.0,
-            load_avg.1,
-            load_avg.2
-        );
+        let columns = self.grid.columns() as u32;
+        let mut index = 0;
+        for (row, row_area) in self.grid.rows().iter().zip(chunks.iter()) {
+            let cells = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints((0..columns).map(|_| Constraint::Ratio(1, columns)))
+                .split(*row_area);
+
+            for cell in row {
+                let ctx = TuiContext {
+                    focused: self.selected_section == index,
+                    alert: self.alerts.is_alerting(cell.widget_type),
+                    process_view: &self.process_view,
+                    disk_offset: self.disk_offset,
+                };
+                cell.widget.render_tui(
+                    frame,
+                    cells[cell.column as usize],
+                    &self.snapshot,
+                    &self.history,
+                    &ctx,
+                );
+                index += 1;
+            }
+        }
 
-        let load_rect = Rect {
-            x: area.x + 2,
-            y: area.y + 1,
-            width: area.width.saturating_sub(4),
-            height: 1,
-        };
+        if alert_lines > 0 {
+            self.draw_alert_log(frame, chunks[chunks.len() - 2]);
+        }
+        self.draw_status_bar(frame, chunks[chunks.len() - 1]);
+    }
 
-        let load_paragraph = Paragraph::new(load_text).style(Style::default().fg(Color::Gray));
+    fn draw_alert_log(&self, frame: &mut ratatui::Frame, area: Rect) {
+        let log = self.alerts.log();
+        let visible = area.height.saturating_sub(2) as usize;
+        let lines: Vec<Line> = log
+            .iter()
+            .skip(log.len().saturating_sub(visible))
+            .map(|event| {
+                let color = if event.fired {
+                    Color::Red
+                } else {
+                    Color::Green
+                };
+                Line::from(Span::styled(event.message(), Style::default().fg(color)))
+            })
+            .collect();
 
-        frame.render_widget(load_paragraph, load_rect);
+        let block = Block::default().title(" ALERTS ").borders(Borders::ALL);
+        frame.render_widget(Paragraph::new(lines).block(block), area);
     }
 
-    fn draw_memory(&self, frame: &mut ratatui::Frame, area: Rect, title: &str) {
-        let (used, total, percent) = if title == "RAM" {
-            let (used_mem, total_mem) = self.system_info.memory_usage();
-            let percent = if total_mem > 0 {
-                (used_mem as f64 / total_mem as f64 * 100.0) as f32
-            } else {
-                0.0
-            };
-            (used_mem, total_mem, percent)
-        } else {
-            let (used_swap, total_swap) = self.system_info.swap_usage();
-            let percent = if total_swap > 0 {
-                (used_swap as f64 / total_swap as f64 * 100.0) as f32
-            } else {
-                0.0
-            };
-            (used_swap, total_swap, percent)
+    fn draw_status_bar(&self, frame: &mut ratatui::Frame, area: Rect) {
+        let status = match &self.input_mode {
+            InputMode::Filter => format!("Filter: {}_ (ENTER:Apply | ESC:Clear)", self.process_view.filter),
+            InputMode::ConfirmKill { pid, name, force } => format!(
+                "Send {} to {} ({})? y/n",
+                if *force { "SIGKILL" } else { "SIGTERM" },
+                pid,
+                name
+            ),
+            InputMode::Normal => match (&self.message, self.source.status()) {
+                (Some(message), _) => message.clone(),
+                (None, Some(status)) if self.replay => format!(
+                    "{} | Q:Quit | SPACE:Play/Pause | ,.:Step | <>:Seek {}s | -+:Speed | TAB:Navigate | ↑↓:Select | /:Filter",
+                    status, SEEK_SECONDS
+                ),
+                // Nested in the dashboard, where Q goes back to the host list
+                (None, Some(status)) => format!(
+                    "{} | Q:Back | SPACE:Pause | TAB:Navigate | ↑↓:Select | /:Filter | C/M/P/N/U:Sort | T:Tree ←→:Fold",
+                    status
+                ),
+                (None, None) if self.paused => "PAUSED - Press SPACE to resume".to_string(),
+                (None, None) => "Q:Quit | SPACE:Pause | TAB:Navigate | R:Refresh | ↑↓:Select | /:Filter | C/M/P/N/U:Sort | T:Tree ←→:Fold | k:TERM K:KILL".to_string(),
+            },
         };
 
-        let section_index = if title == "RAM" { 1 } else { 2 };
-
-        let block = Block::default()
-            .title(format!(" {} ", title))
-            .borders(Borders::ALL)
-            .border_style(if self.selected_section == section_index {
-                Style::default().fg(Color::Yellow)
-            } else {
-                Style::default()
-            });
-
-        let used_gb = used as f64 / 1024.0 / 1024.0 / 1024.0;
-        let total_gb = total as f64 / 1024.0 / 1024.0 / 1024.0;
-
-        let gauge = Gauge::default()
-            .block(block)
-            .gauge_style(Style::default().fg(Color::Blue))
-            .percent(percent as u16)
-            .label(format!(" {:.1}%", percent));
-
-        frame.render_widget(gauge, area);
-
-        // Add memory usage text
-        let mem_text = format!("Used: {:.1}G / {:.1}G", used_gb, total_gb);
-        let text_rect = Rect {
-            x: area.x + 2,
-            y: area.y + 1,
-            width: area.width.saturating_sub(4),
-            height: 1,
-        };
+        let status_line = Line::from(vec![Span::styled(
+            status,
+            Style::default().fg(Color::White).bg(Color::DarkGray),
+        )]);
 
-        let mem_paragraph = Paragraph::new(mem_text).style(Style::default().fg(Color::Gray));
+        let paragraph = Paragraph::new(status_line).block(Block::default());
 
-        frame.render_widget(mem_paragraph, text_rect);
+        frame.render_widget(paragraph, area);
     }
+}
 
-    fn draw_disks(&self, frame: &mut ratatui::Frame, area: Rect) {
-        let block = Block::default()
-            .title(" DISKS ")
-            .borders(Borders::ALL)
-            .border_style(if self.selected_section == 3 {
-                Style::default().fg(Color::Yellow)
-            } else {
-                Style::default()
-            });
-
-        let disk_stats = self.system_info.disk_stats();
-        let mut lines = vec![];
-
-        for (_name, total, available, mount_point) in disk_stats.iter().take(2) {
-            // Show 2 disks max
-            let used = total.saturating_sub(*available);
-            let used_gb = used as f64 / 1024.0 / 1024.0 / 1024.0;
-            let _total_gb = *total as f64 / 1024.0 / 1024.0 / 1024.0;
-            let percent = if *total > 0 {
-                (used as f64 / *total as f64 * 100.0) as u16
-            } else {
-                0
-            };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fake::{self, ScriptedSource};
+    use crate::render::assert_snapshot;
+    use ratatui::backend::TestBackend;
+    use std::rc::Rc;
+
+    fn app(source: ScriptedSource) -> TuiApp {
+        TuiApp::new(
+            &Config::default(),
+            WidgetGrid::default(),
+            AlertMonitor::new(&[]).unwrap(),
+            Box::new(source),
+        )
+    }
 
-            let line = Line::from(vec![
-                Span::styled(
-                    format!("{} {:.1}%", mount_point, percent),
-                    Style::default().fg(Color::Cyan),
-                ),
-                Span::raw(" "),
-                Span::styled(format!("{:.1}G", used_gb), Style::default().fg(Color::Gray)),
-            ]);
-            lines.push(line);
+    fn screen(app: &TuiApp) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(100, 45)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                let line: String = (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect();
+                line.trim_end().to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn keys(app: &mut TuiApp, keys: &str) {
+        for key in keys.chars() {
+            let code = match key {
+                '\n' => KeyCode::Enter,
+                '\t' => KeyCode::Tab,
+                key => KeyCode::Char(key),
+            };
+            assert!(app.handle_key(code));
         }
+    }
 
-        let paragraph = Paragraph::new(lines).block(block).style(Style::default());
+    #[test]
+    fn default_layout() {
+        let mut app = app(ScriptedSource::new(vec![
+            fake::snapshot(0),
+            fake::snapshot(1),
+        ]));
+        assert!(app.source.update());
+        app.take_snapshot();
 
-        frame.render_widget(paragraph, area);
+        assert_snapshot("tui_default", &screen(&app));
     }
 
-    fn draw_network(&self, frame: &mut ratatui::Frame, area: Rect) {
-        let block = Block::default()
-            .title(" NETWORK ")
-            .borders(Borders::ALL)
-            .border_style(if self.selected_section == 4 {
-                Style::default().fg(Color::Yellow)
-            } else {
-                Style::default()
-            });
-
-        let network_stats = self.system_info.network_stats();
-        let mut lines = vec![];
-
-        for (interface, received, transmitted) in network_stats.iter().take(2) {
-            // Show 2 interfaces max
-            let received_mb = *received as f64 / 1024.0 / 1024.0;
-            let transmitted_mb = *transmitted as f64 / 1024.0 / 1024.0;
-
-            let line = Line::from(vec![
-                Span::styled(
-                    format!("{} ", interface),
-                    Style::default().fg(Color::Magenta),
-                ),
-                Span::styled("↓", Style::default().fg(Color::Blue)),
-                Span::raw(format!("{:.1}M ", received_mb)),
-                Span::styled("↑", Style::default().fg(Color::Green)),
-                Span::raw(format!("{:.1}M", transmitted_mb)),
-            ]);
-            lines.push(line);
-        }
+    #[test]
+    fn stacked_memory_bar() {
+        let app = app(ScriptedSource::new(vec![fake::snapshot(0)]));
+        let mut terminal = Terminal::new(TestBackend::new(100, 45)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+
+        let y = screen(&app)
+            .lines()
+            .position(|line| line.contains("Used: 6.0G / 16.0G"))
+            .unwrap() as u16;
+        let cells = |color: Color| {
+            (0..buffer.area.width)
+                .filter(|&x| buffer[(x, y)].bg == color)
+                .count()
+        };
+        // 6.5G programs, 1G shared, 512M buffers and 5G of other cache out of 16G, over 96 cells
+        assert_eq!(cells(Color::Blue), 39);
+        assert_eq!(cells(Color::Magenta), 6);
+        assert_eq!(cells(Color::Cyan), 3);
+        assert_eq!(cells(Color::Yellow), 30);
+    }
 
-        let paragraph = Paragraph::new(lines).block(block).style(Style::default());
+    #[test]
+    fn per_core_usage_and_frequency() {
+        let grid = WidgetGrid::from_config(&[crate::config::WidgetConfig {
+            widget_type: WidgetType::Cpu,
+            position: [0, 0],
+            update_interval: None,
+        }])
+        .unwrap();
+        let app = TuiApp::new(
+            &Config::default(),
+            grid,
+            AlertMonitor::new(&[]).unwrap(),
+            Box::new(ScriptedSource::new(vec![fake::snapshot(2)])),
+        );
 
-        frame.render_widget(paragraph, area);
+        let text = screen(&app);
+        let cores: Vec<String> = text
+            .lines()
+            .skip_while(|line| !line.contains("CPU - 4 cores, 2 physical, Fake CPU 3000"))
+            .skip(2)
+            .take(1)
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect();
+        // One gauge per core, filled to its usage and labelled with its frequency
+        assert_eq!(
+            cores,
+            ["│ ███cpu0 20% 2.4GHz ███cpu1 30% 2.5GHz ███cpu2 40% 2.6GHz ███cpu3 50% 2.7GHz │"]
+        );
     }
 
-    fn draw_processes(&self, frame: &mut ratatui::Frame, area: Rect) {
-        let block = Block::default()
-            .title(" TOP PROCESSES ")
-            .borders(Borders::ALL)
-            .border_style(if self.selected_section == 5 {
-                Style::default().fg(Color::Yellow)
-            } else {
-                Style::default()
-            });
-
-        let top_processes = self.system_info.top_processes(10);
-        let header = Row::new(vec![
-            Cell::from("PID"),
-            Cell::from("CPU%"),
-            Cell::from("MEM"),
-            Cell::from("NAME"),
+    #[test]
+    fn filter_and_kill() {
+        let source = ScriptedSource::new(vec![fake::snapshot(0)]);
+        let signals = Rc::clone(&source.signals);
+        let mut app = app(source);
+
+        keys(&mut app, "/postgres\n");
+        let screen = screen(&app);
+        assert!(
+            screen.contains("PROCESSES (2) filter: postgres"),
+            "{}",
+            screen
+        );
+        assert!(!screen.contains("cargo"));
+
+        // Busiest first, so the cursor starts on the parent
+        keys(&mut app, "Ky");
+        assert_eq!(*signals.borrow(), [(1200, true)]);
+        assert_eq!(app.message.as_deref(), Some("Sent SIGKILL to 1200"));
+    }
+
+    #[test]
+    fn cgroups_grouped_by_container() {
+        let grid = WidgetGrid::from_config(&[
+            crate::config::WidgetConfig {
+                widget_type: WidgetType::Cgroups,
+                position: [0, 0],
+                update_interval: None,
+            },
+            crate::config::WidgetConfig {
+                widget_type: WidgetType::Processes,
+                position: [1, 0],
+                update_interval: None,
+            },
         ])
-        .style(Style::default().fg(Color::Yellow));
+        .unwrap();
+        let mut app = TuiApp::new(
+            &Config::default(),
+            grid,
+            AlertMonitor::new(&[]).unwrap(),
+            Box::new(ScriptedSource::new(vec![fake::snapshot(0)])),
+        );
 
-        let rows: Vec<Row> = top_processes
-            .iter()
-            .skip(self.process_scroll)
-            .take(self.max_processes)
-            .map(|(name, pid, cpu, memory)| {
-                let memory_mb = *memory as f64 / 1024.0 / 1024.0;
-                Row::new(vec![
-                    Cell::from(pid.to_string()),
-                    Cell::from(format!("{:.1}%", cpu)),
-                    Cell::from(format!("{:.0}M", memory_mb)),
-                    Cell::from(name.clone()),
-                ])
-            })
+        let rows: Vec<String> = screen(&app)
+            .lines()
+            .skip_while(|line| !line.contains("CGROUPS (3)"))
+            .skip(2)
+            .take(3)
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
             .collect();
+        assert_eq!(
+            rows,
+            [
+                "│4f6e5d0c3b2a 1 45.0% 1200M / 2048M 0.0 B/s 0.0 B/s │",
+                "│postgresql.service 2 15.5% 1050M 64.0 KB/s 256.0 KB/s │",
+                "│session-2.scope 1 0.5% 8M 0.0 B/s 0.0 B/s │",
+            ]
+        );
 
-        let widths = [
-            Constraint::Length(8),
-            Constraint::Length(8),
-            Constraint::Length(8),
-            Constraint::Min(20),
-        ];
+        // The process filter also takes a container ID
+        keys(&mut app, "/4f6e\n");
+        let screen = screen(&app);
+        assert!(screen.contains("PROCESSES (1) filter: 4f6e"), "{}", screen);
+        assert!(screen.contains("cargo"));
+    }
 
-        let table = Table::new(rows, widths).header(header).block(block);
+    #[test]
+    fn failed_units_stand_out() {
+        let grid = WidgetGrid::from_config(&[crate::config::WidgetConfig {
+            widget_type: WidgetType::Systemd,
+            position: [0, 0],
+            update_interval: None,
+        }])
+        .unwrap();
+        let app = TuiApp::new(
+            &Config::default(),
+            grid,
+            AlertMonitor::new(&[]).unwrap(),
+            Box::new(ScriptedSource::new(vec![fake::snapshot(0)])),
+        );
 
-        frame.render_widget(table, area);
-    }
+        let text = screen(&app);
+        let rows: Vec<String> = text
+            .lines()
+            .skip_while(|line| !line.contains("SYSTEMD (1 failed)"))
+            .skip(2)
+            .take(3)
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                "│postgresql.service active (running) 2h 00m 1200 12.5% 900M │",
+                "│backup.service failed 12m 05s │",
+                "│cups.socket inactive (dead) │",
+            ]
+        );
 
-    fn draw_status_bar(&self, frame: &mut ratatui::Frame, area: Rect) {
-        let status = if self.paused {
-            "PAUSED - Press SPACE to resume"
-        } else {
-            "Q:Quit | SPACE:Pause | TAB:Navigate | R:Refresh | ↑↓:Scroll"
-        };
+        let mut terminal = Terminal::new(TestBackend::new(100, 45)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        // The whole failed row is red, not just its state
+        let (y, line) = text
+            .lines()
+            .enumerate()
+            .find(|(_, line)| line.contains("backup.service"))
+            .unwrap();
+        let x = line.chars().position(|c| c == 'b').unwrap();
+        assert_eq!(buffer[(x as u16, y as u16)].fg, Color::Red);
+    }
 
-        let status_line = Line::from(vec![Span::styled(
-            status,
-            Style::default().fg(Color::White).bg(Color::DarkGray),
-        )]);
+    #[test]
+    fn pause_stops_updates() {
+        let mut app = app(ScriptedSource::new(vec![
+            fake::snapshot(0),
+            fake::snapshot(1),
+        ]));
+        keys(&mut app, " ");
+        assert!(app.paused);
+        assert!(screen(&app).contains("PAUSED - Press SPACE to resume"));
+
+        keys(&mut app, " r");
+        assert_eq!(app.snapshot.timestamp, fake::snapshot(0).timestamp);
+        app.update();
+        assert_eq!(app.snapshot.timestamp, fake::snapshot(1).timestamp);
+    }
 
-        let paragraph = Paragraph::new(status_line).block(Block::default());
+    #[test]
+    fn selection_follows_the_process() {
+        let mut busier = fake::snapshot(1);
+        busier
+            .processes
+            .iter_mut()
+            .find(|process| process.pid == 1210)
+            .unwrap()
+            .cpu_usage = 90.0;
+        let mut exited = busier.clone();
+        exited.processes.retain(|process| process.pid != 1200);
+        let mut app = app(ScriptedSource::new(vec![fake::snapshot(0), busier, exited]));
+        let selected = |app: &TuiApp| {
+            let row = app.process_view.selected(&app.snapshot).unwrap();
+            (app.process_view.cursor, row.process.pid)
+        };
 
-        frame.render_widget(paragraph, area);
+        // cargo, then the postgres parent
+        app.handle_key(KeyCode::Down);
+        assert_eq!(selected(&app), (1, 1200));
+
+        // The worker jumps to the top, the cursor stays on the parent
+        assert!(app.source.update());
+        app.take_snapshot();
+        assert_eq!(selected(&app), (2, 1200));
+
+        // So does sorting by user
+        keys(&mut app, "u");
+        assert_eq!(selected(&app), (1, 1200));
+        keys(&mut app, "c");
+
+        // Gone: same row, nothing followed until the cursor moves
+        assert!(app.source.update());
+        app.take_snapshot();
+        assert_eq!(app.process_view.cursor, 2);
+        assert_eq!(app.process_view.selected_pid, None);
+        app.handle_key(KeyCode::Up);
+        assert_eq!(app.process_view.selected_pid, Some(2000));
     }
 }
@@ -0,0 +1,139 @@
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Style},
+    widgets::{Block, Borders, Cell, Row, Table},
+    Frame,
+};
+
+use super::{format_rate, to_mb, BarItem, TuiContext, Widget};
+use crate::data::snapshot::CgroupGroup;
+use crate::data::{DataSource, History, Snapshot};
+
+/// Groups listed before the rest are cut off
+const MAX_GROUPS: usize = 10;
+
+/// `120M`, or `120M / 512M` under a limit
+fn memory_text(group: &CgroupGroup) -> String {
+    match group.memory_limit {
+        Some(limit) => format!("{:.0}M / {:.0}M", to_mb(group.memory), to_mb(limit)),
+        None => format!("{:.0}M", to_mb(group.memory)),
+    }
+}
+
+impl Widget for super::CgroupsWidget {
+    fn sources(&self) -> &'static [DataSource] {
+        &[DataSource::Processes]
+    }
+
+    fn render_text(&self, snapshot: &Snapshot, _history: &History) -> Vec<String> {
+        let groups = snapshot.cgroup_groups();
+        if groups.is_empty() {
+            return vec![];
+        }
+
+        let mut lines = vec!["Containers and services (by CPU):".to_string()];
+        for group in groups.iter().take(MAX_GROUPS) {
+            lines.push(format!(
+                "  {} {:.1}% {} ({} processes)",
+                group.name,
+                group.cpu_usage,
+                memory_text(group),
+                group.processes
+            ));
+        }
+        lines
+    }
+
+    fn tui_constraint(&self, snapshot: &Snapshot) -> Constraint {
+        let groups = snapshot.cgroup_groups().len().clamp(1, MAX_GROUPS);
+        Constraint::Length(groups as u16 + 3)
+    }
+
+    fn render_tui(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        snapshot: &Snapshot,
+        _history: &History,
+        ctx: &TuiContext,
+    ) {
+        let groups = snapshot.cgroup_groups();
+        let block = Block::default()
+            .title(format!(" CGROUPS ({}) ", groups.len()))
+            .borders(Borders::ALL)
+            .border_style(ctx.border_style());
+
+        let header = Row::new(["NAME", "PROCS", "CPU%", "MEM", "READ", "WRITE"])
+            .style(Style::default().fg(Color::Yellow));
+        let rows: Vec<Row> = groups
+            .iter()
+            .take(MAX_GROUPS)
+            .map(|group| {
+                // Containers stand out from plain services
+                let name = if group.container {
+                    Cell::from(group.name.as_str()).style(Style::default().fg(Color::Cyan))
+                } else {
+                    Cell::from(group.name.as_str())
+                };
+                Row::new(vec![
+                    name,
+                    Cell::from(group.processes.to_string()),
+                    Cell::from(format!("{:.1}%", group.cpu_usage)),
+                    Cell::from(memory_text(group)),
+                    Cell::from(format_rate(group.read_per_sec)),
+                    Cell::from(format_rate(group.written_per_sec)),
+                ])
+            })
+            .collect();
+
+        let widths = [
+            Constraint::Min(20),
+            Constraint::Length(6),
+            Constraint::Length(7),
+            Constraint::Length(14),
+            Constraint::Length(11),
+            Constraint::Length(11),
+        ];
+        let table = Table::new(rows, widths).header(header).block(block);
+
+        frame.render_widget(table, area);
+    }
+
+    fn bar_item(&self, snapshot: &Snapshot) -> Option<BarItem> {
+        let top = snapshot.cgroup_groups().into_iter().next()?;
+        Some(BarItem {
+            text: format!("{} {:.0}%", top.name, top.cpu_usage),
+            percentage: Some(top.cpu_usage),
+        })
+    }
+
+    fn collect(&self, snapshot: &Snapshot, data: &mut Snapshot) {
+        data.cgroups = snapshot.cgroups.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fake;
+    use crate::data::snapshot::CgroupData;
+
+    #[test]
+    fn height_follows_groups() {
+        let mut snapshot = fake::snapshot(0);
+        let groups = snapshot.cgroup_groups().len();
+        // More cgroups of a container already listed add no rows
+        let container = snapshot.cgroups[1].container.clone();
+        snapshot.cgroups.extend((0..20).map(|index| CgroupData {
+            path: format!("/system.slice/docker-extra-{}.scope", index),
+            container: container.clone(),
+            ..CgroupData::default()
+        }));
+
+        assert_eq!(snapshot.cgroup_groups().len(), groups);
+        assert_eq!(
+            crate::widgets::CgroupsWidget.tui_constraint(&snapshot),
+            Constraint::Length(groups as u16 + 3)
+        );
+    }
+}
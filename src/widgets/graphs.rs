@@ -0,0 +1,324 @@
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Style},
+    symbols,
+    text::Span,
+    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Sparkline},
+    Frame,
+};
+
+use super::{format_rate, TuiContext, Widget};
+use crate::data::history::Series;
+use crate::data::{DataSource, History, Snapshot};
+
+const TEXT_WIDTH: usize = 40;
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const PALETTE: [Color; 6] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Red,
+];
+
+/// Last `width` samples as a line of block characters
+fn text_sparkline(series: &Series, max: f64, width: usize) -> String {
+    let values: Vec<f64> = series.values().collect();
+    let start = values.len().saturating_sub(width);
+    values[start..]
+        .iter()
+        .map(|value| {
+            let level = if max > 0.0 { value / max } else { 0.0 };
+            BARS[((level * (BARS.len() - 1) as f64).round() as usize).min(BARS.len() - 1)]
+        })
+        .collect()
+}
+
+/// Line chart over the history window, x is seconds ago
+fn draw_chart(
+    frame: &mut Frame,
+    area: Rect,
+    title: &str,
+    datasets: Vec<Dataset>,
+    window_secs: f64,
+    (y_max, y_labels): (f64, [String; 2]),
+    ctx: &TuiContext,
+) {
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(ctx.border_style());
+
+    let chart = Chart::new(datasets)
+        .block(block)
+        .x_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::Gray))
+                .bounds([-window_secs, 0.0])
+                .labels([
+                    Span::raw(format!("-{}m", (window_secs / 60.0).round())),
+                    Span::raw("now"),
+                ]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::Gray))
+                .bounds([0.0, y_max])
+                .labels(y_labels.map(Span::raw)),
+        );
+
+    frame.render_widget(chart, area);
+}
+
+fn line_dataset<'a>(name: String, points: &'a [(f64, f64)], color: Color) -> Dataset<'a> {
+    Dataset::default()
+        .name(name)
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(color))
+        .data(points)
+}
+
+impl Widget for super::CpuGraphWidget {
+    fn sources(&self) -> &'static [DataSource] {
+        &[DataSource::Cpu]
+    }
+
+    fn render_text(&self, _snapshot: &Snapshot, history: &History) -> Vec<String> {
+        vec![format!(
+            "CPU history: {}",
+            text_sparkline(&history.cpu, 100.0, TEXT_WIDTH)
+        )]
+    }
+
+    fn tui_constraint(&self, _snapshot: &Snapshot) -> Constraint {
+        Constraint::Length(6)
+    }
+
+    fn render_tui(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        _snapshot: &Snapshot,
+        history: &History,
+        ctx: &TuiContext,
+    ) {
+        let block = Block::default()
+            .title(" CPU HISTORY ")
+            .borders(Borders::ALL)
+            .border_style(ctx.border_style());
+
+        // Keep only what fits, newest on the right
+        let values: Vec<u64> = history
+            .cpu
+            .values()
+            .map(|value| value.round() as u64)
+            .collect();
+        let start = values
+            .len()
+            .saturating_sub(area.width.saturating_sub(2) as usize);
+
+        let sparkline = Sparkline::default()
+            .block(block)
+            .style(Style::default().fg(ctx.color(Color::Green)))
+            .max(100)
+            .data(&values[start..]);
+
+        frame.render_widget(sparkline, area);
+    }
+
+    fn collect(&self, _snapshot: &Snapshot, _data: &mut Snapshot) {}
+}
+
+impl Widget for super::MemoryGraphWidget {
+    fn sources(&self) -> &'static [DataSource] {
+        &[DataSource::Memory]
+    }
+
+    fn render_text(&self, _snapshot: &Snapshot, history: &History) -> Vec<String> {
+        vec![
+            format!(
+                "RAM history:  {}",
+                text_sparkline(&history.memory, 100.0, TEXT_WIDTH)
+            ),
+            format!(
+                "Swap history: {}",
+                text_sparkline(&history.swap, 100.0, TEXT_WIDTH)
+            ),
+        ]
+    }
+
+    fn tui_constraint(&self, _snapshot: &Snapshot) -> Constraint {
+        Constraint::Length(10)
+    }
+
+    fn render_tui(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        _snapshot: &Snapshot,
+        history: &History,
+        ctx: &TuiContext,
+    ) {
+        let memory = history.memory.points();
+        let swap = history.swap.points();
+
+        draw_chart(
+            frame,
+            area,
+            "MEMORY HISTORY",
+            vec![
+                line_dataset("RAM".to_string(), &memory, Color::Blue),
+                line_dataset("Swap".to_string(), &swap, Color::Magenta),
+            ],
+            history.window().as_secs_f64(),
+            (100.0, ["0%".to_string(), "100%".to_string()]),
+            ctx,
+        );
+    }
+
+    fn collect(&self, _snapshot: &Snapshot, _data: &mut Snapshot) {}
+}
+
+impl Widget for super::NetworkGraphWidget {
+    fn sources(&self) -> &'static [DataSource] {
+        &[DataSource::Networks]
+    }
+
+    fn render_text(&self, _snapshot: &Snapshot, history: &History) -> Vec<String> {
+        history
+            .network
+            .iter()
+            .filter(|(_, series)| series.received.max() + series.transmitted.max() > 0.0)
+            .flat_map(|(interface, series)| {
+                let max = series.received.max().max(series.transmitted.max());
+                [
+                    format!(
+                        "{} ↓ {}",
+                        interface,
+                        text_sparkline(&series.received, max, TEXT_WIDTH)
+                    ),
+                    format!(
+                        "{} ↑ {}",
+                        interface,
+                        text_sparkline(&series.transmitted, max, TEXT_WIDTH)
+                    ),
+                ]
+            })
+            .collect()
+    }
+
+    fn tui_constraint(&self, _snapshot: &Snapshot) -> Constraint {
+        Constraint::Length(10)
+    }
+
+    fn render_tui(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        _snapshot: &Snapshot,
+        history: &History,
+        ctx: &TuiContext,
+    ) {
+        // Idle interfaces would only add flat lines to the legend
+        let active: Vec<_> = history
+            .network
+            .iter()
+            .filter(|(_, series)| series.received.max() + series.transmitted.max() > 0.0)
+            .map(|(interface, series)| {
+                (
+                    interface,
+                    series.received.points(),
+                    series.transmitted.points(),
+                )
+            })
+            .collect();
+
+        let y_max = active
+            .iter()
+            .flat_map(|(_, received, transmitted)| received.iter().chain(transmitted))
+            .map(|(_, value)| *value)
+            .fold(1.0, f64::max);
+
+        let datasets = active
+            .iter()
+            .enumerate()
+            .flat_map(|(index, (interface, received, transmitted))| {
+                let color = PALETTE[index % PALETTE.len()];
+                [
+                    line_dataset(format!("{} ↓", interface), received, color),
+                    line_dataset(format!("{} ↑", interface), transmitted, color),
+                ]
+            })
+            .collect();
+
+        draw_chart(
+            frame,
+            area,
+            "NETWORK HISTORY",
+            datasets,
+            history.window().as_secs_f64(),
+            (y_max, ["0".to_string(), format_rate(y_max)]),
+            ctx,
+        );
+    }
+
+    fn collect(&self, _snapshot: &Snapshot, _data: &mut Snapshot) {}
+}
+
+impl Widget for super::DiskIoGraphWidget {
+    fn sources(&self) -> &'static [DataSource] {
+        &[DataSource::Disks]
+    }
+
+    fn render_text(&self, _snapshot: &Snapshot, history: &History) -> Vec<String> {
+        let max = history.disk_read.max().max(history.disk_write.max());
+        vec![
+            format!(
+                "Disk read:  {}",
+                text_sparkline(&history.disk_read, max, TEXT_WIDTH)
+            ),
+            format!(
+                "Disk write: {}",
+                text_sparkline(&history.disk_write, max, TEXT_WIDTH)
+            ),
+        ]
+    }
+
+    fn tui_constraint(&self, _snapshot: &Snapshot) -> Constraint {
+        Constraint::Length(8)
+    }
+
+    fn render_tui(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        _snapshot: &Snapshot,
+        history: &History,
+        ctx: &TuiContext,
+    ) {
+        let read = history.disk_read.points();
+        let write = history.disk_write.points();
+        let y_max = history
+            .disk_read
+            .max()
+            .max(history.disk_write.max())
+            .max(1.0);
+
+        draw_chart(
+            frame,
+            area,
+            "DISK I/O HISTORY",
+            vec![
+                line_dataset("read".to_string(), &read, Color::Cyan),
+                line_dataset("write".to_string(), &write, Color::Yellow),
+            ],
+            history.window().as_secs_f64(),
+            (y_max, ["0".to_string(), format_rate(y_max)]),
+            ctx,
+        );
+    }
+
+    fn collect(&self, _snapshot: &Snapshot, _data: &mut Snapshot) {}
+}
@@ -0,0 +1,292 @@
+use anyhow::{bail, Result};
+use ratatui::layout::Constraint;
+use std::time::Duration;
+
+use super::{build, Widget, DEFAULT_WIDGETS};
+use crate::config::{WidgetConfig, WidgetType};
+use crate::data::{RefreshScheduler, Snapshot};
+
+pub struct GridCell {
+    pub column: u16,
+    pub widget_type: WidgetType,
+    pub widget: Box<dyn Widget>,
+    pub update_interval: Option<u64>,
+}
+
+/// Widgets placed on a `[row, column]` grid, as listed under `[[widgets]]`
+pub struct WidgetGrid {
+    /// Non-empty rows in row order, each sorted by column
+    rows: Vec<Vec<GridCell>>,
+    columns: u16,
+}
+
+impl WidgetGrid {
+    pub fn from_config(configs: &[WidgetConfig]) -> Result<Self> {
+        if configs.is_empty() {
+            return Ok(Self::default());
+        }
+
+        for (index, config) in configs.iter().enumerate() {
+            if config.update_interval == Some(0) {
+                bail!(
+                    "Widget `{}` at [{}, {}] has update_interval = 0, it must be at least 1",
+                    config.widget_type.as_str(),
+                    config.position[0],
+                    config.position[1]
+                );
+            }
+
+            if let Some(other) = configs[..index]
+                .iter()
+                .find(|other| other.position == config.position)
+            {
+                bail!(
+                    "Widgets `{}` and `{}` overlap at position [{}, {}]",
+                    other.widget_type.as_str(),
+                    config.widget_type.as_str(),
+                    config.position[0],
+                    config.position[1]
+                );
+            }
+        }
+
+        let mut placed: Vec<&WidgetConfig> = configs.iter().collect();
+        placed.sort_by_key(|config| config.position);
+
+        let mut rows: Vec<Vec<GridCell>> = Vec::new();
+        let mut current_row = None;
+        for config in placed {
+            let [row, column] = config.position;
+            if current_row != Some(row) {
+                rows.push(Vec::new());
+                current_row = Some(row);
+            }
+            if let Some(cells) = rows.last_mut() {
+                cells.push(GridCell {
+                    column,
+                    widget_type: config.widget_type,
+                    widget: build(config.widget_type),
+                    update_interval: config.update_interval,
+                });
+            }
+        }
+
+        let columns = configs
+            .iter()
+            .map(|config| config.position[1] + 1)
+            .max()
+            .unwrap_or(1);
+
+        Ok(Self { rows, columns })
+    }
+
+    pub fn rows(&self) -> &[Vec<GridCell>] {
+        &self.rows
+    }
+
+    pub fn columns(&self) -> u16 {
+        self.columns
+    }
+
+    /// All widgets in row-major order
+    pub fn widgets(&self) -> impl Iterator<Item = &dyn Widget> {
+        self.rows.iter().flatten().map(|cell| cell.widget.as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.iter().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Schedule each data source at the shortest interval of the widgets using it
+    pub fn scheduler(&self, default_interval: u64) -> RefreshScheduler {
+        let mut scheduler = RefreshScheduler::new();
+        for cell in self.rows.iter().flatten() {
+            let interval =
+                Duration::from_secs(cell.update_interval.unwrap_or(default_interval).max(1));
+            for source in cell.widget.sources() {
+                scheduler.every(*source, interval);
+            }
+        }
+        scheduler
+    }
+
+    /// One widget per row, in the order given
+    pub fn stacked(widgets: impl IntoIterator<Item = (WidgetType, Box<dyn Widget>)>) -> Self {
+        Self {
+            rows: widgets
+                .into_iter()
+                .map(|(widget_type, widget)| {
+                    vec![GridCell {
+                        column: 0,
+                        widget_type,
+                        widget,
+                        update_interval: None,
+                    }]
+                })
+                .collect(),
+            columns: 1,
+        }
+    }
+
+    /// Height of a TUI row: the tallest widget, growing if any widget grows
+    pub fn row_constraint(cells: &[GridCell], snapshot: &Snapshot) -> Constraint {
+        let mut height = 0;
+        let mut grow = false;
+        for cell in cells {
+            match cell.widget.tui_constraint(snapshot) {
+                Constraint::Length(n) => height = height.max(n),
+                Constraint::Min(n) => {
+                    height = height.max(n);
+                    grow = true;
+                }
+                _ => grow = true,
+            }
+        }
+
+        if grow {
+            Constraint::Min(height)
+        } else {
+            Constraint::Length(height)
+        }
+    }
+}
+
+impl Default for WidgetGrid {
+    /// One widget per row, in the default widget order
+    fn default() -> Self {
+        Self::stacked(
+            DEFAULT_WIDGETS
+                .into_iter()
+                .map(|widget_type| (widget_type, build(widget_type))),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, WidgetType};
+    use crate::data::DataSource;
+
+    fn widget(widget_type: WidgetType, position: [u16; 2]) -> WidgetConfig {
+        WidgetConfig {
+            widget_type,
+            position,
+            update_interval: None,
+        }
+    }
+
+    fn error(configs: &[WidgetConfig]) -> String {
+        match WidgetGrid::from_config(configs) {
+            Ok(_) => panic!("grid accepted"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn places_widgets_by_row_and_column() {
+        let grid = WidgetGrid::from_config(&[
+            widget(WidgetType::Disk, [3, 0]),
+            widget(WidgetType::Memory, [0, 1]),
+            widget(WidgetType::Cpu, [0, 0]),
+        ])
+        .unwrap();
+
+        // Empty rows collapse, cells within a row are sorted by column
+        let layout: Vec<Vec<(u16, WidgetType)>> = grid
+            .rows()
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| (cell.column, cell.widget_type))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            layout,
+            [
+                vec![(0, WidgetType::Cpu), (1, WidgetType::Memory)],
+                vec![(0, WidgetType::Disk)],
+            ]
+        );
+        assert_eq!(grid.columns(), 2);
+        assert_eq!(grid.len(), 3);
+    }
+
+    #[test]
+    fn keeps_gaps_between_columns() {
+        // The example leaves column 1 empty on purpose
+        let config: Config =
+            toml::from_str(include_str!("../../examples/basic_config.toml")).unwrap();
+        let grid = WidgetGrid::from_config(&config.widgets).unwrap();
+        assert_eq!(grid.columns(), 3);
+        let columns: Vec<u16> = grid.rows()[0].iter().map(|cell| cell.column).collect();
+        assert_eq!(columns, [0, 2]);
+    }
+
+    #[test]
+    fn rejects_overlaps_and_zero_intervals() {
+        assert_eq!(
+            error(&[
+                widget(WidgetType::Cpu, [0, 0]),
+                widget(WidgetType::Memory, [1, 0]),
+                widget(WidgetType::Disk, [0, 0]),
+            ]),
+            "Widgets `cpu` and `disk` overlap at position [0, 0]"
+        );
+        assert_eq!(
+            error(&[WidgetConfig {
+                update_interval: Some(0),
+                ..widget(WidgetType::Network, [2, 1])
+            }]),
+            "Widget `network` at [2, 1] has update_interval = 0, it must be at least 1"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_widget_types() {
+        let error = toml::from_str::<Config>(
+            "update_interval = 1\n\n[[widgets]]\nwidget_type = \"clock\"\nposition = [0, 0]\n",
+        )
+        .unwrap_err();
+        assert!(
+            error.to_string().contains("unknown variant `clock`"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn schedules_the_shortest_interval() {
+        let grid = WidgetGrid::from_config(&[
+            WidgetConfig {
+                update_interval: Some(5),
+                ..widget(WidgetType::Memory, [0, 0])
+            },
+            widget(WidgetType::Swap, [0, 1]),
+            widget(WidgetType::Cpu, [1, 0]),
+        ])
+        .unwrap();
+        let scheduler = grid.scheduler(2);
+        assert_eq!(
+            scheduler.interval(DataSource::Memory),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            scheduler.interval(DataSource::Cpu),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(scheduler.interval(DataSource::Disks), None);
+    }
+
+    #[test]
+    fn empty_config_gets_the_default_layout() {
+        let grid = WidgetGrid::from_config(&[]).unwrap();
+        assert_eq!(grid.len(), DEFAULT_WIDGETS.len());
+        assert_eq!(grid.columns(), 1);
+    }
+}
@@ -1,12 +1,191 @@
-// Widget system - placeholder for future use
-// pub mod system;
+// Widget system - every output mode (simple, TUI, JSON) draws from the same widget set
+pub mod cgroups;
+pub mod graphs;
+pub mod grid;
+pub mod processes;
+pub mod system;
+pub mod systemd;
 
-// Uncomment when implementing widgets
-// use crate::data::SystemInfo;
+pub use grid::WidgetGrid;
+pub use processes::{ProcessView, SortColumn};
 
-// pub trait Widget {
-//     fn render(&self, system_info: &SystemInfo) -> String;
-// }
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    Frame,
+};
 
-// pub struct CpuWidget;
-// pub struct MemoryWidget;
+use crate::config::WidgetType;
+use crate::data::{DataSource, History, Snapshot};
+use std::time::Duration;
+
+/// Per-widget state the TUI hands over when drawing
+pub struct TuiContext<'a> {
+    pub focused: bool,
+    /// A firing alert concerns this widget
+    pub alert: bool,
+    pub process_view: &'a ProcessView,
+    /// First disk shown in the scrollable disk list
+    pub disk_offset: usize,
+}
+
+impl TuiContext<'_> {
+    pub fn border_style(&self) -> Style {
+        if self.alert {
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        } else if self.focused {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        }
+    }
+
+    /// `color`, or red while alerting
+    pub fn color(&self, color: Color) -> Color {
+        if self.alert {
+            Color::Red
+        } else {
+            color
+        }
+    }
+}
+
+pub trait Widget {
+    /// Data sources that must be refreshed for this widget to be current
+    fn sources(&self) -> &'static [DataSource];
+
+    /// Plain text lines for the simple renderer (empty means nothing to show)
+    fn render_text(&self, snapshot: &Snapshot, history: &History) -> Vec<String>;
+
+    /// Vertical space this widget wants in the TUI layout
+    fn tui_constraint(&self, snapshot: &Snapshot) -> Constraint;
+
+    fn render_tui(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        snapshot: &Snapshot,
+        history: &History,
+        ctx: &TuiContext,
+    );
+
+    /// Copy this widget's part of the snapshot into the structured (JSON) output
+    fn collect(&self, snapshot: &Snapshot, data: &mut Snapshot);
+
+    /// One-line summary for status bars; widgets without one (graphs) are left out
+    fn bar_item(&self, _snapshot: &Snapshot) -> Option<BarItem> {
+        None
+    }
+}
+
+/// A widget squeezed into a status bar block
+pub struct BarItem {
+    pub text: String,
+    /// Fill level for bars that draw icons or gauges from it
+    pub percentage: Option<f64>,
+}
+
+pub struct CpuWidget;
+pub struct MemoryWidget;
+pub struct SwapWidget;
+pub struct DiskWidget;
+pub struct NetworkWidget;
+pub struct ProcessesWidget {
+    pub count: usize,
+}
+pub struct UptimeWidget;
+pub struct LoadWidget;
+pub struct CpuGraphWidget;
+pub struct MemoryGraphWidget;
+pub struct NetworkGraphWidget;
+pub struct DiskIoGraphWidget;
+pub struct BatteryWidget;
+pub struct SensorsWidget;
+pub struct CgroupsWidget;
+pub struct SystemdWidget;
+
+pub fn build(widget_type: WidgetType) -> Box<dyn Widget> {
+    match widget_type {
+        WidgetType::Cpu => Box::new(CpuWidget),
+        WidgetType::Memory => Box::new(MemoryWidget),
+        WidgetType::Swap => Box::new(SwapWidget),
+        WidgetType::Disk => Box::new(DiskWidget),
+        WidgetType::Network => Box::new(NetworkWidget),
+        WidgetType::Processes => Box::new(ProcessesWidget { count: 5 }),
+        WidgetType::Uptime => Box::new(UptimeWidget),
+        WidgetType::Load => Box::new(LoadWidget),
+        WidgetType::CpuGraph => Box::new(CpuGraphWidget),
+        WidgetType::MemoryGraph => Box::new(MemoryGraphWidget),
+        WidgetType::NetworkGraph => Box::new(NetworkGraphWidget),
+        WidgetType::DiskIoGraph => Box::new(DiskIoGraphWidget),
+        WidgetType::Battery => Box::new(BatteryWidget),
+        WidgetType::Sensors => Box::new(SensorsWidget),
+        WidgetType::Cgroups => Box::new(CgroupsWidget),
+        WidgetType::Systemd => Box::new(SystemdWidget),
+    }
+}
+
+/// The full widget set, in the order the renderers used to hard-code
+pub const DEFAULT_WIDGETS: [WidgetType; 8] = [
+    WidgetType::Cpu,
+    WidgetType::Load,
+    WidgetType::Memory,
+    WidgetType::Swap,
+    WidgetType::Disk,
+    WidgetType::Network,
+    WidgetType::Processes,
+    WidgetType::Uptime,
+];
+
+/// Build the structured output from a widget grid, keeping only what its widgets show
+pub fn collect(grid: &WidgetGrid, snapshot: &Snapshot) -> Snapshot {
+    let mut data = Snapshot {
+        timestamp: snapshot.timestamp,
+        ..Default::default()
+    };
+
+    for widget in grid.widgets() {
+        widget.collect(snapshot, &mut data);
+    }
+
+    data
+}
+
+pub(crate) fn to_gb(bytes: u64) -> f64 {
+    bytes as f64 / 1024.0 / 1024.0 / 1024.0
+}
+
+pub(crate) fn to_mb(bytes: u64) -> f64 {
+    bytes as f64 / 1024.0 / 1024.0
+}
+
+/// Short duration, largest two units: `45s`, `12m 05s`, `3d 04h`
+pub(crate) fn format_age(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds {
+        0..60 => format!("{}s", seconds),
+        60..3600 => format!("{}m {:02}s", seconds / 60, seconds % 60),
+        3600..86400 => format!("{}h {:02}m", seconds / 3600, (seconds % 3600) / 60),
+        _ => format!("{}d {:02}h", seconds / 86400, (seconds % 86400) / 3600),
+    }
+}
+
+/// Human readable byte rate, e.g. `1.2 MB/s`
+pub(crate) fn format_rate(bytes_per_sec: f64) -> String {
+    const UNITS: [&str; 4] = ["B/s", "KB/s", "MB/s", "GB/s"];
+    let mut value = bytes_per_sec;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+pub(crate) fn percentage(used: u64, total: u64) -> f64 {
+    if total > 0 {
+        (used as f64 / total as f64) * 100.0
+    } else {
+        0.0
+    }
+}
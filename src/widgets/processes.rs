@@ -0,0 +1,511 @@
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Cell, Row, Table},
+    Frame,
+};
+
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use super::{to_mb, BarItem, TuiContext, Widget};
+use crate::data::process_tree::ProcessNode;
+use crate::data::snapshot::ProcessData;
+use crate::data::{DataSource, History, Snapshot};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortColumn {
+    Cpu,
+    Memory,
+    Pid,
+    Name,
+    User,
+}
+
+impl SortColumn {
+    fn label(self) -> &'static str {
+        match self {
+            SortColumn::Cpu => "CPU%",
+            SortColumn::Memory => "MEM",
+            SortColumn::Pid => "PID",
+            SortColumn::Name => "NAME",
+            SortColumn::User => "USER",
+        }
+    }
+}
+
+/// One line of the process table
+#[derive(Clone)]
+pub struct ProcessRow {
+    pub process: ProcessData,
+    /// Nesting level in tree mode, always 0 in flat mode
+    pub depth: usize,
+    pub has_children: bool,
+    pub collapsed: bool,
+    /// Own usage, or the whole subtree's when collapsed
+    pub cpu_usage: f32,
+    pub memory: u64,
+}
+
+/// What a row is compared on; in tree mode CPU and memory are subtree totals
+struct SortKey<'a> {
+    pid: u32,
+    name: &'a str,
+    user: &'a str,
+    cpu: f32,
+    memory: u64,
+}
+
+/// Everything the rows are built from: the snapshot they came from and the view state
+#[derive(PartialEq)]
+struct RowsKey {
+    timestamp: i64,
+    sort: SortColumn,
+    descending: bool,
+    filter: String,
+    tree: bool,
+    collapsed: HashSet<u32>,
+}
+
+/// Sorting, filtering, tree and cursor state of the interactive process table
+pub struct ProcessView {
+    pub sort: SortColumn,
+    pub descending: bool,
+    pub filter: String,
+    pub cursor: usize,
+    /// The process under the cursor, followed as rows move between refreshes
+    pub selected_pid: Option<u32>,
+    pub tree: bool,
+    pub collapsed: HashSet<u32>,
+    // Rows are asked for several times per key press and frame, build them once
+    rows: RefCell<Option<(RowsKey, Rc<Vec<ProcessRow>>)>>,
+}
+
+impl Default for ProcessView {
+    fn default() -> Self {
+        Self {
+            sort: SortColumn::Cpu,
+            descending: true,
+            filter: String::new(),
+            cursor: 0,
+            selected_pid: None,
+            tree: false,
+            collapsed: HashSet::new(),
+            rows: RefCell::new(None),
+        }
+    }
+}
+
+impl ProcessView {
+    /// Sort by `column`, or flip the order if it is already the sort column
+    pub fn sort_by(&mut self, column: SortColumn) {
+        if self.sort == column {
+            self.descending = !self.descending;
+        } else {
+            self.sort = column;
+            // Biggest consumers first, alphabetical/numeric columns ascending
+            self.descending = matches!(column, SortColumn::Cpu | SortColumn::Memory);
+        }
+    }
+
+    pub fn toggle_tree(&mut self) {
+        self.tree = !self.tree;
+        self.cursor = 0;
+    }
+
+    /// Fold or unfold the subtree under the cursor (tree mode only)
+    pub fn set_collapsed(&mut self, snapshot: &Snapshot, collapsed: bool) {
+        if !self.tree {
+            return;
+        }
+        if let Some(row) = self.selected(snapshot) {
+            if collapsed && row.has_children {
+                self.collapsed.insert(row.process.pid);
+            } else {
+                self.collapsed.remove(&row.process.pid);
+            }
+        }
+    }
+
+    fn compare(&self, a: SortKey, b: SortKey) -> Ordering {
+        let ordering = match self.sort {
+            SortColumn::Cpu => a.cpu.total_cmp(&b.cpu),
+            SortColumn::Memory => a.memory.cmp(&b.memory),
+            SortColumn::Pid => a.pid.cmp(&b.pid),
+            SortColumn::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortColumn::User => a.user.cmp(b.user),
+        };
+        let ordering = if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        // Ties in PID order, so equal rows don't swap places between refreshes
+        ordering.then(a.pid.cmp(&b.pid))
+    }
+
+    /// Name or container ID contains the filter
+    fn matches(&self, process: &ProcessData) -> bool {
+        let filter = self.filter.to_lowercase();
+        filter.is_empty()
+            || process.name.to_lowercase().contains(&filter)
+            || process
+                .container
+                .as_ref()
+                .is_some_and(|container| container.starts_with(&filter))
+    }
+
+    /// Rows in display order, flat or as a tree; rebuilt only when the snapshot or view changed
+    pub fn rows(&self, snapshot: &Snapshot) -> Rc<Vec<ProcessRow>> {
+        let key = RowsKey {
+            timestamp: snapshot.timestamp,
+            sort: self.sort,
+            descending: self.descending,
+            filter: self.filter.clone(),
+            tree: self.tree,
+            collapsed: self.collapsed.clone(),
+        };
+        if let Some((cached, rows)) = &*self.rows.borrow() {
+            if *cached == key {
+                return Rc::clone(rows);
+            }
+        }
+        let rows = Rc::new(self.build_rows(snapshot));
+        *self.rows.borrow_mut() = Some((key, Rc::clone(&rows)));
+        rows
+    }
+
+    /// Drop the cached rows after a new snapshot; two refreshes in the same second share a
+    /// timestamp, so the key alone can't tell them apart
+    pub fn new_snapshot(&self) {
+        self.rows.take();
+    }
+
+    fn build_rows(&self, snapshot: &Snapshot) -> Vec<ProcessRow> {
+        if self.tree {
+            let mut rows = Vec::new();
+            let roots = snapshot
+                .process_tree()
+                .into_iter()
+                .filter_map(|node| self.prune(node))
+                .collect();
+            self.flatten(roots, 0, &mut rows);
+            return rows;
+        }
+
+        let mut processes: Vec<ProcessData> = snapshot
+            .processes
+            .iter()
+            .filter(|process| self.matches(process))
+            .cloned()
+            .collect();
+        processes.sort_by(|a, b| self.compare(sort_key(a), sort_key(b)));
+
+        processes
+            .into_iter()
+            .map(|process| ProcessRow {
+                depth: 0,
+                has_children: false,
+                collapsed: false,
+                cpu_usage: process.cpu_usage,
+                memory: process.memory,
+                process,
+            })
+            .collect()
+    }
+
+    /// Drop subtrees with nothing matching the filter
+    fn prune(&self, mut node: ProcessNode) -> Option<ProcessNode> {
+        node.children = node
+            .children
+            .into_iter()
+            .filter_map(|child| self.prune(child))
+            .collect();
+
+        if node.children.is_empty() && !self.matches(&node.process) {
+            None
+        } else {
+            Some(node)
+        }
+    }
+
+    fn flatten(&self, mut nodes: Vec<ProcessNode>, depth: usize, rows: &mut Vec<ProcessRow>) {
+        nodes.sort_by(|a, b| self.compare(node_sort_key(a), node_sort_key(b)));
+
+        for node in nodes {
+            let has_children = !node.children.is_empty();
+            let collapsed = has_children && self.collapsed.contains(&node.process.pid);
+            let (cpu_usage, memory) = if collapsed {
+                (node.total_cpu, node.total_memory)
+            } else {
+                (node.process.cpu_usage, node.process.memory)
+            };
+
+            rows.push(ProcessRow {
+                process: node.process,
+                depth,
+                has_children,
+                collapsed,
+                cpu_usage,
+                memory,
+            });
+
+            if !collapsed {
+                self.flatten(node.children, depth + 1, rows);
+            }
+        }
+    }
+
+    /// The row under the cursor
+    pub fn selected(&self, snapshot: &Snapshot) -> Option<ProcessRow> {
+        self.rows(snapshot).get(self.cursor).cloned()
+    }
+
+    /// Move the cursor by `delta` rows and follow the process it lands on
+    pub fn move_cursor(&mut self, snapshot: &Snapshot, delta: isize) {
+        let rows = self.rows(snapshot);
+        self.cursor = self
+            .cursor
+            .saturating_add_signed(delta)
+            .min(rows.len().saturating_sub(1));
+        self.selected_pid = rows.get(self.cursor).map(|row| row.process.pid);
+    }
+
+    /// Put the cursor back on the selected process after the rows changed;
+    /// when it exited the cursor stays where it was
+    pub fn follow(&mut self, snapshot: &Snapshot) {
+        let Some(pid) = self.selected_pid else {
+            return;
+        };
+        match self
+            .rows(snapshot)
+            .iter()
+            .position(|row| row.process.pid == pid)
+        {
+            Some(index) => self.cursor = index,
+            None => self.selected_pid = None,
+        }
+    }
+}
+
+fn sort_key(process: &ProcessData) -> SortKey<'_> {
+    SortKey {
+        pid: process.pid,
+        name: &process.name,
+        user: &process.user,
+        cpu: process.cpu_usage,
+        memory: process.memory,
+    }
+}
+
+fn node_sort_key(node: &ProcessNode) -> SortKey<'_> {
+    SortKey {
+        cpu: node.total_cpu,
+        memory: node.total_memory,
+        ..sort_key(&node.process)
+    }
+}
+
+impl Widget for super::ProcessesWidget {
+    fn sources(&self) -> &'static [DataSource] {
+        &[DataSource::Processes]
+    }
+
+    fn render_text(&self, snapshot: &Snapshot, _history: &History) -> Vec<String> {
+        let top_processes = snapshot.top_processes(self.count);
+        if top_processes.is_empty() {
+            return vec![];
+        }
+
+        let mut lines = vec!["Top Processes (by CPU):".to_string()];
+        for process in top_processes {
+            lines.push(format!(
+                "  {:6} {:.1}% {:.1}MB {}",
+                process.pid,
+                process.cpu_usage,
+                to_mb(process.memory),
+                process.name
+            ));
+        }
+        lines
+    }
+
+    fn tui_constraint(&self, _snapshot: &Snapshot) -> Constraint {
+        Constraint::Min(10)
+    }
+
+    fn render_tui(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        snapshot: &Snapshot,
+        _history: &History,
+        ctx: &TuiContext,
+    ) {
+        let view = ctx.process_view;
+        let rows = view.rows(snapshot);
+
+        let mode = if view.tree { "TREE" } else { "PROCESSES" };
+        let mut title = format!(" {} ({}) ", mode, rows.len());
+        if !view.filter.is_empty() {
+            title = format!(" {} ({}) filter: {} ", mode, rows.len(), view.filter);
+        }
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(ctx.border_style());
+
+        let arrow = if view.descending { "▼" } else { "▲" };
+        let header = Row::new(
+            [
+                SortColumn::Pid,
+                SortColumn::User,
+                SortColumn::Cpu,
+                SortColumn::Memory,
+                SortColumn::Name,
+            ]
+            .map(|column| {
+                if column == view.sort {
+                    Cell::from(format!("{}{}", column.label(), arrow))
+                } else {
+                    Cell::from(column.label())
+                }
+            }),
+        )
+        .style(Style::default().fg(Color::Yellow));
+
+        // Borders plus the header row; scroll just enough to keep the cursor visible
+        let visible = area.height.saturating_sub(3).max(1) as usize;
+        let cursor = view.cursor.min(rows.len().saturating_sub(1));
+        let offset = cursor.saturating_sub(visible - 1);
+
+        let rows: Vec<Row> = rows
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(visible)
+            .map(|(index, row)| {
+                let name = if view.tree {
+                    let marker = match (row.has_children, row.collapsed) {
+                        (true, true) => "▸ ",
+                        (true, false) => "▾ ",
+                        (false, _) => "  ",
+                    };
+                    format!("{}{}{}", "  ".repeat(row.depth), marker, row.process.name)
+                } else {
+                    row.process.name.clone()
+                };
+
+                let line = Row::new(vec![
+                    Cell::from(row.process.pid.to_string()),
+                    Cell::from(row.process.user.as_str()),
+                    Cell::from(format!("{:.1}%", row.cpu_usage)),
+                    Cell::from(format!("{:.0}M", to_mb(row.memory))),
+                    Cell::from(name),
+                ]);
+                if index == cursor {
+                    line.style(
+                        Style::default()
+                            .fg(Color::Black)
+                            .bg(Color::Cyan)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    line
+                }
+            })
+            .collect();
+
+        let widths = [
+            Constraint::Length(8),
+            Constraint::Length(10),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Min(20),
+        ];
+
+        let table = Table::new(rows, widths).header(header).block(block);
+
+        frame.render_widget(table, area);
+    }
+
+    fn collect(&self, snapshot: &Snapshot, data: &mut Snapshot) {
+        data.processes = snapshot.top_processes(self.count);
+    }
+
+    fn bar_item(&self, snapshot: &Snapshot) -> Option<BarItem> {
+        let top = snapshot.top_processes(1).into_iter().next()?;
+        Some(BarItem {
+            text: format!("{} {:.0}%", top.name, top.cpu_usage),
+            percentage: Some(top.cpu_usage as f64),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fake;
+
+    fn pids(view: &ProcessView, snapshot: &Snapshot) -> Vec<u32> {
+        view.rows(snapshot)
+            .iter()
+            .map(|row| row.process.pid)
+            .collect()
+    }
+
+    #[test]
+    fn sort_columns() {
+        let mut snapshot = fake::snapshot(0);
+        // Ties with sshd on CPU and with postgres on memory
+        snapshot.processes.insert(
+            0,
+            ProcessData {
+                name: "Zsh".to_string(),
+                pid: 3000,
+                user: "dev".to_string(),
+                cpu_usage: 0.5,
+                memory: 900 * 1024 * 1024,
+                ..ProcessData::default()
+            },
+        );
+        let mut view = ProcessView::default();
+
+        assert_eq!(pids(&view, &snapshot), [2000, 1200, 1210, 400, 3000, 1]);
+        view.sort_by(SortColumn::Memory);
+        assert_eq!(pids(&view, &snapshot), [2000, 1200, 3000, 1210, 1, 400]);
+        // Same column again flips the order, ties stay in PID order
+        view.sort_by(SortColumn::Memory);
+        assert_eq!(pids(&view, &snapshot), [400, 1, 1210, 1200, 3000, 2000]);
+        view.sort_by(SortColumn::Pid);
+        assert_eq!(pids(&view, &snapshot), [1, 400, 1200, 1210, 2000, 3000]);
+        // Names ignore case
+        view.sort_by(SortColumn::Name);
+        assert_eq!(pids(&view, &snapshot), [2000, 1, 1200, 1210, 400, 3000]);
+    }
+
+    #[test]
+    fn rows_are_cached() {
+        let snapshot = fake::snapshot(0);
+        let mut view = ProcessView::default();
+
+        let rows = view.rows(&snapshot);
+        assert!(Rc::ptr_eq(&rows, &view.rows(&snapshot)));
+        // Any change to what they are built from rebuilds them
+        view.filter.push_str("post");
+        let filtered = view.rows(&snapshot);
+        assert_eq!(filtered.len(), 2);
+        view.tree = true;
+        assert!(!Rc::ptr_eq(&filtered, &view.rows(&snapshot)));
+        assert!(!Rc::ptr_eq(
+            &view.rows(&snapshot),
+            &view.rows(&fake::snapshot(1))
+        ));
+
+        let rows = view.rows(&snapshot);
+        view.new_snapshot();
+        assert!(!Rc::ptr_eq(&rows, &view.rows(&snapshot)));
+    }
+}
@@ -1,18 +1,1055 @@
-use crate::data::SystemInfo;
-use crate::widgets::Widget;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Margin, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Gauge, Paragraph},
+    Frame,
+};
+
+use super::{format_rate, percentage, to_gb, to_mb, BarItem, TuiContext, Widget};
+use crate::data::snapshot::{
+    BatteryData, CpuData, DiskData, MemoryData, MemoryDetails, TemperatureData,
+};
+use crate::data::{DataSource, History, Snapshot};
+
+/// Draw a bordered gauge with a line of detail text on top of it
+fn draw_gauge(
+    frame: &mut Frame,
+    area: Rect,
+    title: &str,
+    color: Color,
+    percent: f64,
+    detail: String,
+    ctx: &TuiContext,
+) {
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(ctx.border_style());
+
+    let gauge = Gauge::default()
+        .block(block)
+        .gauge_style(Style::default().fg(ctx.color(color)))
+        .percent(percent.clamp(0.0, 100.0) as u16)
+        .label(format!(" {:.1}%", percent));
+
+    frame.render_widget(gauge, area);
+
+    let text_rect = Rect {
+        x: area.x + 2,
+        y: area.y + 1,
+        width: area.width.saturating_sub(4),
+        height: 1,
+    };
+
+    let paragraph = Paragraph::new(detail).style(Style::default().fg(Color::Gray));
+
+    frame.render_widget(paragraph, text_rect);
+}
+
+fn draw_lines(frame: &mut Frame, area: Rect, title: &str, lines: Vec<Line>, ctx: &TuiContext) {
+    let block = Block::default()
+        .title(format!(" {} ", title))
+        .borders(Borders::ALL)
+        .border_style(ctx.border_style());
+
+    let paragraph = Paragraph::new(lines).block(block).style(Style::default());
+
+    frame.render_widget(paragraph, area);
+}
+
+/// A bar of `width` cells split between `segments` in proportion to `total`, `label` centered on it
+fn stacked_bar(segments: &[(u64, Color)], total: u64, width: u16, label: &str) -> Line<'static> {
+    let width = width as usize;
+    // Cell where each segment ends, rounded so small segments don't all vanish or all show
+    let mut ends = Vec::with_capacity(segments.len());
+    let mut sum = 0;
+    for (bytes, color) in segments {
+        sum += bytes;
+        let end = (sum as f64 / total.max(1) as f64 * width as f64).round() as usize;
+        ends.push((end.min(width), *color));
+    }
+
+    let label: Vec<char> = label.chars().collect();
+    let label_start = width.saturating_sub(label.len()) / 2;
+    let spans: Vec<Span> = (0..width)
+        .map(|cell| {
+            let symbol = cell
+                .checked_sub(label_start)
+                .and_then(|index| label.get(index))
+                .copied()
+                .unwrap_or(' ');
+            let style = match ends.iter().find(|(end, _)| cell < *end) {
+                Some((_, color)) => Style::default().fg(Color::Black).bg(*color),
+                None => Style::default().fg(Color::Gray),
+            };
+            Span::styled(symbol.to_string(), style)
+        })
+        .collect();
+    Line::from(spans)
+}
+
+/// Programs, shared memory, buffers and the rest of the cache, in stacking order
+fn memory_segments(
+    memory: &MemoryData,
+    details: &MemoryDetails,
+    ctx: &TuiContext,
+) -> [(&'static str, u64, Color); 4] {
+    // Shared memory lives in the page cache but can't be dropped like the rest of it
+    let shared = details.shared.min(details.cached);
+    [
+        (
+            "programs",
+            details.program_use(memory.total),
+            ctx.color(Color::Blue),
+        ),
+        ("shared", shared, Color::Magenta),
+        ("buffers", details.buffers, Color::Cyan),
+        ("cache", details.cached - shared, Color::Yellow),
+    ]
+}
+
+/// `1.2G` from a gigabyte up, `340M` below
+fn format_size(bytes: u64) -> String {
+    if bytes >= 1 << 30 {
+        format!("{:.1}G", to_gb(bytes))
+    } else {
+        format!("{:.0}M", to_mb(bytes))
+    }
+}
+
+/// Cores per row in the per-core breakdown
+fn core_columns(count: usize) -> usize {
+    if count > 32 {
+        8
+    } else {
+        4
+    }
+}
+
+/// Status plus time left and draw, e.g. "Discharging, 2h 05m left, 9.8W"
+fn battery_detail(battery: &BatteryData) -> String {
+    let mut detail = battery.status.clone();
+    if let Some(seconds) = battery.time_to_empty {
+        detail.push_str(&format!(", {} left", format_hours(seconds)));
+    }
+    if let Some(seconds) = battery.time_to_full {
+        detail.push_str(&format!(", {} to full", format_hours(seconds)));
+    }
+    if let Some(power) = battery.power_now.filter(|power| *power > 0.0) {
+        detail.push_str(&format!(", {:.1}W", power));
+    }
+    detail
+}
+
+fn format_hours(seconds: u64) -> String {
+    format!("{}h {:02}m", seconds / 3600, (seconds % 3600) / 60)
+}
+
+// Assumed when a sensor reports no max or critical threshold
+const DEFAULT_TEMPERATURE_LIMIT: f64 = 100.0;
+
+/// How close a sensor is to its limit, in percent
+fn temperature_percent(temperature: &TemperatureData) -> f64 {
+    let limit = temperature.limit().unwrap_or(DEFAULT_TEMPERATURE_LIMIT);
+    temperature.celsius / limit * 100.0
+}
+
+fn temperature_color(temperature: &TemperatureData) -> Color {
+    match temperature_percent(temperature) {
+        percent if percent >= 90.0 => Color::Red,
+        percent if percent >= 75.0 => Color::Yellow,
+        _ => Color::Green,
+    }
+}
+
+// Disks listed before the TUI section starts scrolling
+const DISK_ROWS: usize = 4;
+
+/// ", ro, removable" style suffix for the filesystem type
+fn disk_flags(disk: &DiskData) -> String {
+    let mut flags = String::new();
+    if disk.read_only {
+        flags.push_str(", ro");
+    }
+    if disk.removable {
+        flags.push_str(", removable");
+    }
+    flags
+}
+
+fn inode_percentage(disk: &DiskData) -> f64 {
+    percentage(
+        disk.inodes_total.saturating_sub(disk.inodes_free),
+        disk.inodes_total,
+    )
+}
+
+fn cpu_summary(snapshot: &Snapshot) -> String {
+    let cpu = &snapshot.cpu;
+    let mut summary = format!("{} cores", cpu.count);
+    if let Some(physical) = cpu.physical_count {
+        summary.push_str(&format!(", {} physical", physical));
+    }
+    if let Some(limit) = cpu.limit {
+        summary.push_str(&format!(", limited to {} CPUs", limit));
+    }
+    if !cpu.brand.is_empty() {
+        summary.push_str(&format!(", {}", cpu.brand));
+    }
+    summary
+}
 
 impl Widget for super::CpuWidget {
-    fn render(&self, system_info: &SystemInfo) -> String {
-        format!("CPU: {:.1}%", system_info.cpu_usage())
+    fn sources(&self) -> &'static [DataSource] {
+        &[DataSource::Cpu]
+    }
+
+    fn render_text(&self, snapshot: &Snapshot, _history: &History) -> Vec<String> {
+        let cores = &snapshot.cpu.cores;
+        let mut lines = vec![format!(
+            "CPU: {:.1}% ({})",
+            snapshot.cpu.usage,
+            cpu_summary(snapshot)
+        )];
+
+        for row in cores.chunks(core_columns(cores.len())) {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|core| {
+                    format!(
+                        "{:>6} {:5.1}% {:4}MHz",
+                        core.name, core.usage, core.frequency
+                    )
+                })
+                .collect();
+            lines.push(format!("  {}", cells.join("  ")));
+        }
+        lines
+    }
+
+    fn tui_constraint(&self, snapshot: &Snapshot) -> Constraint {
+        let count = snapshot.cpu.count;
+        let rows = count.div_ceil(core_columns(count)) as u16;
+        // Borders, overall gauge and one line per row of cores
+        Constraint::Length(3 + rows)
+    }
+
+    fn render_tui(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        snapshot: &Snapshot,
+        _history: &History,
+        ctx: &TuiContext,
+    ) {
+        let block = Block::default()
+            .title(format!(" CPU - {} ", cpu_summary(snapshot)))
+            .borders(Borders::ALL)
+            .border_style(ctx.border_style());
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let cores = &snapshot.cpu.cores;
+        let columns = core_columns(cores.len());
+        let mut rows = vec![Constraint::Length(1)];
+        rows.extend(cores.chunks(columns).map(|_| Constraint::Length(1)));
+        let lines = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(rows)
+            .split(inner);
+
+        let cpu_usage = snapshot.cpu.usage as f64;
+        let gauge = Gauge::default()
+            .gauge_style(Style::default().fg(ctx.color(Color::Green)))
+            .percent(cpu_usage.clamp(0.0, 100.0) as u16)
+            .label(format!("{:.1}%", cpu_usage));
+        frame.render_widget(gauge, lines[0]);
+
+        for (row, line) in cores.chunks(columns).zip(lines.iter().skip(1)) {
+            let cells = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints((0..columns).map(|_| Constraint::Ratio(1, columns as u32)))
+                .split(*line);
+
+            for (core, cell) in row.iter().zip(cells.iter()) {
+                let bar = Gauge::default()
+                    .gauge_style(Style::default().fg(Color::Cyan).bg(Color::Black))
+                    .percent(core.usage.clamp(0.0, 100.0) as u16)
+                    .label(format!(
+                        "{} {:.0}% {:.1}GHz",
+                        core.name,
+                        core.usage,
+                        core.frequency as f64 / 1000.0
+                    ));
+                frame.render_widget(bar, cell.inner(Margin::new(1, 0)));
+            }
+        }
+    }
+
+    fn collect(&self, snapshot: &Snapshot, data: &mut Snapshot) {
+        data.cpu = CpuData {
+            load_average: data.cpu.load_average.clone(),
+            ..snapshot.cpu.clone()
+        };
+    }
+
+    fn bar_item(&self, snapshot: &Snapshot) -> Option<BarItem> {
+        Some(BarItem {
+            text: format!("CPU {:.0}%", snapshot.cpu.usage),
+            percentage: Some(snapshot.cpu.usage as f64),
+        })
+    }
+}
+
+impl Widget for super::LoadWidget {
+    fn sources(&self) -> &'static [DataSource] {
+        &[DataSource::Clock]
+    }
+
+    fn render_text(&self, snapshot: &Snapshot, _history: &History) -> Vec<String> {
+        let load_avg = &snapshot.cpu.load_average;
+        vec![format!(
+            "Load Average: {:.2}, {:.2}, {:.2}",
+            load_avg.one, load_avg.five, load_avg.fifteen
+        )]
+    }
+
+    fn tui_constraint(&self, _snapshot: &Snapshot) -> Constraint {
+        Constraint::Length(3)
+    }
+
+    fn render_tui(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        snapshot: &Snapshot,
+        _history: &History,
+        ctx: &TuiContext,
+    ) {
+        let load_avg = &snapshot.cpu.load_average;
+        let line = Line::from(vec![
+            Span::styled("1m ", Style::default().fg(Color::Gray)),
+            Span::raw(format!("{:.2}  ", load_avg.one)),
+            Span::styled("5m ", Style::default().fg(Color::Gray)),
+            Span::raw(format!("{:.2}  ", load_avg.five)),
+            Span::styled("15m ", Style::default().fg(Color::Gray)),
+            Span::raw(format!("{:.2}", load_avg.fifteen)),
+        ]);
+
+        draw_lines(frame, area, "LOAD", vec![line], ctx);
+    }
+
+    fn collect(&self, snapshot: &Snapshot, data: &mut Snapshot) {
+        data.cpu.load_average = snapshot.cpu.load_average.clone();
+    }
+
+    fn bar_item(&self, snapshot: &Snapshot) -> Option<BarItem> {
+        Some(BarItem {
+            text: format!("load {:.2}", snapshot.cpu.load_average.one),
+            percentage: None,
+        })
     }
 }
 
 impl Widget for super::MemoryWidget {
-    fn render(&self, system_info: &SystemInfo) -> String {
-        let (used, total) = system_info.memory_usage();
-        let used_gb = used as f64 / 1024.0 / 1024.0 / 1024.0;
-        let total_gb = total as f64 / 1024.0 / 1024.0 / 1024.0;
+    fn sources(&self) -> &'static [DataSource] {
+        &[DataSource::Memory]
+    }
+
+    fn render_text(&self, snapshot: &Snapshot, _history: &History) -> Vec<String> {
+        let memory = &snapshot.memory;
+        let (used, total) = (memory.used, memory.total);
+        let mut lines = vec![format!(
+            "Memory: {:.2}GB / {:.2}GB ({:.1}%)",
+            to_gb(used),
+            to_gb(total),
+            percentage(used, total)
+        )];
+        if let Some(details) = &memory.details {
+            let mut line = format!(
+                "        available {:.2}GB, cache {:.2}GB, buffers {:.2}GB",
+                to_gb(details.available),
+                to_gb(details.cached),
+                to_gb(details.buffers)
+            );
+            if let Some(pressure) = &memory.pressure {
+                line.push_str(&format!(", pressure {:.2}%", pressure.some_avg10));
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    fn tui_constraint(&self, snapshot: &Snapshot) -> Constraint {
+        // Bar, legend and the smaller figures under it
+        match snapshot.memory.details {
+            Some(_) => Constraint::Length(5),
+            None => Constraint::Length(3),
+        }
+    }
+
+    fn render_tui(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        snapshot: &Snapshot,
+        _history: &History,
+        ctx: &TuiContext,
+    ) {
+        let memory = &snapshot.memory;
+        let (used, total) = (memory.used, memory.total);
+        let Some(details) = &memory.details else {
+            draw_gauge(
+                frame,
+                area,
+                "RAM",
+                Color::Blue,
+                percentage(used, total),
+                format!("Used: {:.1}G / {:.1}G", to_gb(used), to_gb(total)),
+                ctx,
+            );
+            return;
+        };
+
+        let segments = memory_segments(memory, details, ctx);
+        let label = format!(
+            "Used: {:.1}G / {:.1}G ({:.1}%)",
+            to_gb(used),
+            to_gb(total),
+            percentage(used, total)
+        );
+        let bar_segments: Vec<(u64, Color)> = segments
+            .iter()
+            .map(|(_, bytes, color)| (*bytes, *color))
+            .collect();
+        let bar = stacked_bar(&bar_segments, total, area.width.saturating_sub(2), &label);
+
+        let mut legend: Vec<Span> = segments
+            .iter()
+            .flat_map(|(name, bytes, color)| {
+                [
+                    Span::styled("■ ", Style::default().fg(*color)),
+                    Span::raw(format!("{} {}  ", name, format_size(*bytes))),
+                ]
+            })
+            .collect();
+        legend.push(Span::styled(
+            format!("available {}", format_size(details.available)),
+            Style::default().fg(Color::Green),
+        ));
+
+        let mut extra = vec![
+            format!("dirty {}", format_size(details.dirty)),
+            format!("writeback {}", format_size(details.writeback)),
+        ];
+        if details.hugepages_total > 0 {
+            extra.push(format!(
+                "hugepages {} / {}",
+                format_size(details.hugepages_total - details.hugepages_free),
+                format_size(details.hugepages_total)
+            ));
+        }
+        if details.zswapped > 0 {
+            extra.push(format!(
+                "zswap {} in {}",
+                format_size(details.zswapped),
+                format_size(details.zswap)
+            ));
+        }
+        if details.zram_original > 0 {
+            extra.push(format!(
+                "zram {} in {}",
+                format_size(details.zram_original),
+                format_size(details.zram_used)
+            ));
+        }
+        if let Some(pressure) = &memory.pressure {
+            extra.push(format!(
+                "pressure {:.2}% / {:.2}% full",
+                pressure.some_avg10, pressure.full_avg10
+            ));
+        }
+        let extra = Line::styled(extra.join("  "), Style::default().fg(Color::Gray));
+
+        draw_lines(
+            frame,
+            area,
+            "RAM",
+            vec![bar, Line::from(legend), extra],
+            ctx,
+        );
+    }
+
+    fn collect(&self, snapshot: &Snapshot, data: &mut Snapshot) {
+        data.memory.used = snapshot.memory.used;
+        data.memory.total = snapshot.memory.total;
+        data.memory.details = snapshot.memory.details;
+        data.memory.pressure = snapshot.memory.pressure;
+    }
+
+    fn bar_item(&self, snapshot: &Snapshot) -> Option<BarItem> {
+        let (used, total) = (snapshot.memory.used, snapshot.memory.total);
+        Some(BarItem {
+            text: format!("RAM {:.1}/{:.1}G", to_gb(used), to_gb(total)),
+            percentage: Some(percentage(used, total)),
+        })
+    }
+}
+
+impl Widget for super::SwapWidget {
+    fn sources(&self) -> &'static [DataSource] {
+        &[DataSource::Memory]
+    }
+
+    fn render_text(&self, snapshot: &Snapshot, _history: &History) -> Vec<String> {
+        let (used, total) = (snapshot.memory.used_swap, snapshot.memory.total_swap);
+        if total == 0 {
+            return vec![];
+        }
+
+        vec![format!(
+            "Swap:   {:.2}GB / {:.2}GB ({:.1}%)",
+            to_gb(used),
+            to_gb(total),
+            percentage(used, total)
+        )]
+    }
+
+    fn tui_constraint(&self, _snapshot: &Snapshot) -> Constraint {
+        Constraint::Length(3)
+    }
+
+    fn render_tui(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        snapshot: &Snapshot,
+        _history: &History,
+        ctx: &TuiContext,
+    ) {
+        let (used, total) = (snapshot.memory.used_swap, snapshot.memory.total_swap);
+        draw_gauge(
+            frame,
+            area,
+            "SWAP",
+            Color::Blue,
+            percentage(used, total),
+            format!("Used: {:.1}G / {:.1}G", to_gb(used), to_gb(total)),
+            ctx,
+        );
+    }
+
+    fn collect(&self, snapshot: &Snapshot, data: &mut Snapshot) {
+        data.memory.used_swap = snapshot.memory.used_swap;
+        data.memory.total_swap = snapshot.memory.total_swap;
+    }
+
+    fn bar_item(&self, snapshot: &Snapshot) -> Option<BarItem> {
+        let (used, total) = (snapshot.memory.used_swap, snapshot.memory.total_swap);
+        if total == 0 {
+            return None;
+        }
+
+        Some(BarItem {
+            text: format!("SWP {:.0}%", percentage(used, total)),
+            percentage: Some(percentage(used, total)),
+        })
+    }
+}
+
+impl Widget for super::DiskWidget {
+    fn sources(&self) -> &'static [DataSource] {
+        &[DataSource::Disks]
+    }
+
+    fn render_text(&self, snapshot: &Snapshot, _history: &History) -> Vec<String> {
+        if snapshot.disks.is_empty() {
+            return vec![];
+        }
+
+        let mut lines = vec!["Disks:".to_string()];
+        for disk in &snapshot.disks {
+            let used = disk.total.saturating_sub(disk.available);
+            lines.push(format!(
+                "  {} ({}, {}{}) {:.1}GB / {:.1}GB ({:.1}%)",
+                disk.name,
+                disk.mount_point,
+                disk.file_system,
+                disk_flags(disk),
+                to_gb(used),
+                to_gb(disk.total),
+                percentage(used, disk.total)
+            ));
+            lines.push(format!(
+                "    read {} write {}, {:.0} IOPS, {:.0}% busy, inodes {:.1}% used",
+                format_rate(disk.read_per_sec),
+                format_rate(disk.written_per_sec),
+                disk.read_iops + disk.write_iops,
+                disk.busy_percent,
+                inode_percentage(disk)
+            ));
+        }
+        lines
+    }
+
+    fn tui_constraint(&self, snapshot: &Snapshot) -> Constraint {
+        // Room for a few disks, the rest scroll
+        let shown = snapshot.disks.len().clamp(1, DISK_ROWS) as u16;
+        Constraint::Length(shown * 2 + 2)
+    }
+
+    fn render_tui(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        snapshot: &Snapshot,
+        _history: &History,
+        ctx: &TuiContext,
+    ) {
+        let disks = &snapshot.disks;
+        let visible = (area.height.saturating_sub(2) / 2) as usize;
+        // Keep the last page full rather than scrolling into empty space
+        let offset = ctx.disk_offset.min(disks.len().saturating_sub(visible));
+
+        let lines = disks
+            .iter()
+            .skip(offset)
+            .take(visible)
+            .flat_map(|disk| {
+                let used = disk.total.saturating_sub(disk.available);
+                [
+                    Line::from(vec![
+                        Span::styled(
+                            format!("{} {:.1}%", disk.mount_point, percentage(used, disk.total)),
+                            Style::default().fg(Color::Cyan),
+                        ),
+                        Span::raw(" "),
+                        Span::styled(
+                            format!(
+                                "{:.1}G / {:.1}G  {}{}",
+                                to_gb(used),
+                                to_gb(disk.total),
+                                disk.file_system,
+                                disk_flags(disk)
+                            ),
+                            Style::default().fg(Color::Gray),
+                        ),
+                    ]),
+                    Line::from(vec![
+                        Span::raw(format!(
+                            "  R {} W {} ",
+                            format_rate(disk.read_per_sec),
+                            format_rate(disk.written_per_sec)
+                        )),
+                        Span::styled(
+                            format!(
+                                "{:.0} IOPS {:.0}%  inodes {:.0}%",
+                                disk.read_iops + disk.write_iops,
+                                disk.busy_percent,
+                                inode_percentage(disk)
+                            ),
+                            Style::default().fg(Color::Gray),
+                        ),
+                    ]),
+                ]
+            })
+            .collect();
+
+        let title = if disks.len() > visible {
+            format!(
+                "DISKS {}-{}/{}",
+                offset + 1,
+                (offset + visible).min(disks.len()),
+                disks.len()
+            )
+        } else {
+            "DISKS".to_string()
+        };
+        draw_lines(frame, area, &title, lines, ctx);
+    }
+
+    fn collect(&self, snapshot: &Snapshot, data: &mut Snapshot) {
+        data.disks = snapshot.disks.clone();
+    }
+
+    fn bar_item(&self, snapshot: &Snapshot) -> Option<BarItem> {
+        if snapshot.disks.is_empty() {
+            return None;
+        }
+
+        let usage: Vec<(&str, f64)> = snapshot
+            .disks
+            .iter()
+            .map(|disk| {
+                let used = disk.total.saturating_sub(disk.available);
+                (disk.mount_point.as_str(), percentage(used, disk.total))
+            })
+            .collect();
+        Some(BarItem {
+            text: usage
+                .iter()
+                .map(|(mount_point, percent)| format!("{} {:.0}%", mount_point, percent))
+                .collect::<Vec<_>>()
+                .join(" "),
+            // The fullest disk is the one worth a warning color
+            percentage: usage.iter().map(|(_, percent)| *percent).reduce(f64::max),
+        })
+    }
+}
+
+impl Widget for super::NetworkWidget {
+    fn sources(&self) -> &'static [DataSource] {
+        &[DataSource::Networks]
+    }
+
+    fn render_text(&self, snapshot: &Snapshot, _history: &History) -> Vec<String> {
+        if snapshot.network.is_empty() {
+            return vec![];
+        }
+
+        let mut lines = vec!["Network Interfaces:".to_string()];
+        for network in &snapshot.network {
+            let mut line = format!(
+                "  {}: ↓ {} ↑ {} (total ↓ {:.2}MB ↑ {:.2}MB)",
+                network.interface,
+                format_rate(network.received_per_sec),
+                format_rate(network.transmitted_per_sec),
+                to_mb(network.total_received),
+                to_mb(network.total_transmitted)
+            );
+            if network.errors_received + network.errors_transmitted > 0 {
+                line.push_str(&format!(
+                    " errors {}/{}",
+                    network.errors_received, network.errors_transmitted
+                ));
+            }
+            if network.dropped_received + network.dropped_transmitted > 0 {
+                line.push_str(&format!(
+                    " dropped {}/{}",
+                    network.dropped_received, network.dropped_transmitted
+                ));
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    fn tui_constraint(&self, _snapshot: &Snapshot) -> Constraint {
+        Constraint::Length(4)
+    }
+
+    fn render_tui(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        snapshot: &Snapshot,
+        _history: &History,
+        ctx: &TuiContext,
+    ) {
+        let visible = area.height.saturating_sub(2) as usize;
+        let lines = snapshot
+            .network
+            .iter()
+            .take(visible)
+            .map(|network| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{} ", network.interface),
+                        Style::default().fg(Color::Magenta),
+                    ),
+                    Span::styled("↓", Style::default().fg(Color::Blue)),
+                    Span::raw(format!("{} ", format_rate(network.received_per_sec))),
+                    Span::styled("↑", Style::default().fg(Color::Green)),
+                    Span::raw(format!("{} ", format_rate(network.transmitted_per_sec))),
+                    Span::styled(
+                        format!(
+                            "({:.0}M/{:.0}M)",
+                            to_mb(network.total_received),
+                            to_mb(network.total_transmitted)
+                        ),
+                        Style::default().fg(Color::Gray),
+                    ),
+                ])
+            })
+            .collect();
+
+        draw_lines(frame, area, "NETWORK", lines, ctx);
+    }
+
+    fn collect(&self, snapshot: &Snapshot, data: &mut Snapshot) {
+        data.network = snapshot.network.clone();
+    }
+
+    fn bar_item(&self, snapshot: &Snapshot) -> Option<BarItem> {
+        // Loopback traffic never leaves the machine
+        let (received, transmitted) = snapshot
+            .network
+            .iter()
+            .filter(|network| network.interface != "lo")
+            .fold((0.0, 0.0), |(received, transmitted), network| {
+                (
+                    received + network.received_per_sec,
+                    transmitted + network.transmitted_per_sec,
+                )
+            });
+        Some(BarItem {
+            text: format!("↓{} ↑{}", format_rate(received), format_rate(transmitted)),
+            percentage: None,
+        })
+    }
+}
+
+impl Widget for super::UptimeWidget {
+    fn sources(&self) -> &'static [DataSource] {
+        &[DataSource::Clock]
+    }
+
+    fn render_text(&self, snapshot: &Snapshot, _history: &History) -> Vec<String> {
+        let uptime = snapshot.system.uptime;
+        vec![format!(
+            "Uptime: {} hours, {} minutes",
+            uptime / 3600,
+            (uptime % 3600) / 60
+        )]
+    }
+
+    fn tui_constraint(&self, _snapshot: &Snapshot) -> Constraint {
+        Constraint::Length(3)
+    }
+
+    fn render_tui(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        snapshot: &Snapshot,
+        _history: &History,
+        ctx: &TuiContext,
+    ) {
+        let uptime = snapshot.system.uptime;
+        let line = Line::from(format!(
+            "{}d {}h {}m",
+            uptime / 86400,
+            (uptime % 86400) / 3600,
+            (uptime % 3600) / 60
+        ));
+
+        draw_lines(frame, area, "UPTIME", vec![line], ctx);
+    }
+
+    fn collect(&self, snapshot: &Snapshot, data: &mut Snapshot) {
+        data.system = snapshot.system.clone();
+    }
+
+    fn bar_item(&self, snapshot: &Snapshot) -> Option<BarItem> {
+        let uptime = snapshot.system.uptime;
+        Some(BarItem {
+            text: format!(
+                "up {}d {}h {}m",
+                uptime / 86400,
+                (uptime % 86400) / 3600,
+                (uptime % 3600) / 60
+            ),
+            percentage: None,
+        })
+    }
+}
+
+impl Widget for super::BatteryWidget {
+    fn sources(&self) -> &'static [DataSource] {
+        &[DataSource::Power]
+    }
+
+    fn render_text(&self, snapshot: &Snapshot, _history: &History) -> Vec<String> {
+        let power = &snapshot.power;
+        if power.batteries.is_empty() {
+            return vec![];
+        }
+
+        let mut lines: Vec<String> = power
+            .batteries
+            .iter()
+            .map(|battery| {
+                format!(
+                    "Battery {}: {:.0}% {}",
+                    battery.name,
+                    battery.capacity.unwrap_or(0.0),
+                    battery_detail(battery)
+                )
+            })
+            .collect();
+        if let Some(online) = power.ac_online {
+            lines.push(format!(
+                "AC:     {}",
+                if online { "online" } else { "offline" }
+            ));
+        }
+        lines
+    }
+
+    fn tui_constraint(&self, snapshot: &Snapshot) -> Constraint {
+        Constraint::Length(3 * snapshot.power.batteries.len().max(1) as u16)
+    }
+
+    fn render_tui(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        snapshot: &Snapshot,
+        _history: &History,
+        ctx: &TuiContext,
+    ) {
+        let batteries = &snapshot.power.batteries;
+        if batteries.is_empty() {
+            let line = Line::styled("No battery", Style::default().fg(Color::Gray));
+            draw_lines(frame, area, "BATTERY", vec![line], ctx);
+            return;
+        }
+
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Length(3); batteries.len()])
+            .split(area);
+
+        for (battery, row) in batteries.iter().zip(rows.iter()) {
+            let capacity = battery.capacity.unwrap_or(0.0);
+            // Low and not on its way back up
+            let color = if capacity < 20.0 && battery.status != "Charging" {
+                Color::Red
+            } else {
+                Color::Green
+            };
+            let title = match snapshot.power.ac_online {
+                Some(true) => format!("{} (AC)", battery.name),
+                _ => battery.name.clone(),
+            };
+            draw_gauge(
+                frame,
+                *row,
+                &title,
+                color,
+                capacity,
+                battery_detail(battery),
+                ctx,
+            );
+        }
+    }
+
+    fn bar_item(&self, snapshot: &Snapshot) -> Option<BarItem> {
+        let battery = snapshot.power.batteries.first()?;
+        let capacity = battery.capacity.unwrap_or(0.0);
+        let mut text = format!("BAT {:.0}%", capacity);
+        match battery.status.as_str() {
+            "Charging" => text.push('+'),
+            "Discharging" => {
+                if let Some(seconds) = battery.time_to_empty {
+                    text.push_str(&format!(" {}", format_hours(seconds)));
+                }
+            }
+            _ => {}
+        }
+        Some(BarItem {
+            text,
+            percentage: Some(capacity),
+        })
+    }
+
+    fn collect(&self, snapshot: &Snapshot, data: &mut Snapshot) {
+        data.power = snapshot.power.clone();
+    }
+}
+
+impl Widget for super::SensorsWidget {
+    fn sources(&self) -> &'static [DataSource] {
+        &[DataSource::Sensors]
+    }
+
+    fn render_text(&self, snapshot: &Snapshot, _history: &History) -> Vec<String> {
+        let sensors = &snapshot.sensors;
+        let temperatures = sensors.temperatures.iter().map(|temperature| {
+            let mut line = format!(
+                "Temp {} {}: {:.1}°C",
+                temperature.chip, temperature.label, temperature.celsius
+            );
+            if let Some(critical) = temperature.critical {
+                line.push_str(&format!(" (crit {:.0}°C)", critical));
+            } else if let Some(max) = temperature.max {
+                line.push_str(&format!(" (max {:.0}°C)", max));
+            }
+            line
+        });
+        let fans = sensors
+            .fans
+            .iter()
+            .map(|fan| format!("Fan {} {}: {} RPM", fan.chip, fan.label, fan.rpm));
+        temperatures.chain(fans).collect()
+    }
+
+    fn tui_constraint(&self, snapshot: &Snapshot) -> Constraint {
+        let sensors = &snapshot.sensors;
+        let lines = sensors.temperatures.len() + sensors.fans.len();
+        Constraint::Length(lines.max(1) as u16 + 2)
+    }
+
+    fn render_tui(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        snapshot: &Snapshot,
+        _history: &History,
+        ctx: &TuiContext,
+    ) {
+        let sensors = &snapshot.sensors;
+        let mut lines: Vec<Line> = sensors
+            .temperatures
+            .iter()
+            .map(|temperature| {
+                let limit = temperature
+                    .limit()
+                    .map(|limit| format!(" / {:.0}°C", limit))
+                    .unwrap_or_default();
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:<10} {:<14} ", temperature.chip, temperature.label),
+                        Style::default().fg(Color::Gray),
+                    ),
+                    Span::styled(
+                        format!("{:>5.1}°C", temperature.celsius),
+                        Style::default().fg(temperature_color(temperature)),
+                    ),
+                    Span::styled(limit, Style::default().fg(Color::DarkGray)),
+                ])
+            })
+            .collect();
+        lines.extend(sensors.fans.iter().map(|fan| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<10} {:<14} ", fan.chip, fan.label),
+                    Style::default().fg(Color::Gray),
+                ),
+                Span::raw(format!("{} RPM", fan.rpm)),
+            ])
+        }));
+        if lines.is_empty() {
+            lines.push(Line::styled("No sensors", Style::default().fg(Color::Gray)));
+        }
+
+        draw_lines(frame, area, "SENSORS", lines, ctx);
+    }
+
+    fn bar_item(&self, snapshot: &Snapshot) -> Option<BarItem> {
+        let hottest = snapshot
+            .sensors
+            .temperatures
+            .iter()
+            .max_by(|a, b| temperature_percent(a).total_cmp(&temperature_percent(b)))?;
+        Some(BarItem {
+            text: format!("{:.0}°C", hottest.celsius),
+            percentage: Some(temperature_percent(hottest)),
+        })
+    }
 
-        format!("Memory: {:.2}GB / {:.2}GB", used_gb, total_gb)
+    fn collect(&self, snapshot: &Snapshot, data: &mut Snapshot) {
+        data.sensors = snapshot.sensors.clone();
     }
 }
@@ -0,0 +1,160 @@
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Cell, Row, Table},
+    Frame,
+};
+use std::time::Duration;
+
+use super::{format_age, to_mb, BarItem, TuiContext, Widget};
+use crate::data::snapshot::UnitData;
+use crate::data::{DataSource, History, Snapshot};
+
+fn state_color(unit: &UnitData) -> Color {
+    match unit.state.as_str() {
+        "active" => Color::Green,
+        "failed" => Color::Red,
+        "activating" | "deactivating" | "reloading" => Color::Yellow,
+        _ => Color::Gray,
+    }
+}
+
+/// `active (running)`, or just the state when there is no sub state
+fn state_text(unit: &UnitData) -> String {
+    if unit.sub_state.is_empty() || unit.sub_state == unit.state {
+        unit.state.clone()
+    } else {
+        format!("{} ({})", unit.state, unit.sub_state)
+    }
+}
+
+/// Time in the current state, as of the snapshot
+fn since_text(unit: &UnitData, snapshot: &Snapshot) -> Option<String> {
+    let seconds = (snapshot.timestamp - unit.since?).max(0) as u64;
+    Some(format_age(Duration::from_secs(seconds)))
+}
+
+/// CPU and memory of the main process, e.g. `0.5% 12M`
+fn usage_text(unit: &UnitData) -> Option<String> {
+    Some(format!(
+        "{:.1}% {:.0}M",
+        unit.cpu_usage?,
+        to_mb(unit.memory.unwrap_or(0))
+    ))
+}
+
+impl Widget for super::SystemdWidget {
+    fn sources(&self) -> &'static [DataSource] {
+        &[DataSource::Units, DataSource::Processes]
+    }
+
+    fn render_text(&self, snapshot: &Snapshot, _history: &History) -> Vec<String> {
+        snapshot
+            .units
+            .iter()
+            .map(|unit| {
+                let mut line = format!("Unit {}: {}", unit.name, state_text(unit));
+                if let Some(since) = since_text(unit, snapshot) {
+                    line.push_str(&format!(" for {}", since));
+                }
+                if let (Some(pid), Some(usage)) = (unit.main_pid, usage_text(unit)) {
+                    line.push_str(&format!(", PID {} {}", pid, usage));
+                }
+                line
+            })
+            .collect()
+    }
+
+    fn tui_constraint(&self, snapshot: &Snapshot) -> Constraint {
+        Constraint::Length(snapshot.units.len().max(1) as u16 + 3)
+    }
+
+    fn render_tui(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        snapshot: &Snapshot,
+        _history: &History,
+        ctx: &TuiContext,
+    ) {
+        let units = &snapshot.units;
+        let failed = units.iter().filter(|unit| unit.is_failed()).count();
+        let title = if failed > 0 {
+            format!(" SYSTEMD ({} failed) ", failed)
+        } else {
+            format!(" SYSTEMD ({}) ", units.len())
+        };
+        // Failed units outrank the focus highlight
+        let border_style = if failed > 0 {
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        } else {
+            ctx.border_style()
+        };
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(border_style);
+
+        let header = Row::new(["UNIT", "STATE", "SINCE", "PID", "CPU%", "MEM"])
+            .style(Style::default().fg(Color::Yellow));
+        let rows: Vec<Row> = units
+            .iter()
+            .map(|unit| {
+                let row = Row::new(vec![
+                    Cell::from(unit.name.as_str()),
+                    Cell::from(state_text(unit)).style(Style::default().fg(state_color(unit))),
+                    Cell::from(since_text(unit, snapshot).unwrap_or_default()),
+                    Cell::from(unit.main_pid.map(|pid| pid.to_string()).unwrap_or_default()),
+                    Cell::from(
+                        unit.cpu_usage
+                            .map(|usage| format!("{:.1}%", usage))
+                            .unwrap_or_default(),
+                    ),
+                    Cell::from(
+                        unit.memory
+                            .map(|memory| format!("{:.0}M", to_mb(memory)))
+                            .unwrap_or_default(),
+                    ),
+                ]);
+                if unit.is_failed() {
+                    row.style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+                } else {
+                    row
+                }
+            })
+            .collect();
+
+        let widths = [
+            Constraint::Min(20),
+            Constraint::Length(22),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(7),
+            Constraint::Length(7),
+        ];
+        let table = Table::new(rows, widths).header(header).block(block);
+
+        frame.render_widget(table, area);
+    }
+
+    fn bar_item(&self, snapshot: &Snapshot) -> Option<BarItem> {
+        let units = &snapshot.units;
+        if units.is_empty() {
+            return None;
+        }
+        let active = units.iter().filter(|unit| unit.state == "active").count();
+        let failed = units.iter().filter(|unit| unit.is_failed()).count();
+        Some(BarItem {
+            text: if failed > 0 {
+                format!("UNITS {} failed", failed)
+            } else {
+                format!("UNITS {}/{}", active, units.len())
+            },
+            percentage: Some(active as f64 / units.len() as f64 * 100.0),
+        })
+    }
+
+    fn collect(&self, snapshot: &Snapshot, data: &mut Snapshot) {
+        data.units = snapshot.units.clone();
+    }
+}